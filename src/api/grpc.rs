@@ -13,12 +13,14 @@ use crate::models::{
     CreateRelationRequest as ModelCreateRelationRequest,
     CreateTagRequest as ModelCreateTagRequest,
     CreateTransformRequest as ModelCreateTransformRequest,
-    Address, TagCategory,
+    CreateVoteRequest as ModelCreateVoteRequest,
+    UpdateTrustRequest as ModelUpdateTrustRequest,
+    Address, TagCategory, TrustScope, TrustUpdate, VoteKind,
 };
 use crate::proto::hub_service_server::HubService;
 use crate::proto::{self as pb};
 use crate::services::{
-    EntityService, TrustService, TrustConfig,
+    EntityService, TrustService, TrustConfig, TrustQueryOptions, ValidityService, ValidityReport, ValidityTrigger,
     DiscoveryService, DiscoveryConfig, FederatedSearchService,
 };
 use crate::config::HubRole;
@@ -28,15 +30,21 @@ use crate::store::EntityStore;
 pub struct HubServiceImpl {
     service: Arc<EntityService>,
     trust_service: Arc<TrustService>,
+    validity_service: Arc<ValidityService>,
     federated_search_service: Arc<FederatedSearchService>,
     #[allow(dead_code)]
     hub_id: String,
 }
 
 impl HubServiceImpl {
-    /// Create a new gRPC service implementation
-    pub fn new(service: Arc<EntityService>, store: Arc<EntityStore>) -> Self {
-        let trust_service = Arc::new(TrustService::new(Arc::clone(&store), TrustConfig::default()));
+    /// Create a new gRPC service implementation.
+    ///
+    /// Fails if the hub registry in the store cannot be restored.
+    pub fn new(service: Arc<EntityService>, store: Arc<EntityStore>, trust_config: TrustConfig) -> models::HubResult<Self> {
+        let trust_service = Arc::new(TrustService::new(Arc::clone(&store), trust_config));
+        let validity_service = Arc::new(
+            ValidityService::new().with_store(Arc::clone(&store), Arc::clone(&trust_service)),
+        );
 
         // Create a minimal discovery config for the federated search service
         let discovery_config = DiscoveryConfig {
@@ -45,25 +53,27 @@ impl HubServiceImpl {
             public_url: "http://localhost:8080".to_string(),
             ..Default::default()
         };
-        let discovery_service = Arc::new(DiscoveryService::new(discovery_config, Arc::clone(&store)));
+        let discovery_service = Arc::new(DiscoveryService::new(discovery_config, Arc::clone(&store))?);
 
         let federated_search_service = Arc::new(FederatedSearchService::new(
             Arc::clone(&service),
             Arc::clone(&discovery_service),
         ));
 
-        Self {
+        Ok(Self {
             service,
             trust_service,
+            validity_service,
             federated_search_service,
             hub_id: "grpc-hub".to_string(),
-        }
+        })
     }
 
     /// Create with a custom discovery service
     pub fn with_discovery(
         service: Arc<EntityService>,
         trust_service: Arc<TrustService>,
+        validity_service: Arc<ValidityService>,
         discovery_service: Arc<DiscoveryService>,
     ) -> Self {
         let hub_id = discovery_service.hub_id().to_string();
@@ -75,6 +85,7 @@ impl HubServiceImpl {
         Self {
             service,
             trust_service,
+            validity_service,
             federated_search_service,
             hub_id,
         }
@@ -97,11 +108,15 @@ impl From<models::Agent> for pb::Agent {
                 trusts: agent.trust.trusts.into_iter().map(|t| pb::Trust {
                     agent_address: t.agent.to_string(),
                     trust_level: t.trust,
+                    scope: t.scope.map(|s| s.to_string()).unwrap_or_default(),
+                    asserted_at: t.asserted_at.map(datetime_to_timestamp),
                 }).collect(),
             }),
             signature: agent.signature,
+            trust_signature: agent.trust_signature.unwrap_or_default(),
             created_at: Some(datetime_to_timestamp(agent.created_at)),
             updated_at: Some(datetime_to_timestamp(agent.updated_at)),
+            recovery_key: agent.recovery_key.unwrap_or_default(),
         }
     }
 }
@@ -120,6 +135,37 @@ impl From<models::Fragment> for pb::Fragment {
             signature: fragment.signature,
             created_at: Some(datetime_to_timestamp(fragment.created_at)),
             updated_at: Some(datetime_to_timestamp(fragment.updated_at)),
+            votes: Some(fragment.votes.into()),
+        }
+    }
+}
+
+impl From<models::VoteSummary> for pb::VoteSummary {
+    fn from(summary: models::VoteSummary) -> Self {
+        pb::VoteSummary {
+            votes_count: summary.votes_count as i32,
+            verifications: summary.verifications as i32,
+            contestations: summary.contestations as i32,
+            verification_weight: summary.verification_weight,
+            contestation_weight: summary.contestation_weight,
+        }
+    }
+}
+
+impl From<models::Vote> for pb::Vote {
+    fn from(vote: models::Vote) -> Self {
+        pb::Vote {
+            uuid: vote.uuid,
+            fragment_address: vote.fragment.to_string(),
+            voter_address: vote.voter.to_string(),
+            kind: vote.kind.to_string(),
+            weight: vote.weight,
+            comment: vote.comment,
+            version: vote.version as i32,
+            signature: vote.signature,
+            when: Some(datetime_to_timestamp(vote.when)),
+            created_at: Some(datetime_to_timestamp(vote.created_at)),
+            updated_at: Some(datetime_to_timestamp(vote.updated_at)),
         }
     }
 }
@@ -180,6 +226,7 @@ impl From<models::TrustPath> for pb::TrustPath {
             hops: path.hops.into_iter().map(|h| pb::TrustPathHop {
                 agent_address: h.agent.to_string(),
                 trust_level: h.trust_level,
+                hub: h.hub.unwrap_or_default(),
             }).collect(),
             effective_trust: path.effective_trust,
             depth: path.depth as i32,
@@ -195,6 +242,61 @@ impl From<models::TrustScore> for pb::TrustScore {
             score: score.score,
             path_count: score.path_count as i32,
             best_path: score.best_path.map(Into::into),
+            explanation: score.explanation.map(Into::into),
+        }
+    }
+}
+
+impl From<ValidityReport> for pb::ValidityReport {
+    fn from(report: ValidityReport) -> Self {
+        pb::ValidityReport {
+            validity: report.validity.to_string(),
+            issues: report.issues.into_iter().map(|i| pb::ValidityIssue {
+                fragment_uuid: i.fragment_id,
+                issue_type: i.issue_type.to_string(),
+                description: i.description,
+                severity: i.severity,
+            }).collect(),
+            confidence_weighted_score: report.confidence_weighted_score,
+            fragments_analyzed: report.fragments_analyzed as i32,
+            relations_analyzed: report.relations_analyzed as i32,
+        }
+    }
+}
+
+impl From<models::TrustExplanation> for pb::TrustExplanation {
+    fn from(explanation: models::TrustExplanation) -> Self {
+        pb::TrustExplanation {
+            formula: explanation.formula,
+            target_address: explanation.target.to_string(),
+            damping_factor: explanation.damping_factor,
+            min_trust_threshold: explanation.min_trust_threshold,
+            max_depth: explanation.max_depth as i32,
+            paths: explanation.paths.into_iter().map(|p| pb::ExplainedPath {
+                hops: p.hops.into_iter().map(|h| pb::ExplainedHop {
+                    agent_address: h.agent.to_string(),
+                    hub: h.hub.unwrap_or_default(),
+                    trust_level: h.trust_level,
+                    damping: h.damping,
+                    sybil_factor: h.sybil_factor,
+                    cumulative_trust: h.cumulative_trust,
+                }).collect(),
+                effective_trust: p.effective_trust,
+                endorsement: p.endorsement.unwrap_or_default(),
+            }).collect(),
+            endorsements: explanation.endorsements.into_iter().map(|e| pb::ExplainedEndorsement {
+                relation_uuid: e.relation,
+                endorser_address: e.endorser.to_string(),
+                confidence: e.confidence,
+                endorser_trust: e.endorser_trust,
+                contribution: e.contribution,
+            }).collect(),
+            cut_edges: explanation.cut_edges.into_iter().map(|e| pb::CutTrustEdge {
+                from_address: e.from.to_string(),
+                to_address: e.to.to_string(),
+                trust_level: e.trust_level,
+                cumulative_trust: e.cumulative_trust,
+            }).collect(),
         }
     }
 }
@@ -210,6 +312,32 @@ fn pb_to_create_agent(req: pb::CreateAgentRequest) -> Result<ModelCreateAgentReq
         description: if req.description.is_empty() { None } else { Some(req.description) },
         trust: None,
         primary_hub: if req.primary_hub.is_empty() { None } else { Some(req.primary_hub) },
+        recovery_key: if req.recovery_key.is_empty() { None } else { Some(req.recovery_key) },
+        signature: req.signature,
+    })
+}
+
+fn pb_to_update_trust(req: pb::UpdateTrustRequest) -> Result<ModelUpdateTrustRequest, Status> {
+    let updates = req.updates
+        .into_iter()
+        .map(|u| {
+            let agent = Address::parse(&u.agent_address)
+                .ok_or_else(|| Status::invalid_argument(format!("Invalid agent address: {}", u.agent_address)))?;
+            Ok(TrustUpdate {
+                agent,
+                trust: if u.remove { None } else { Some(u.trust_level) },
+                scope: pb_trust_scope(&u.scope)?,
+                asserted_at: u.asserted_at.map(timestamp_to_datetime).transpose()?,
+            })
+        })
+        .collect::<Result<Vec<_>, Status>>()?;
+
+    let version = u32::try_from(req.version)
+        .map_err(|_| Status::invalid_argument(format!("Invalid version: {}", req.version)))?;
+
+    Ok(ModelUpdateTrustRequest {
+        updates,
+        version,
         signature: req.signature,
     })
 }
@@ -269,6 +397,26 @@ fn pb_to_create_relation(req: pb::CreateRelationRequest) -> Result<ModelCreateRe
     })
 }
 
+fn pb_to_create_vote(req: pb::CastVoteRequest) -> Result<ModelCreateVoteRequest, Status> {
+    let fragment = Address::parse(&req.fragment_address)
+        .ok_or_else(|| Status::invalid_argument(format!("Invalid fragment address: {}", req.fragment_address)))?;
+    let voter = Address::parse(&req.voter_address)
+        .ok_or_else(|| Status::invalid_argument(format!("Invalid voter address: {}", req.voter_address)))?;
+    let kind: VoteKind = req.kind.parse()
+        .map_err(|_| Status::invalid_argument(format!("Invalid vote kind: {}", req.kind)))?;
+
+    Ok(ModelCreateVoteRequest {
+        uuid: if req.uuid.is_empty() { None } else { Some(req.uuid) },
+        fragment,
+        voter,
+        kind,
+        weight: req.weight,
+        comment: if req.comment.is_empty() { None } else { Some(req.comment) },
+        when: req.when.map(timestamp_to_datetime).transpose()?,
+        signature: req.signature,
+    })
+}
+
 fn pb_to_create_tag(req: pb::CreateTagRequest) -> Result<ModelCreateTagRequest, Status> {
     let creator = Address::parse(&req.created_by)
         .ok_or_else(|| Status::invalid_argument(format!("Invalid creator address: {}", req.created_by)))?;
@@ -312,6 +460,32 @@ fn pb_to_create_transform(req: pb::CreateTransformRequest) -> Result<ModelCreate
 // Helper Functions
 // ============================================================================
 
+fn pb_trust_scope(scope: &str) -> Result<Option<TrustScope>, Status> {
+    if scope.is_empty() {
+        return Ok(None);
+    }
+    scope.parse().map(Some).map_err(Status::invalid_argument)
+}
+
+fn pb_trust_options(max_depth: i32, damping_factor: f32, scopes: &[String]) -> Result<TrustQueryOptions, Status> {
+    let max_depth = match max_depth {
+        0 => None,
+        d => Some(u8::try_from(d).map_err(|_| Status::invalid_argument(format!("Invalid max_depth: {}", d)))?),
+    };
+    let damping_factor = if damping_factor == 0.0 { None } else { Some(damping_factor) };
+    let scope = scopes
+        .iter()
+        .filter_map(|s| pb_trust_scope(s).transpose())
+        .collect::<Result<Vec<_>, Status>>()?;
+
+    Ok(TrustQueryOptions { max_depth, damping_factor, scope, ..Default::default() })
+}
+
+fn timestamp_to_datetime(ts: prost_types::Timestamp) -> Result<chrono::DateTime<chrono::Utc>, Status> {
+    chrono::DateTime::from_timestamp(ts.seconds, ts.nanos.max(0) as u32)
+        .ok_or_else(|| Status::invalid_argument(format!("Invalid timestamp: {}", ts)))
+}
+
 fn datetime_to_timestamp(dt: chrono::DateTime<chrono::Utc>) -> prost_types::Timestamp {
     prost_types::Timestamp {
         seconds: dt.timestamp(),
@@ -410,6 +584,21 @@ impl HubService for HubServiceImpl {
         }))
     }
 
+    async fn update_agent_trust(
+        &self,
+        request: Request<pb::UpdateTrustRequest>,
+    ) -> Result<Response<pb::Agent>, Status> {
+        let req = request.into_inner();
+        let uuid = req.agent_uuid.clone();
+        let model_req = pb_to_update_trust(req)?;
+
+        let agent = self.service
+            .update_agent_trust(&uuid, model_req)
+            .map_err(hub_error_to_status)?;
+
+        Ok(Response::new(agent.into()))
+    }
+
     // ========================================================================
     // Fragments
     // ========================================================================
@@ -423,6 +612,9 @@ impl HubService for HubServiceImpl {
         let fragment = self.service
             .create_fragment(model_req)
             .map_err(hub_error_to_status)?;
+        self.validity_service.propagate_in_background(ValidityTrigger::FragmentCreated {
+            fragment: fragment.uuid.clone(),
+        });
 
         Ok(Response::new(fragment.into()))
     }
@@ -440,6 +632,25 @@ impl HubService for HubServiceImpl {
         Ok(Response::new(fragment.into()))
     }
 
+    async fn get_fragment_validity(
+        &self,
+        request: Request<pb::FragmentValidityRequest>,
+    ) -> Result<Response<pb::ValidityReport>, Status> {
+        let req = request.into_inner();
+        let viewer = if req.viewer_address.is_empty() {
+            None
+        } else {
+            Some(Address::parse(&req.viewer_address)
+                .ok_or_else(|| Status::invalid_argument(format!("Invalid viewer address: {}", req.viewer_address)))?)
+        };
+
+        let report = self.validity_service
+            .analyze_fragment(&req.uuid, viewer.as_ref())
+            .map_err(hub_error_to_status)?;
+
+        Ok(Response::new(report.into()))
+    }
+
     type SearchFragmentsStream = FragmentStream;
 
     async fn search_fragments(
@@ -473,6 +684,7 @@ impl HubService for HubServiceImpl {
         let relation = self.service
             .create_relation(model_req)
             .map_err(hub_error_to_status)?;
+        self.validity_service.propagate_in_background(ValidityTrigger::relation_created(&relation));
 
         Ok(Response::new(relation.into()))
     }
@@ -490,6 +702,36 @@ impl HubService for HubServiceImpl {
         Ok(Response::new(relation.into()))
     }
 
+    // ========================================================================
+    // Votes
+    // ========================================================================
+
+    async fn cast_vote(
+        &self,
+        request: Request<pb::CastVoteRequest>,
+    ) -> Result<Response<pb::Vote>, Status> {
+        let model_req = pb_to_create_vote(request.into_inner())?;
+
+        let vote = self.service
+            .cast_vote(model_req)
+            .map_err(hub_error_to_status)?;
+
+        Ok(Response::new(vote.into()))
+    }
+
+    async fn get_vote(
+        &self,
+        request: Request<pb::GetVoteRequest>,
+    ) -> Result<Response<pb::Vote>, Status> {
+        let uuid = request.into_inner().uuid;
+
+        let vote = self.service
+            .get_vote(&uuid)
+            .map_err(hub_error_to_status)?;
+
+        Ok(Response::new(vote.into()))
+    }
+
     // ========================================================================
     // Tags
     // ========================================================================
@@ -582,9 +824,11 @@ impl HubService for HubServiceImpl {
             .ok_or_else(|| Status::invalid_argument(format!("Invalid from address: {}", req.from_address)))?;
         let to = Address::parse(&req.to_address)
             .ok_or_else(|| Status::invalid_argument(format!("Invalid to address: {}", req.to_address)))?;
+        let options = pb_trust_options(req.max_depth, req.damping_factor, &req.scopes)?;
 
         let path = self.trust_service
-            .find_best_path(&from, &to)
+            .find_best_path_federated(&from, &to, &options)
+            .await
             .map_err(hub_error_to_status)?;
 
         match path {
@@ -603,9 +847,18 @@ impl HubService for HubServiceImpl {
             .ok_or_else(|| Status::invalid_argument(format!("Invalid entity address: {}", req.entity_address)))?;
         let viewer = Address::parse(&req.viewer_address)
             .ok_or_else(|| Status::invalid_argument(format!("Invalid viewer address: {}", req.viewer_address)))?;
+        let mut options = pb_trust_options(req.max_depth, req.damping_factor, &req.scopes)?;
+        options.include_endorsements = req.include_endorsements;
+        if req.explain {
+            options.explain_top_k = Some(match req.top_k {
+                k if k <= 0 => 3,
+                k => k as usize,
+            });
+        }
 
         let score = self.trust_service
-            .calculate_trust_score(&entity, &viewer)
+            .calculate_trust_score_federated(&entity, &viewer, &options)
+            .await
             .map_err(hub_error_to_status)?;
 
         Ok(Response::new(score.into()))
@@ -680,7 +933,17 @@ impl HubService for HubServiceImpl {
     }
 }
 
-/// Create a new gRPC server router
-pub fn create_grpc_service(service: Arc<EntityService>, store: Arc<EntityStore>) -> pb::hub_service_server::HubServiceServer<HubServiceImpl> {
-    pb::hub_service_server::HubServiceServer::new(HubServiceImpl::new(service, store))
+/// Create a new gRPC server router sharing the REST API's services
+pub fn create_grpc_service(
+    service: Arc<EntityService>,
+    trust_service: Arc<TrustService>,
+    validity_service: Arc<ValidityService>,
+    discovery_service: Arc<DiscoveryService>,
+) -> pb::hub_service_server::HubServiceServer<HubServiceImpl> {
+    pb::hub_service_server::HubServiceServer::new(HubServiceImpl::with_discovery(
+        service,
+        trust_service,
+        validity_service,
+        discovery_service,
+    ))
 }
@@ -4,6 +4,8 @@ use actix_web::{get, HttpResponse, web};
 use serde::Serialize;
 use chrono::{DateTime, Utc};
 
+use crate::services::{DiscoveryMonitor, DiscoveryStatus};
+
 /// Health check response
 #[derive(Debug, Serialize)]
 pub struct HealthResponse {
@@ -13,6 +15,8 @@ pub struct HealthResponse {
     pub timestamp: DateTime<Utc>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub uptime_seconds: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discovery: Option<DiscoveryStatus>,
 }
 
 impl HealthResponse {
@@ -23,6 +27,7 @@ impl HealthResponse {
             hub_id: hub_id.to_string(),
             timestamp: Utc::now(),
             uptime_seconds: None,
+            discovery: None,
         }
     }
 
@@ -30,6 +35,11 @@ impl HealthResponse {
         self.uptime_seconds = Some(uptime);
         self
     }
+
+    pub fn with_discovery(mut self, discovery: Option<DiscoveryStatus>) -> Self {
+        self.discovery = discovery;
+        self
+    }
 }
 
 /// Shared application state
@@ -38,6 +48,7 @@ pub struct AppState {
     pub hub_id: String,
     pub version: String,
     pub start_time: DateTime<Utc>,
+    pub discovery: Option<DiscoveryMonitor>,
 }
 
 impl AppState {
@@ -46,9 +57,16 @@ impl AppState {
             hub_id: hub_id.into(),
             version: env!("CARGO_PKG_VERSION").to_string(),
             start_time: Utc::now(),
+            discovery: None,
         }
     }
 
+    /// Report the discovery tasks on `/health`
+    pub fn with_discovery(mut self, monitor: DiscoveryMonitor) -> Self {
+        self.discovery = Some(monitor);
+        self
+    }
+
     pub fn uptime_seconds(&self) -> f64 {
         let duration = Utc::now().signed_duration_since(self.start_time);
         duration.num_milliseconds() as f64 / 1000.0
@@ -59,7 +77,8 @@ impl AppState {
 #[get("/health")]
 pub async fn health_check(state: web::Data<AppState>) -> HttpResponse {
     let response = HealthResponse::healthy(&state.hub_id, &state.version)
-        .with_uptime(state.uptime_seconds());
+        .with_uptime(state.uptime_seconds())
+        .with_discovery(state.discovery.as_ref().map(|d| d.status()));
 
     HttpResponse::Ok().json(response)
 }
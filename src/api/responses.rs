@@ -3,6 +3,7 @@
 use actix_web::{HttpResponse, http::StatusCode};
 use serde::Serialize;
 
+use crate::crypto::{HubIdentity, HubSignature};
 use crate::models::HubError;
 use crate::resources::HubStatusSummary;
 
@@ -16,6 +17,9 @@ pub struct ApiResponse<T: Serialize> {
     pub error: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub hub_status: Option<HubStatusSummary>,
+    /// Hub signature over `data`, on responses other hubs rely on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<HubSignature>,
 }
 
 impl<T: Serialize> ApiResponse<T> {
@@ -26,6 +30,7 @@ impl<T: Serialize> ApiResponse<T> {
             data: Some(data),
             error: None,
             hub_status: None,
+            signature: None,
         }
     }
 
@@ -36,6 +41,7 @@ impl<T: Serialize> ApiResponse<T> {
             data: Some(data),
             error: None,
             hub_status,
+            signature: None,
         }
     }
 
@@ -46,6 +52,7 @@ impl<T: Serialize> ApiResponse<T> {
             data: None,
             error: Some(message.into()),
             hub_status: None,
+            signature: None,
         }
     }
 
@@ -56,7 +63,19 @@ impl<T: Serialize> ApiResponse<T> {
             data: None,
             error: Some(message.into()),
             hub_status,
+            signature: None,
+        }
+    }
+
+    /// Sign the response data with the hub identity
+    pub fn signed(mut self, identity: &HubIdentity) -> Self {
+        if let Some(ref data) = self.data {
+            match identity.sign(data) {
+                Ok(signature) => self.signature = Some(signature),
+                Err(e) => tracing::warn!("Failed to sign response: {}", e),
+            }
         }
+        self
     }
 }
 
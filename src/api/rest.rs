@@ -1,21 +1,24 @@
 //! REST API endpoints using Actix-Web
 
-use actix_web::{web, HttpResponse, Result};
+use actix_web::{web, HttpRequest, HttpResponse, Result};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 use crate::api::responses::{ApiResponse, PaginatedResponse};
+use crate::crypto::HubIdentity;
 use crate::models::{
-    CreateAgentRequest, CreateFragmentRequest, CreateRelationRequest,
-    CreateTagRequest, CreateTransformRequest, Address,
+    CreateAgentRequest, UpdateTrustRequest, RotateKeyRequest, RevokeKeyRequest, ConfirmContentRequest,
+    CreateFragmentRequest, CreateRelationRequest,
+    CreateTagRequest, CreateTransformRequest, CreateVoteRequest, Address, Domain, TrustScope, HubResult,
 };
 use crate::resources::{ResourceMonitor, ResourceLevel};
 use crate::services::{
-    EntityService, TrustService, TrustConfig,
+    EntityService, TrustService, TrustConfig, TrustQueryOptions, ValidityService, ValidityTrigger,
     DiscoveryService, DiscoveryConfig, RegisterHubRequest, HeartbeatRequest as ServiceHeartbeatRequest,
     FederatedSearchService,
 };
 use crate::store::EntityStore;
+use crate::trust::{GraphFormat, HubDirectory, graph_to_dot, graph_to_graphml, server_port_from_url};
 
 use super::health::configure_health_routes;
 
@@ -24,30 +27,52 @@ use super::health::configure_health_routes;
 pub struct ApiState {
     pub service: Arc<EntityService>,
     pub trust_service: Arc<TrustService>,
+    pub validity_service: Arc<ValidityService>,
     pub discovery_service: Arc<DiscoveryService>,
     pub federated_search_service: Arc<FederatedSearchService>,
     pub resource_monitor: Arc<ResourceMonitor>,
 }
 
 impl ApiState {
-    /// Create a new API state with all services
-    pub fn new(store: Arc<EntityStore>, discovery_config: DiscoveryConfig, resource_monitor: Arc<ResourceMonitor>) -> Self {
-        let service = Arc::new(EntityService::new(Arc::clone(&store)));
-        let trust_service = Arc::new(TrustService::new(Arc::clone(&store), TrustConfig::default()));
-        let discovery_service = Arc::new(DiscoveryService::new(discovery_config, Arc::clone(&store)));
+    /// Create a new API state with all services.
+    ///
+    /// Fails if the hub registry of a primary hub cannot be restored.
+    pub fn new(
+        store: Arc<EntityStore>,
+        trust_config: TrustConfig,
+        discovery_config: DiscoveryConfig,
+        identity: HubIdentity,
+        resource_monitor: Arc<ResourceMonitor>,
+    ) -> HubResult<Self> {
+        let local_server = server_port_from_url(&discovery_config.public_url);
+        let discovery_service = Arc::new(
+            DiscoveryService::new(discovery_config, Arc::clone(&store))?.with_identity(identity),
+        );
+        let trust_service = Arc::new(
+            TrustService::new(Arc::clone(&store), trust_config)
+                .with_local_server(local_server)
+                .with_hub_directory(Arc::clone(&discovery_service) as Arc<dyn HubDirectory>),
+        );
+        let service = Arc::new(
+            EntityService::new(Arc::clone(&store)).with_trust_service(Arc::clone(&trust_service)),
+        );
+        let validity_service = Arc::new(
+            ValidityService::new().with_store(Arc::clone(&store), Arc::clone(&trust_service)),
+        );
 
         let federated_search_service = Arc::new(FederatedSearchService::new(
             Arc::clone(&service),
             Arc::clone(&discovery_service),
         ));
 
-        Self {
+        Ok(Self {
             service,
             trust_service,
+            validity_service,
             discovery_service,
             federated_search_service,
             resource_monitor,
-        }
+        })
     }
 }
 
@@ -56,6 +81,9 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
     // Health endpoints at root
     configure_health_routes(cfg);
 
+    // Hub identity for peers to pin
+    cfg.route("/.well-known/wisdom-hub", web::get().to(well_known_hub));
+
     // API v1 routes
     cfg.service(
         web::scope("/api/v1")
@@ -73,6 +101,12 @@ fn configure_v1_routes(cfg: &mut web::ServiceConfig) {
                 .route("", web::post().to(create_agent))
                 .route("/{uuid}", web::get().to(get_agent))
                 .route("/{uuid}", web::delete().to(delete_agent))
+                .route("/{uuid}/trust", web::post().to(update_agent_trust))
+                .route("/{uuid}/rotate-key", web::post().to(rotate_agent_key))
+                .route("/{uuid}/revoke-key", web::post().to(revoke_agent_key))
+                .route("/{uuid}/confirm-content", web::post().to(confirm_agent_content))
+                .route("/{uuid}/trust/stale", web::get().to(get_stale_trust))
+                .route("/{uuid}/validity-events", web::get().to(get_validity_events))
         )
         // Fragment routes
         .service(
@@ -81,6 +115,9 @@ fn configure_v1_routes(cfg: &mut web::ServiceConfig) {
                 .route("", web::post().to(create_fragment))
                 .route("/{uuid}", web::get().to(get_fragment))
                 .route("/{uuid}", web::delete().to(delete_fragment))
+                .route("/{uuid}/validity", web::get().to(get_fragment_validity))
+                .route("/{uuid}/evidence", web::get().to(get_fragment_evidence))
+                .route("/{uuid}/votes", web::get().to(list_fragment_votes))
                 .route("/search", web::get().to(search_fragments))
         )
         // Relation routes
@@ -90,6 +127,12 @@ fn configure_v1_routes(cfg: &mut web::ServiceConfig) {
                 .route("", web::post().to(create_relation))
                 .route("/{uuid}", web::get().to(get_relation))
         )
+        // Vote routes
+        .service(
+            web::scope("/votes")
+                .route("", web::post().to(cast_vote))
+                .route("/{uuid}", web::get().to(get_vote))
+        )
         // Tag routes
         .service(
             web::scope("/tags")
@@ -109,6 +152,8 @@ fn configure_v1_routes(cfg: &mut web::ServiceConfig) {
             web::scope("/trust")
                 .route("/path", web::get().to(get_trust_path))
                 .route("/score", web::get().to(get_trust_score))
+                .route("/graph", web::get().to(get_trust_graph))
+                .route("/sybil", web::get().to(get_sybil_report))
         )
         // Sync routes
         .service(
@@ -121,6 +166,19 @@ fn configure_v1_routes(cfg: &mut web::ServiceConfig) {
                 .route("/hubs", web::get().to(get_known_hubs))
                 .route("/register", web::post().to(register_hub))
                 .route("/heartbeat", web::post().to(heartbeat))
+                .route("/key-rotations", web::post().to(key_rotation_notice))
+                .route("/gossip", web::post().to(gossip))
+                .route("/registry", web::get().to(registry_snapshot))
+        )
+        // Key revocation routes
+        .service(
+            web::scope("/revocations")
+                .route("", web::get().to(list_revocations))
+        )
+        // Dispute routes
+        .service(
+            web::scope("/disputes")
+                .route("", web::get().to(list_disputes))
         )
         // Search routes
         .service(
@@ -231,6 +289,122 @@ async fn delete_agent(
     Ok(HttpResponse::NoContent().finish())
 }
 
+async fn update_agent_trust(
+    state: web::Data<ApiState>,
+    path: web::Path<String>,
+    body: web::Json<UpdateTrustRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let uuid = path.into_inner();
+    let agent = state.service
+        .update_agent_trust(&uuid, body.into_inner())
+        .map_err(|e| actix_web::error::InternalError::from_response(
+            e.to_string(),
+            HttpResponse::from(e)
+        ))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(agent)))
+}
+
+/// Rotate an agent's key and tell the other hubs about it
+async fn rotate_agent_key(
+    state: web::Data<ApiState>,
+    path: web::Path<String>,
+    body: web::Json<RotateKeyRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let uuid = path.into_inner();
+    let agent = state.service
+        .rotate_agent_key(&uuid, body.into_inner())
+        .map_err(|e| actix_web::error::InternalError::from_response(
+            e.to_string(),
+            HttpResponse::from(e)
+        ))?;
+
+    let discovery = Arc::clone(&state.discovery_service);
+    let address = Address::agent(server_port_from_url(&discovery.self_info().public_url), &uuid);
+    tokio::spawn(async move {
+        discovery.announce_key_rotation(&address).await;
+    });
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(agent)))
+}
+
+/// Declare an agent's key compromised and quarantine content signed with it
+async fn revoke_agent_key(
+    state: web::Data<ApiState>,
+    path: web::Path<String>,
+    body: web::Json<RevokeKeyRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let uuid = path.into_inner();
+    let (revocation, quarantined) = state.service
+        .revoke_key(&uuid, body.into_inner())
+        .map_err(|e| actix_web::error::InternalError::from_response(
+            e.to_string(),
+            HttpResponse::from(e)
+        ))?;
+    state.validity_service.propagate_quarantine_in_background(quarantined.clone());
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({
+        "revocation": revocation,
+        "quarantined": quarantined,
+    }))))
+}
+
+/// Release quarantined content the agent confirms
+async fn confirm_agent_content(
+    state: web::Data<ApiState>,
+    path: web::Path<String>,
+    body: web::Json<ConfirmContentRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let uuid = path.into_inner();
+    let released = state.service
+        .confirm_content(&uuid, body.into_inner())
+        .map_err(|e| actix_web::error::InternalError::from_response(
+            e.to_string(),
+            HttpResponse::from(e)
+        ))?;
+    state.validity_service.propagate_quarantine_in_background(released.clone());
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(released)))
+}
+
+async fn get_stale_trust(
+    state: web::Data<ApiState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let uuid = path.into_inner();
+    let report = state.trust_service
+        .stale_trust_report(&uuid)
+        .map_err(|e| actix_web::error::InternalError::from_response(
+            e.to_string(),
+            HttpResponse::from(e)
+        ))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(report)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ValidityEventsQuery {
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+/// Recent validity changes of fragments created by the agent
+async fn get_validity_events(
+    state: web::Data<ApiState>,
+    path: web::Path<String>,
+    query: web::Query<ValidityEventsQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let uuid = path.into_inner();
+    let events = state.validity_service
+        .recent_events_for(&uuid, query.limit.min(100))
+        .map_err(|e| actix_web::error::InternalError::from_response(
+            e.to_string(),
+            HttpResponse::from(e)
+        ))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(events)))
+}
+
 // ============================================================================
 // Fragment Handlers
 // ============================================================================
@@ -284,6 +458,9 @@ async fn create_fragment(
             e.to_string(),
             HttpResponse::from(e)
         ))?;
+    state.validity_service.propagate_in_background(ValidityTrigger::FragmentCreated {
+        fragment: fragment.uuid.clone(),
+    });
 
     Ok(HttpResponse::Created().json(ApiResponse::success_with_status(fragment, hub_status)))
 }
@@ -303,6 +480,104 @@ async fn get_fragment(
     Ok(HttpResponse::Ok().json(ApiResponse::success(fragment)))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ValidityQuery {
+    /// Address of the agent whose trust is used for source checks (default: the creator)
+    pub viewer: Option<String>,
+}
+
+async fn get_fragment_validity(
+    state: web::Data<ApiState>,
+    path: web::Path<String>,
+    query: web::Query<ValidityQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let uuid = path.into_inner();
+    let viewer = match query.viewer {
+        Some(ref viewer) => match Address::parse(viewer) {
+            Some(addr) => Some(addr),
+            None => {
+                return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+                    format!("Invalid 'viewer' address: {}", viewer)
+                )));
+            }
+        },
+        None => None,
+    };
+
+    let report = state.validity_service
+        .analyze_fragment(&uuid, viewer.as_ref())
+        .map_err(|e| actix_web::error::InternalError::from_response(
+            e.to_string(),
+            HttpResponse::from(e)
+        ))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(report)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EvidenceQuery {
+    /// Address of the agent whose trust weights the evidence
+    pub viewer: String,
+}
+
+async fn get_fragment_evidence(
+    state: web::Data<ApiState>,
+    path: web::Path<String>,
+    query: web::Query<EvidenceQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let uuid = path.into_inner();
+    let viewer = Address::parse(&query.viewer)
+        .ok_or_else(|| actix_web::error::InternalError::from_response(
+            format!("Invalid 'viewer' address: {}", query.viewer),
+            HttpResponse::BadRequest().json(ApiResponse::<()>::error("Invalid 'viewer' address"))
+        ))?;
+
+    let balance = state.validity_service
+        .weighted_evidence_balance(&uuid, &viewer)
+        .map_err(|e| actix_web::error::InternalError::from_response(
+            e.to_string(),
+            HttpResponse::from(e)
+        ))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(balance)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DisputeQuery {
+    /// Only clusters containing a fragment with this tag (UUID or name)
+    pub tag: Option<String>,
+    /// Address of the agent whose trust weights the evidence (default: raw confidences)
+    pub viewer: Option<String>,
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+async fn list_disputes(
+    state: web::Data<ApiState>,
+    query: web::Query<DisputeQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let viewer = match query.viewer {
+        Some(ref viewer) => match Address::parse(viewer) {
+            Some(addr) => Some(addr),
+            None => {
+                return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+                    format!("Invalid 'viewer' address: {}", viewer)
+                )));
+            }
+        },
+        None => None,
+    };
+
+    let disputes = state.validity_service
+        .find_disputes(query.tag.as_deref(), viewer.as_ref(), query.limit.min(100))
+        .map_err(|e| actix_web::error::InternalError::from_response(
+            e.to_string(),
+            HttpResponse::from(e)
+        ))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(disputes)))
+}
+
 async fn delete_fragment(
     state: web::Data<ApiState>,
     path: web::Path<String>,
@@ -314,6 +589,7 @@ async fn delete_fragment(
             e.to_string(),
             HttpResponse::from(e)
         ))?;
+    state.validity_service.propagate_in_background(ValidityTrigger::FragmentDeleted { fragment: uuid });
 
     Ok(HttpResponse::NoContent().finish())
 }
@@ -369,6 +645,7 @@ async fn create_relation(
             e.to_string(),
             HttpResponse::from(e)
         ))?;
+    state.validity_service.propagate_in_background(ValidityTrigger::relation_created(&relation));
 
     Ok(HttpResponse::Created().json(ApiResponse::success(relation)))
 }
@@ -388,6 +665,54 @@ async fn get_relation(
     Ok(HttpResponse::Ok().json(ApiResponse::success(relation)))
 }
 
+// ============================================================================
+// Vote Handlers
+// ============================================================================
+
+async fn cast_vote(
+    state: web::Data<ApiState>,
+    body: web::Json<CreateVoteRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let vote = state.service
+        .cast_vote(body.into_inner())
+        .map_err(|e| actix_web::error::InternalError::from_response(
+            e.to_string(),
+            HttpResponse::from(e)
+        ))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(vote)))
+}
+
+async fn get_vote(
+    state: web::Data<ApiState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let uuid = path.into_inner();
+    let vote = state.service
+        .get_vote(&uuid)
+        .map_err(|e| actix_web::error::InternalError::from_response(
+            e.to_string(),
+            HttpResponse::from(e)
+        ))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(vote)))
+}
+
+async fn list_fragment_votes(
+    state: web::Data<ApiState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let uuid = path.into_inner();
+    let votes = state.service
+        .list_votes(&uuid)
+        .map_err(|e| actix_web::error::InternalError::from_response(
+            e.to_string(),
+            HttpResponse::from(e)
+        ))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(votes)))
+}
+
 // ============================================================================
 // Tag Handlers
 // ============================================================================
@@ -502,6 +827,12 @@ pub struct TrustPathQuery {
     pub from: String,
     /// Address string: "server:port/DOMAIN/entity-uuid"
     pub to: String,
+    /// Optional depth override (bounded by trust.max_depth)
+    pub max_depth: Option<u8>,
+    /// Optional damping override (bounded by the configured damping range)
+    pub damping_factor: Option<f32>,
+    /// Optional comma-separated scopes, e.g. "programming:rust,tag:hub:8080:TAG:uuid"
+    pub scope: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -510,6 +841,45 @@ pub struct TrustScoreQuery {
     pub entity: String,
     /// Address of the viewer (perspective)
     pub viewer: String,
+    /// Optional depth override (bounded by trust.max_depth)
+    pub max_depth: Option<u8>,
+    /// Optional damping override (bounded by the configured damping range)
+    pub damping_factor: Option<f32>,
+    /// Optional comma-separated scopes, e.g. "programming:rust,tag:hub:8080:TAG:uuid"
+    pub scope: Option<String>,
+    /// Include an explanation of how the score was derived
+    pub explain: Option<bool>,
+    /// Number of contributing paths to explain (default 3)
+    pub top_k: Option<usize>,
+    /// Fold TRUST relations about the entity into the score
+    pub endorsements: Option<bool>,
+}
+
+/// Paths returned by trust score explanations unless `top_k` is given
+const DEFAULT_EXPLAIN_PATHS: usize = 3;
+
+#[derive(Debug, Deserialize)]
+pub struct TrustGraphQuery {
+    /// Address of the agent at the center of the graph
+    pub center: String,
+    /// Number of hops to include (default and maximum: trust.max_depth)
+    pub depth: Option<u8>,
+    /// Output format: json (default), graphml or dot
+    pub format: Option<String>,
+}
+
+/// Parse a comma-separated list of trust scopes
+fn parse_scope_param(scope: Option<&str>) -> Result<Vec<TrustScope>, actix_web::Error> {
+    scope
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<TrustScope>().map_err(|e| actix_web::error::InternalError::from_response(
+            e.clone(),
+            HttpResponse::BadRequest().json(ApiResponse::<()>::error(e))
+        ).into()))
+        .collect()
 }
 
 #[derive(Debug, Serialize)]
@@ -535,8 +905,16 @@ async fn get_trust_path(
             HttpResponse::BadRequest().json(ApiResponse::<()>::error("Invalid 'to' address"))
         ))?;
 
+    let options = TrustQueryOptions {
+        max_depth: query.max_depth,
+        damping_factor: query.damping_factor,
+        scope: parse_scope_param(query.scope.as_deref())?,
+        ..Default::default()
+    };
+
     let path = state.trust_service
-        .find_best_path(&from, &to)
+        .find_best_path_federated(&from, &to, &options)
+        .await
         .map_err(|e| actix_web::error::InternalError::from_response(
             e.to_string(),
             HttpResponse::from(e)
@@ -567,8 +945,17 @@ async fn get_trust_score(
             HttpResponse::BadRequest().json(ApiResponse::<()>::error("Invalid 'viewer' address"))
         ))?;
 
+    let options = TrustQueryOptions {
+        max_depth: query.max_depth,
+        damping_factor: query.damping_factor,
+        scope: parse_scope_param(query.scope.as_deref())?,
+        explain_top_k: query.explain.unwrap_or(false).then(|| query.top_k.unwrap_or(DEFAULT_EXPLAIN_PATHS)),
+        include_endorsements: query.endorsements.unwrap_or(false),
+    };
+
     let score = state.trust_service
-        .calculate_trust_score(&entity, &viewer)
+        .calculate_trust_score_federated(&entity, &viewer, &options)
+        .await
         .map_err(|e| actix_web::error::InternalError::from_response(
             e.to_string(),
             HttpResponse::from(e)
@@ -577,6 +964,95 @@ async fn get_trust_score(
     Ok(HttpResponse::Ok().json(ApiResponse::success(score)))
 }
 
+async fn get_trust_graph(
+    state: web::Data<ApiState>,
+    query: web::Query<TrustGraphQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let center = match Address::parse(&query.center) {
+        Some(addr) => addr,
+        None => {
+            return Ok(HttpResponse::BadRequest().json(
+                ApiResponse::<()>::error("Invalid 'center' address")
+            ));
+        }
+    };
+
+    let format: GraphFormat = match query.format.as_deref().unwrap_or("json").parse() {
+        Ok(format) => format,
+        Err(e) => return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(e))),
+    };
+
+    let max_depth = state.trust_service.config().max_depth;
+    let depth = query.depth.unwrap_or(max_depth);
+    if depth > max_depth {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+            format!("depth must not exceed {}", max_depth)
+        )));
+    }
+
+    let graph = state.trust_service
+        .build_trust_graph(&center, depth)
+        .map_err(|e| actix_web::error::InternalError::from_response(
+            e.to_string(),
+            HttpResponse::from(e)
+        ))?;
+
+    let response = match format {
+        GraphFormat::Json => HttpResponse::Ok().json(ApiResponse::success(graph)),
+        GraphFormat::GraphMl => HttpResponse::Ok()
+            .content_type(format.content_type())
+            .body(graph_to_graphml(&graph)),
+        GraphFormat::Dot => HttpResponse::Ok()
+            .content_type(format.content_type())
+            .body(graph_to_dot(&graph)),
+    };
+
+    Ok(response)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SybilQuery {
+    /// Only return the score of this agent UUID
+    pub agent: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SybilScoreResponse {
+    pub agent: String,
+    pub suspicion: f32,
+    pub suspected: bool,
+    pub computed_at: chrono::DateTime<chrono::Utc>,
+}
+
+async fn get_sybil_report(
+    state: web::Data<ApiState>,
+    query: web::Query<SybilQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    // Run the analysis on demand if the background task has not produced one yet
+    let report = match state.trust_service.sybil_report() {
+        Some(report) => report,
+        None => state.trust_service
+            .run_sybil_analysis()
+            .await
+            .map_err(|e| actix_web::error::InternalError::from_response(
+                e.to_string(),
+                HttpResponse::from(e)
+            ))?,
+    };
+
+    if let Some(agent) = &query.agent {
+        let suspicion = report.suspicion(agent, true);
+        return Ok(HttpResponse::Ok().json(ApiResponse::success(SybilScoreResponse {
+            agent: agent.clone(),
+            suspicion,
+            suspected: suspicion >= state.trust_service.config().sybil.suspicion_threshold,
+            computed_at: report.computed_at,
+        })));
+    }
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(&*report)))
+}
+
 // ============================================================================
 // Sync Handlers (Not needed - Gateways use Push + Search)
 // ============================================================================
@@ -602,13 +1078,37 @@ async fn pull_changes(
 // Discovery Handlers
 // ============================================================================
 
+#[derive(Debug, Deserialize)]
+pub struct WellKnownQuery {
+    /// Challenge from a primary verifying this hub's URL
+    pub nonce: Option<String>,
+}
+
+/// This hub's identity and public key, signed with that key
+async fn well_known_hub(
+    state: web::Data<ApiState>,
+    query: web::Query<WellKnownQuery>,
+) -> HttpResponse {
+    if query.nonce.as_ref().is_some_and(|n| n.len() > 128) {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error("nonce too long"));
+    }
+
+    let mut descriptor = state.discovery_service.descriptor();
+    descriptor.nonce = query.into_inner().nonce;
+    HttpResponse::Ok().json(ApiResponse::success(descriptor).signed(state.discovery_service.identity()))
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ApiRegisterHubRequest {
     pub hub_id: String,
     pub public_url: String,
-    pub capabilities: Vec<String>,
+    pub capabilities: crate::discovery::CapabilitySet,
     pub version: Option<String>,
-    pub public_key: Option<String>,
+    #[serde(default = "crate::discovery::legacy_protocol_version")]
+    pub protocol_version: u32,
+    pub public_key: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub signature: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -616,6 +1116,8 @@ pub struct ApiHeartbeatRequest {
     pub hub_id: String,
     pub status: String,
     pub stats: crate::discovery::HubStats,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub signature: String,
 }
 
 async fn get_known_hubs(
@@ -628,21 +1130,30 @@ async fn get_known_hubs(
             HttpResponse::from(e)
         ))?;
 
-    Ok(HttpResponse::Ok().json(ApiResponse::success(hub_list)))
+    Ok(HttpResponse::Ok().json(
+        ApiResponse::success(hub_list).signed(state.discovery_service.identity()),
+    ))
 }
 
 async fn register_hub(
     state: web::Data<ApiState>,
+    http_req: HttpRequest,
     body: web::Json<ApiRegisterHubRequest>,
 ) -> Result<HttpResponse, actix_web::Error> {
     let req = body.into_inner();
 
+    let hub_id = req.hub_id.clone();
     let service_req = RegisterHubRequest {
         hub_id: req.hub_id,
         public_url: req.public_url,
         capabilities: req.capabilities,
         version: req.version,
+        protocol_version: req.protocol_version,
         public_key: req.public_key,
+        timestamp: req.timestamp,
+        signature: req.signature,
+        // The connecting address; forwarded headers can be set by anyone
+        source: http_req.peer_addr().map(|addr| addr.ip()),
     };
 
     let response = state.discovery_service
@@ -652,7 +1163,15 @@ async fn register_hub(
             HttpResponse::from(e)
         ))?;
 
-    Ok(HttpResponse::Ok().json(ApiResponse::success(response)))
+    // Prove the hub controls the URL it claims before federating with it
+    let discovery = Arc::clone(&state.discovery_service);
+    tokio::spawn(async move {
+        let _ = discovery.verify_hub_url(&hub_id).await;
+    });
+
+    Ok(HttpResponse::Ok().json(
+        ApiResponse::success(response).signed(state.discovery_service.identity()),
+    ))
 }
 
 async fn heartbeat(
@@ -665,6 +1184,8 @@ async fn heartbeat(
         hub_id: req.hub_id,
         status: req.status,
         stats: req.stats,
+        timestamp: req.timestamp,
+        signature: req.signature,
     };
 
     let response = state.discovery_service
@@ -674,7 +1195,105 @@ async fn heartbeat(
             HttpResponse::from(e)
         ))?;
 
-    Ok(HttpResponse::Ok().json(ApiResponse::success(response)))
+    Ok(HttpResponse::Ok().json(
+        ApiResponse::success(response).signed(state.discovery_service.identity()),
+    ))
+}
+
+/// Registry state for replication between primary hubs
+async fn registry_snapshot(
+    state: web::Data<ApiState>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let snapshot = state.discovery_service
+        .registry_snapshot()
+        .map_err(|e| actix_web::error::InternalError::from_response(
+            e.to_string(),
+            HttpResponse::from(e)
+        ))?;
+
+    Ok(HttpResponse::Ok().json(
+        ApiResponse::success(snapshot).signed(state.discovery_service.identity()),
+    ))
+}
+
+/// Exchange signed hub entries with a peer (gossip mode)
+async fn gossip(
+    state: web::Data<ApiState>,
+    body: web::Json<crate::discovery::GossipMessage>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let response = state.discovery_service
+        .handle_gossip(body.into_inner())
+        .map_err(|e| actix_web::error::InternalError::from_response(
+            e.to_string(),
+            HttpResponse::from(e)
+        ))?;
+
+    Ok(HttpResponse::Ok().json(
+        ApiResponse::success(response).signed(state.discovery_service.identity()),
+    ))
+}
+
+/// Another hub announced that one of its agents rotated its key
+async fn key_rotation_notice(
+    state: web::Data<ApiState>,
+    body: web::Bytes,
+) -> Result<HttpResponse, actix_web::Error> {
+    let notice = state.discovery_service
+        .verify_key_rotation_notice(&body)
+        .map_err(|e| actix_web::error::InternalError::from_response(
+            e.to_string(),
+            HttpResponse::from(e)
+        ))?;
+    if notice.agent.domain != Domain::Agent {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("agent must be an agent address")));
+    }
+
+    // The agent is refetched from its hub's registered URL and checked against the pinned key
+    let agent = state.trust_service
+        .refresh_remote_agent(&notice.agent)
+        .await
+        .map_err(|e| actix_web::error::InternalError::from_response(
+            e.to_string(),
+            HttpResponse::from(e)
+        ))?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({
+        "agent": notice.agent,
+        "refreshed": agent.is_some(),
+    }))))
+}
+
+// ============================================================================
+// Revocation Handlers
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+pub struct RevocationQuery {
+    /// Only revocations recorded after this time
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Key revocations for other hubs and gateways to poll.
+///
+/// Each entry is signed by the agent's current or recovery key.
+async fn list_revocations(
+    state: web::Data<ApiState>,
+    query: web::Query<RevocationQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let revocations = state.service
+        .list_revocations(query.since)
+        .map_err(|e| actix_web::error::InternalError::from_response(
+            e.to_string(),
+            HttpResponse::from(e)
+        ))?;
+
+    Ok(HttpResponse::Ok().json(
+        ApiResponse::success(serde_json::json!({
+            "revocations": revocations,
+            "generated_at": chrono::Utc::now(),
+        }))
+        .signed(state.discovery_service.identity()),
+    ))
 }
 
 // ============================================================================
@@ -705,5 +1324,7 @@ async fn federated_search(
             HttpResponse::from(e)
         ))?;
 
-    Ok(HttpResponse::Ok().json(ApiResponse::success(response)))
+    Ok(HttpResponse::Ok().json(
+        ApiResponse::success(response).signed(state.discovery_service.identity()),
+    ))
 }
@@ -28,17 +28,26 @@ pub struct HubSettings {
     pub public_url: String,
     /// Path to Ed25519 private key file
     pub private_key_path: Option<String>,
-    /// Hub capabilities
+    /// Capabilities to advertise; those the hub does not serve in its role
+    /// and discovery mode are dropped
     #[serde(default = "default_capabilities")]
     pub capabilities: Vec<String>,
 }
 
 fn default_capabilities() -> Vec<String> {
-    vec![
-        "entities".to_string(),
-        "trust".to_string(),
-        "search".to_string(),
+    [
+        "entities",
+        "trust",
+        "search",
+        "search.signed",
+        "search.limit",
+        "replication",
+        "gossip",
+        "grpc",
     ]
+    .iter()
+    .map(|name| name.to_string())
+    .collect()
 }
 
 /// Hub role
@@ -122,6 +131,10 @@ pub struct DiscoverySettings {
     pub enabled: bool,
     /// Primary hub URL (for secondary hubs)
     pub primary_hub_url: Option<String>,
+    /// Further primary hubs: failover targets for secondary hubs, replication
+    /// peers for primary hubs
+    #[serde(default)]
+    pub primary_hub_urls: Vec<String>,
     /// Registration interval in seconds
     #[serde(default = "default_registration_interval")]
     pub registration_interval_sec: u64,
@@ -131,6 +144,48 @@ pub struct DiscoverySettings {
     /// Heartbeat timeout multiplier (times registration_interval)
     #[serde(default = "default_heartbeat_timeout_multiplier")]
     pub heartbeat_timeout_multiplier: u32,
+    /// How long an inactive or unverified hub stays in the registry (primary hubs)
+    #[serde(default = "default_hub_retention")]
+    pub hub_retention_sec: u64,
+    /// How hubs find each other
+    #[serde(default)]
+    pub mode: DiscoveryMode,
+    /// Hubs contacted in gossip mode while no peer answers
+    #[serde(default)]
+    pub seed_hubs: Vec<String>,
+    /// Gossip round interval in seconds
+    #[serde(default = "default_gossip_interval")]
+    pub gossip_interval_sec: u64,
+    /// Peers contacted per gossip round
+    #[serde(default = "default_gossip_fanout")]
+    pub gossip_fanout: usize,
+    /// Interval between health probes of federation targets (0 disables)
+    #[serde(default = "default_probe_interval")]
+    pub probe_interval_sec: u64,
+    /// p95 probe latency above which a hub is degraded
+    #[serde(default = "default_degraded_latency")]
+    pub degraded_latency_ms: u64,
+    /// Most hubs queried per federated search
+    #[serde(default = "default_federation_max_hubs")]
+    pub federation_max_hubs: usize,
+    /// Relevance score of results from degraded hubs
+    #[serde(default = "default_degraded_relevance")]
+    pub degraded_relevance: f64,
+    /// Verify hub URLs on loopback, private and link-local addresses
+    /// (primary hubs of local test federations only)
+    #[serde(default)]
+    pub allow_private_hub_urls: bool,
+}
+
+/// How hubs find each other
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiscoveryMode {
+    /// Secondary hubs register with a primary hub
+    #[default]
+    Registry,
+    /// Every hub exchanges signed hub lists with random peers
+    Gossip,
 }
 
 fn default_registration_interval() -> u64 {
@@ -145,6 +200,34 @@ fn default_heartbeat_timeout_multiplier() -> u32 {
     3
 }
 
+fn default_hub_retention() -> u64 {
+    7 * 24 * 3600 // 7 days
+}
+
+fn default_gossip_interval() -> u64 {
+    30
+}
+
+fn default_gossip_fanout() -> usize {
+    3
+}
+
+fn default_probe_interval() -> u64 {
+    60
+}
+
+fn default_degraded_latency() -> u64 {
+    1000
+}
+
+fn default_federation_max_hubs() -> usize {
+    8
+}
+
+fn default_degraded_relevance() -> f64 {
+    0.8
+}
+
 /// Trust calculation settings
 #[derive(Debug, Clone, Deserialize)]
 pub struct TrustSettings {
@@ -157,8 +240,73 @@ pub struct TrustSettings {
     /// Minimum effective trust threshold
     #[serde(default = "default_min_trust")]
     pub min_trust_threshold: f32,
+    /// Lowest damping factor a request may ask for
+    #[serde(default = "default_min_damping_factor")]
+    pub min_damping_factor: f32,
+    /// Highest damping factor a request may ask for
+    #[serde(default = "default_max_damping_factor")]
+    pub max_damping_factor: f32,
+    /// How trust edges lose weight with age
+    #[serde(default)]
+    pub decay_mode: TrustDecayMode,
+    /// Half-life in days for exponential decay
+    #[serde(default = "default_decay_half_life_days")]
+    pub decay_half_life_days: f32,
+    /// Interval in days after which step decay applies another step
+    #[serde(default = "default_decay_step_days")]
+    pub decay_step_days: u32,
+    /// Multiplier applied per step for step decay
+    #[serde(default = "default_decay_step_factor")]
+    pub decay_step_factor: f32,
+    /// Age in days after which trust edges are ignored (0 = never expire)
+    #[serde(default)]
+    pub max_trust_age_days: u32,
+    /// Age in days after which trust edges are reported as stale
+    #[serde(default = "default_stale_trust_after_days")]
+    pub stale_trust_after_days: u32,
+    /// Agents treated as established for Sybil analysis (empty = use agent age)
+    #[serde(default)]
+    pub sybil_seed_agents: Vec<String>,
+    /// Minimum agent age in days to count as established when no seeds are configured
+    #[serde(default = "default_sybil_min_seed_age_days")]
+    pub sybil_min_seed_age_days: u32,
+    /// Suspicion score at which an agent counts as a suspected Sybil
+    #[serde(default = "default_sybil_threshold")]
+    pub sybil_threshold: f32,
+    /// Down-weighting of paths per suspected intermediate agent (0 = disabled)
+    #[serde(default = "default_sybil_penalty")]
+    pub sybil_penalty: f32,
+    /// Interval in seconds between Sybil analysis runs
+    #[serde(default = "default_sybil_refresh_sec")]
+    pub sybil_refresh_sec: u64,
+    /// Follow trust edges into agents hosted on other hubs
+    #[serde(default = "default_cross_hub")]
+    pub cross_hub: bool,
+    /// Maximum remote trust store fetches per trust query
+    #[serde(default = "default_max_remote_fetches")]
+    pub max_remote_fetches: usize,
+    /// How long fetched remote trust stores are cached, in seconds
+    #[serde(default = "default_remote_cache_ttl_sec")]
+    pub remote_cache_ttl_sec: u64,
+    /// Timeout for a single remote fetch, in milliseconds
+    #[serde(default = "default_remote_timeout_ms")]
+    pub remote_timeout_ms: u64,
+}
+
+/// Decay function applied to aging trust edges
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrustDecayMode {
+    /// Trust keeps its full weight
+    #[default]
+    None,
+    /// Weight halves every `decay_half_life_days`
+    Exponential,
+    /// Weight is multiplied by `decay_step_factor` every `decay_step_days`
+    Step,
 }
 
+
 fn default_max_depth() -> u8 {
     5
 }
@@ -171,6 +319,62 @@ fn default_min_trust() -> f32 {
     0.01
 }
 
+fn default_min_damping_factor() -> f32 {
+    0.1
+}
+
+fn default_max_damping_factor() -> f32 {
+    1.0
+}
+
+fn default_decay_half_life_days() -> f32 {
+    365.0
+}
+
+fn default_decay_step_days() -> u32 {
+    180
+}
+
+fn default_decay_step_factor() -> f32 {
+    0.5
+}
+
+fn default_stale_trust_after_days() -> u32 {
+    365
+}
+
+fn default_sybil_min_seed_age_days() -> u32 {
+    30
+}
+
+fn default_sybil_threshold() -> f32 {
+    0.8
+}
+
+fn default_sybil_penalty() -> f32 {
+    0.5
+}
+
+fn default_sybil_refresh_sec() -> u64 {
+    600 // 10 minutes
+}
+
+fn default_cross_hub() -> bool {
+    true
+}
+
+fn default_max_remote_fetches() -> usize {
+    16
+}
+
+fn default_remote_cache_ttl_sec() -> u64 {
+    300 // 5 minutes
+}
+
+fn default_remote_timeout_ms() -> u64 {
+    3000
+}
+
 /// Metrics settings
 #[derive(Debug, Clone, Deserialize)]
 pub struct MetricsSettings {
@@ -259,9 +463,34 @@ impl Settings {
             .set_default("discovery.registration_interval_sec", 300)?
             .set_default("discovery.hub_list_refresh_sec", 60)?
             .set_default("discovery.heartbeat_timeout_multiplier", 3)?
+            .set_default("discovery.hub_retention_sec", 604800)?
+            .set_default("discovery.mode", "registry")?
+            .set_default("discovery.gossip_interval_sec", 30)?
+            .set_default("discovery.gossip_fanout", 3)?
+            .set_default("discovery.probe_interval_sec", 60)?
+            .set_default("discovery.degraded_latency_ms", 1000)?
+            .set_default("discovery.federation_max_hubs", 8)?
+            .set_default("discovery.degraded_relevance", 0.8)?
+            .set_default("discovery.allow_private_hub_urls", false)?
             .set_default("trust.max_depth", 5)?
             .set_default("trust.damping_factor", 0.8)?
             .set_default("trust.min_trust_threshold", 0.01)?
+            .set_default("trust.min_damping_factor", 0.1)?
+            .set_default("trust.max_damping_factor", 1.0)?
+            .set_default("trust.decay_mode", "none")?
+            .set_default("trust.decay_half_life_days", 365.0)?
+            .set_default("trust.decay_step_days", 180)?
+            .set_default("trust.decay_step_factor", 0.5)?
+            .set_default("trust.max_trust_age_days", 0)?
+            .set_default("trust.stale_trust_after_days", 365)?
+            .set_default("trust.sybil_min_seed_age_days", 30)?
+            .set_default("trust.sybil_threshold", 0.8)?
+            .set_default("trust.sybil_penalty", 0.5)?
+            .set_default("trust.sybil_refresh_sec", 600)?
+            .set_default("trust.cross_hub", true)?
+            .set_default("trust.max_remote_fetches", 16)?
+            .set_default("trust.remote_cache_ttl_sec", 300)?
+            .set_default("trust.remote_timeout_ms", 3000)?
             .set_default("metrics.enabled", true)?
             .set_default("metrics.path", "/metrics")?
             // Add config file if it exists
@@ -299,14 +528,42 @@ impl Default for Settings {
                 discovery: DiscoverySettings {
                     enabled: true,
                     primary_hub_url: None,
+                    primary_hub_urls: Vec::new(),
                     registration_interval_sec: default_registration_interval(),
                     hub_list_refresh_sec: default_hub_list_refresh(),
                     heartbeat_timeout_multiplier: default_heartbeat_timeout_multiplier(),
+                    hub_retention_sec: default_hub_retention(),
+                    mode: DiscoveryMode::Registry,
+                    seed_hubs: Vec::new(),
+                    gossip_interval_sec: default_gossip_interval(),
+                    gossip_fanout: default_gossip_fanout(),
+                    probe_interval_sec: default_probe_interval(),
+                    degraded_latency_ms: default_degraded_latency(),
+                    federation_max_hubs: default_federation_max_hubs(),
+                    degraded_relevance: default_degraded_relevance(),
+                    allow_private_hub_urls: false,
                 },
                 trust: TrustSettings {
                     max_depth: default_max_depth(),
                     damping_factor: default_damping_factor(),
                     min_trust_threshold: default_min_trust(),
+                    min_damping_factor: default_min_damping_factor(),
+                    max_damping_factor: default_max_damping_factor(),
+                    decay_mode: TrustDecayMode::None,
+                    decay_half_life_days: default_decay_half_life_days(),
+                    decay_step_days: default_decay_step_days(),
+                    decay_step_factor: default_decay_step_factor(),
+                    max_trust_age_days: 0,
+                    stale_trust_after_days: default_stale_trust_after_days(),
+                    sybil_seed_agents: Vec::new(),
+                    sybil_min_seed_age_days: default_sybil_min_seed_age_days(),
+                    sybil_threshold: default_sybil_threshold(),
+                    sybil_penalty: default_sybil_penalty(),
+                    sybil_refresh_sec: default_sybil_refresh_sec(),
+                    cross_hub: default_cross_hub(),
+                    max_remote_fetches: default_max_remote_fetches(),
+                    remote_cache_ttl_sec: default_remote_cache_ttl_sec(),
+                    remote_timeout_ms: default_remote_timeout_ms(),
                 },
                 metrics: MetricsSettings {
                     enabled: true,
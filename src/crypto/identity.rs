@@ -0,0 +1,153 @@
+//! Hub identity: the Ed25519 keypair a hub signs its responses with
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+use super::{canonical_json, sign, verify_with_key, KeyPair};
+use crate::models::{HubError, HubResult};
+
+/// A hub's long-lived signing identity
+#[derive(Debug, Clone)]
+pub struct HubIdentity {
+    hub_id: String,
+    keypair: KeyPair,
+}
+
+impl HubIdentity {
+    /// Create an identity from an existing keypair
+    pub fn new(hub_id: impl Into<String>, keypair: KeyPair) -> Self {
+        Self {
+            hub_id: hub_id.into(),
+            keypair,
+        }
+    }
+
+    /// Create an identity with a fresh, unsaved keypair
+    pub fn generate(hub_id: impl Into<String>) -> Self {
+        Self::new(hub_id, KeyPair::generate())
+    }
+
+    /// Load the identity key from `path`, generating and saving one on first start
+    pub fn load_or_generate<P: AsRef<Path>>(hub_id: impl Into<String>, path: P) -> HubResult<Self> {
+        let path = path.as_ref();
+        if path.exists() {
+            return Ok(Self::new(hub_id, KeyPair::load_from_file(path)?));
+        }
+
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .map_err(|e| HubError::CryptoError(format!("Failed to create key directory: {}", e)))?;
+        }
+        let keypair = KeyPair::generate();
+        keypair.save_to_file(path)?;
+        info!("Generated new hub identity key at {}", path.display());
+
+        Ok(Self::new(hub_id, keypair))
+    }
+
+    /// This hub's ID
+    pub fn hub_id(&self) -> &str {
+        &self.hub_id
+    }
+
+    /// Base64-encoded public key
+    pub fn public_key(&self) -> String {
+        self.keypair.public_key_base64()
+    }
+
+    /// Sign a plain-text message (registration and heartbeat requests)
+    pub fn sign_message(&self, message: &str) -> String {
+        sign(&self.keypair, message.as_bytes())
+    }
+
+    /// Sign response data on behalf of this hub
+    pub fn sign<T: Serialize>(&self, data: &T) -> HubResult<HubSignature> {
+        // Go through the serialized text so the signed value is exactly what
+        // peers parse (an f32 widened by `to_value` would print differently)
+        let bytes = serde_json::to_vec(data)
+            .map_err(|e| HubError::SerializationError(e.to_string()))?;
+        let value: Value = serde_json::from_slice(&bytes)
+            .map_err(|e| HubError::SerializationError(e.to_string()))?;
+
+        let signed_at = Utc::now();
+        let payload = HubSignature::signing_payload(&self.hub_id, signed_at, &value);
+        Ok(HubSignature {
+            hub_id: self.hub_id.clone(),
+            public_key: self.public_key(),
+            signed_at,
+            signature: sign(&self.keypair, canonical_json(&payload).as_bytes()),
+        })
+    }
+}
+
+/// Signature a hub attaches to the data of a response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HubSignature {
+    pub hub_id: String,
+    /// Base64-encoded public key of the signing hub
+    pub public_key: String,
+    pub signed_at: DateTime<Utc>,
+    /// Signature over `signing_payload`
+    pub signature: String,
+}
+
+impl HubSignature {
+    /// Payload covered by the signature
+    pub fn signing_payload(hub_id: &str, signed_at: DateTime<Utc>, data: &Value) -> Value {
+        json!({
+            "data": data,
+            "hub_id": hub_id,
+            "signed_at": signed_at.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        })
+    }
+
+    /// Check the signature over response data as received
+    pub fn verify(&self, data: &Value) -> HubResult<bool> {
+        let payload = Self::signing_payload(&self.hub_id, self.signed_at, data);
+        verify_with_key(&self.public_key, canonical_json(&payload).as_bytes(), &self.signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let identity = HubIdentity::generate("hub-1");
+        let data = json!({ "score": 0.1f32, "hubs": ["a", "b"] });
+
+        let signature = identity.sign(&data).unwrap();
+        assert_eq!(signature.public_key, identity.public_key());
+
+        // Verified against the data as a peer parses it off the wire
+        let wire = serde_json::to_string(&data).unwrap();
+        let received: Value = serde_json::from_str(&wire).unwrap();
+        assert!(signature.verify(&received).unwrap());
+
+        let tampered = json!({ "score": 0.2f32, "hubs": ["a", "b"] });
+        assert!(!signature.verify(&tampered).unwrap());
+    }
+
+    #[test]
+    fn test_load_or_generate() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("keys").join("hub.key");
+
+        let first = HubIdentity::load_or_generate("hub-1", &path).unwrap();
+        let second = HubIdentity::load_or_generate("hub-1", &path).unwrap();
+        assert_eq!(first.public_key(), second.public_key());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+}
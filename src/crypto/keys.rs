@@ -4,6 +4,7 @@ use base64::{engine::general_purpose::STANDARD, Engine};
 use ed25519_dalek::{SigningKey, VerifyingKey};
 use rand::rngs::OsRng;
 use std::fs;
+use std::io::Write;
 use std::path::Path;
 
 use crate::models::{HubError, HubResult};
@@ -58,7 +59,22 @@ impl KeyPair {
 
     /// Save the private key to a file
     pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> HubResult<()> {
-        fs::write(path, self.signing_key.to_bytes())
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        // Readable by the owner only from the start, never world-readable
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let mut file = options.open(path)
+            .map_err(|e| HubError::CryptoError(format!("Failed to write key file: {}", e)))?;
+        // An existing file keeps its mode on open
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(0o600))
+                .map_err(|e| HubError::CryptoError(format!("Failed to restrict key file permissions: {}", e)))?;
+        }
+        file.write_all(&self.signing_key.to_bytes())
             .map_err(|e| HubError::CryptoError(format!("Failed to write key file: {}", e)))
     }
 
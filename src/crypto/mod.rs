@@ -4,6 +4,8 @@
 
 mod keys;
 mod signing;
+mod identity;
 
 pub use keys::*;
 pub use signing::*;
+pub use identity::*;
@@ -0,0 +1,211 @@
+//! Hub capabilities and protocol versions
+//!
+//! Hubs advertise what they offer as a set of capability names and the
+//! federation protocol version they speak. Before calling a peer, a hub
+//! intersects the peer's capabilities with its own and checks that their
+//! protocol versions overlap.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Federation protocol version spoken by this build
+pub const PROTOCOL_VERSION: u32 = 2;
+/// Oldest protocol version this build still talks to
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Protocol version of hubs that do not advertise one
+pub fn legacy_protocol_version() -> u32 {
+    1
+}
+
+/// Protocol version to use with a peer, if the versions overlap
+pub fn negotiate_protocol(remote: u32) -> Option<u32> {
+    (remote >= MIN_PROTOCOL_VERSION).then(|| remote.min(PROTOCOL_VERSION))
+}
+
+/// Something a hub offers to its peers
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Capability {
+    /// Serves agents, fragments and other entities
+    Entities,
+    /// Serves trust stores for cross-hub trust paths
+    Trust,
+    /// Answers `/api/v1/search`
+    Search,
+    /// Signs search responses with its hub key
+    SearchSigned,
+    /// Honors the `limit` search parameter
+    SearchLimit,
+    /// Accepts entities in batches
+    BatchIngest,
+    /// Serves registry snapshots to other primaries
+    Replication,
+    /// Takes part in gossip discovery
+    Gossip,
+    /// Serves the gRPC API
+    Grpc,
+    /// Advertised by a peer but unknown to this build
+    Other(String),
+}
+
+impl Capability {
+    /// Capabilities this build implements
+    pub const SUPPORTED: [Capability; 8] = [
+        Capability::Entities,
+        Capability::Trust,
+        Capability::Search,
+        Capability::SearchSigned,
+        Capability::SearchLimit,
+        Capability::Replication,
+        Capability::Gossip,
+        Capability::Grpc,
+    ];
+
+    /// Parse an advertised capability name
+    pub fn parse(name: &str) -> Self {
+        match name {
+            "entities" => Capability::Entities,
+            "trust" => Capability::Trust,
+            "search" => Capability::Search,
+            "search.signed" => Capability::SearchSigned,
+            "search.limit" => Capability::SearchLimit,
+            "batch_ingest" => Capability::BatchIngest,
+            "replication" => Capability::Replication,
+            "gossip" => Capability::Gossip,
+            "grpc" => Capability::Grpc,
+            other => Capability::Other(other.to_string()),
+        }
+    }
+
+    /// Advertised name
+    pub fn name(&self) -> &str {
+        match self {
+            Capability::Entities => "entities",
+            Capability::Trust => "trust",
+            Capability::Search => "search",
+            Capability::SearchSigned => "search.signed",
+            Capability::SearchLimit => "search.limit",
+            Capability::BatchIngest => "batch_ingest",
+            Capability::Replication => "replication",
+            Capability::Gossip => "gossip",
+            Capability::Grpc => "grpc",
+            Capability::Other(name) => name,
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Set of capabilities, serialized as a list of names
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "Vec<String>", into = "Vec<String>")]
+pub struct CapabilitySet(BTreeSet<Capability>);
+
+impl CapabilitySet {
+    /// Parse advertised capability names
+    pub fn from_names<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        names.into_iter().map(|name| Capability::parse(name.as_ref())).collect()
+    }
+
+    /// Everything this build implements
+    pub fn supported() -> Self {
+        Capability::SUPPORTED.into_iter().collect()
+    }
+
+    /// Check for a capability
+    pub fn contains(&self, capability: &Capability) -> bool {
+        self.0.contains(capability)
+    }
+
+    /// Check for all of `capabilities`
+    pub fn contains_all(&self, capabilities: &[Capability]) -> bool {
+        capabilities.iter().all(|c| self.contains(c))
+    }
+
+    /// Capabilities both sets have
+    pub fn intersect(&self, other: &CapabilitySet) -> CapabilitySet {
+        CapabilitySet(self.0.intersection(&other.0).cloned().collect())
+    }
+
+    /// Add a capability
+    pub fn insert(&mut self, capability: Capability) {
+        self.0.insert(capability);
+    }
+
+    /// Remove a capability
+    pub fn remove(&mut self, capability: &Capability) {
+        self.0.remove(capability);
+    }
+
+    /// Iterate over the capabilities in declaration order
+    pub fn iter(&self) -> impl Iterator<Item = &Capability> {
+        self.0.iter()
+    }
+
+    /// Check if the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl FromIterator<Capability> for CapabilitySet {
+    fn from_iter<I: IntoIterator<Item = Capability>>(iter: I) -> Self {
+        CapabilitySet(iter.into_iter().collect())
+    }
+}
+
+impl From<Vec<String>> for CapabilitySet {
+    fn from(names: Vec<String>) -> Self {
+        CapabilitySet::from_names(names)
+    }
+}
+
+impl From<CapabilitySet> for Vec<String> {
+    fn from(set: CapabilitySet) -> Self {
+        set.0.iter().map(|c| c.name().to_string()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capability_names_round_trip() {
+        let set = CapabilitySet::from_names(["search", "search.signed", "grpc", "holograms"]);
+        assert!(set.contains(&Capability::SearchSigned));
+        assert!(set.contains(&Capability::Other("holograms".to_string())));
+
+        let json = serde_json::to_string(&set).unwrap();
+        let parsed: CapabilitySet = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, set);
+        for capability in Capability::SUPPORTED {
+            assert_eq!(Capability::parse(capability.name()), capability);
+        }
+    }
+
+    #[test]
+    fn test_negotiation() {
+        let local = CapabilitySet::supported();
+        let remote = CapabilitySet::from_names(["entities", "search", "batch_ingest"]);
+
+        let common = local.intersect(&remote);
+        assert!(common.contains_all(&[Capability::Entities, Capability::Search]));
+        // Not implemented here, so never used with a peer
+        assert!(!common.contains(&Capability::BatchIngest));
+        assert!(!common.contains(&Capability::SearchSigned));
+
+        assert_eq!(negotiate_protocol(PROTOCOL_VERSION + 1), Some(PROTOCOL_VERSION));
+        assert_eq!(negotiate_protocol(legacy_protocol_version()), Some(1));
+        assert_eq!(negotiate_protocol(0), None);
+    }
+}
@@ -1,33 +1,70 @@
 //! Hub discovery client for secondary hubs
 //!
-//! Will be fully implemented in Phase 5.
+//! Talks to one of the configured primary hubs and fails over to the next
+//! when it is unreachable.
 
 use chrono::{DateTime, Utc};
 use parking_lot::RwLock;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
+use tracing::warn;
 
-use super::{HubInfo, HubList, HubStats, HubStatus};
+use serde::de::DeserializeOwned;
+
+use super::{CapabilitySet, HubInfo, HubList, HubStats, HubStatus, SignedResponse, PROTOCOL_VERSION};
+use crate::crypto::HubIdentity;
 use crate::models::{HubError, HubResult};
 
+/// Message a hub signs to register: `hub_id|public_url|timestamp`
+pub fn registration_message(hub_id: &str, public_url: &str, timestamp: DateTime<Utc>) -> String {
+    format!(
+        "{}|{}|{}",
+        hub_id,
+        public_url,
+        timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+    )
+}
+
+/// Message a hub signs to send a heartbeat:
+/// `heartbeat|hub_id|status|entities|agents|fragments|uptime|timestamp`
+pub fn heartbeat_message(hub_id: &str, status: &str, stats: &HubStats, timestamp: DateTime<Utc>) -> String {
+    format!(
+        "heartbeat|{}|{}|{}|{}|{}|{}|{}",
+        hub_id,
+        status,
+        stats.entities_count,
+        stats.agents_count,
+        stats.fragments_count,
+        stats.uptime_seconds,
+        timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+    )
+}
+
 /// Discovery client for secondary hubs
 pub struct DiscoveryClient {
-    primary_hub_url: String,
+    primary_hub_urls: Vec<String>,
+    /// Index of the primary that answered last
+    active_primary: Arc<RwLock<usize>>,
     hub_id: String,
+    identity: Arc<HubIdentity>,
     public_url: String,
-    capabilities: Vec<String>,
+    capabilities: CapabilitySet,
     http_client: reqwest::Client,
     cached_hub_list: Arc<RwLock<Option<HubList>>>,
     last_registration: Arc<RwLock<Option<DateTime<Utc>>>>,
+    /// Primary hub keys by URL, pinned from their first signed response
+    primary_public_keys: Arc<RwLock<HashMap<String, String>>>,
 }
 
 impl DiscoveryClient {
-    /// Create a new discovery client
+    /// Create a new discovery client for the primary hubs at
+    /// `primary_hub_urls`, in order of preference
     pub fn new(
-        primary_hub_url: impl Into<String>,
-        hub_id: impl Into<String>,
+        primary_hub_urls: Vec<String>,
+        identity: Arc<HubIdentity>,
         public_url: impl Into<String>,
-        capabilities: Vec<String>,
+        capabilities: CapabilitySet,
     ) -> Self {
         let http_client = reqwest::Client::builder()
             .timeout(Duration::from_secs(30))
@@ -35,43 +72,122 @@ impl DiscoveryClient {
             .expect("Failed to create HTTP client");
 
         Self {
-            primary_hub_url: primary_hub_url.into(),
-            hub_id: hub_id.into(),
+            primary_hub_urls,
+            active_primary: Arc::new(RwLock::new(0)),
+            hub_id: identity.hub_id().to_string(),
+            identity,
             public_url: public_url.into(),
             capabilities,
             http_client,
             cached_hub_list: Arc::new(RwLock::new(None)),
             last_registration: Arc::new(RwLock::new(None)),
+            primary_public_keys: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
-    /// Register this hub with the primary hub
-    pub async fn register(&self, public_key: Option<&str>) -> HubResult<HubList> {
-        let url = format!("{}/api/v1/discovery/register", self.primary_hub_url);
+    /// URL of the primary hub currently talked to
+    pub fn active_primary_url(&self) -> Option<&str> {
+        self.primary_hub_urls
+            .get(*self.active_primary.read())
+            .map(String::as_str)
+    }
 
+    /// The key responses of the active primary hub are verified against
+    pub fn primary_public_key(&self) -> Option<String> {
+        let url = self.active_primary_url()?;
+        self.primary_public_keys.read().get(url).cloned()
+    }
+
+    /// Read a signed response from the primary hub at `primary_url`
+    async fn read_signed<T: DeserializeOwned>(&self, primary_url: &str, response: reqwest::Response) -> HubResult<T> {
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| HubError::NetworkError(e.to_string()))?;
+
+        let pinned = self.primary_public_keys.read().get(primary_url).cloned();
+        let (data, signature) = SignedResponse::from_slice(&body)?
+            .verified_data(None, pinned.as_deref())?;
+
+        if pinned.is_none() {
+            self.primary_public_keys.write().insert(primary_url.to_string(), signature.public_key);
+        }
+        Ok(data)
+    }
+
+    /// Send a request to the active primary hub, failing over to the others
+    /// in order while one is unreachable, answers with a server error or an
+    /// invalid signature.
+    ///
+    /// `request` builds the request for a primary's base URL.
+    async fn send_with_failover<T, F>(&self, action: &str, request: F) -> HubResult<T>
+    where
+        T: DeserializeOwned,
+        F: Fn(&str) -> reqwest::RequestBuilder,
+    {
+        let count = self.primary_hub_urls.len();
+        let start = *self.active_primary.read();
+        let mut last_error = HubError::FederationError("No primary hub configured".to_string());
+
+        for index in (0..count).map(|i| (start + i) % count) {
+            let primary_url = &self.primary_hub_urls[index];
+            let result = match request(primary_url).send().await {
+                Ok(response) if response.status().is_client_error() => {
+                    // The primary rejected the request itself; another one would too
+                    return Err(HubError::FederationError(format!(
+                        "{} failed: {}",
+                        action,
+                        response.status()
+                    )));
+                }
+                Ok(response) if !response.status().is_success() => Err(HubError::FederationError(format!(
+                    "{} failed: {}",
+                    action,
+                    response.status()
+                ))),
+                Ok(response) => self.read_signed(primary_url, response).await,
+                Err(e) => Err(HubError::NetworkError(e.to_string())),
+            };
+
+            match result {
+                Ok(data) => {
+                    if index != start {
+                        warn!("Failed over to primary hub {}", primary_url);
+                        *self.active_primary.write() = index;
+                    }
+                    return Ok(data);
+                }
+                Err(e) => {
+                    warn!("{} at primary hub {} failed: {}", action, primary_url, e);
+                    last_error = e;
+                }
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Use another signing identity (same hub_id)
+    pub fn with_identity(mut self, identity: Arc<HubIdentity>) -> Self {
+        self.identity = identity;
+        self
+    }
+
+    /// Register this hub with the primary hub
+    pub async fn register(&self) -> HubResult<HubList> {
+        let timestamp = Utc::now();
         let body = serde_json::json!({
             "hub_id": self.hub_id,
             "public_url": self.public_url,
             "capabilities": self.capabilities,
             "version": env!("CARGO_PKG_VERSION"),
-            "public_key": public_key,
+            "protocol_version": PROTOCOL_VERSION,
+            "public_key": self.identity.public_key(),
+            "timestamp": timestamp,
+            "signature": self.identity.sign_message(
+                &registration_message(&self.hub_id, &self.public_url, timestamp)
+            ),
         });
 
-        let response = self
-            .http_client
-            .post(&url)
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| HubError::NetworkError(e.to_string()))?;
-
-        if !response.status().is_success() {
-            return Err(HubError::FederationError(format!(
-                "Registration failed: {}",
-                response.status()
-            )));
-        }
-
         #[derive(serde::Deserialize)]
         struct RegisterResponse {
             registered: bool,
@@ -80,10 +196,13 @@ impl DiscoveryClient {
             hub_list: Option<HubList>,
         }
 
-        let result: RegisterResponse = response
-            .json()
-            .await
-            .map_err(|e| HubError::NetworkError(e.to_string()))?;
+        let result: RegisterResponse = self
+            .send_with_failover("Registration", |primary_url| {
+                self.http_client
+                    .post(format!("{}/api/v1/discovery/register", primary_url))
+                    .json(&body)
+            })
+            .await?;
 
         if !result.registered {
             return Err(HubError::FederationError("Registration rejected".to_string()));
@@ -101,28 +220,39 @@ impl DiscoveryClient {
             .ok_or_else(|| HubError::FederationError("No hub list in response".to_string()))
     }
 
-    /// Send heartbeat to primary hub
+    /// Send heartbeat to primary hub.
+    ///
+    /// After a failover the new primary may not know this hub yet; the
+    /// heartbeat then is not acknowledged and the hub has to register again.
     pub async fn heartbeat(&self, stats: HubStats) -> HubResult<()> {
-        let url = format!("{}/api/v1/discovery/heartbeat", self.primary_hub_url);
-
+        let timestamp = Utc::now();
         let body = serde_json::json!({
             "hub_id": self.hub_id,
             "status": "healthy",
             "stats": stats,
+            "timestamp": timestamp,
+            "signature": self.identity.sign_message(
+                &heartbeat_message(&self.hub_id, "healthy", &stats, timestamp)
+            ),
         });
 
-        let response = self
-            .http_client
-            .post(&url)
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| HubError::NetworkError(e.to_string()))?;
+        #[derive(serde::Deserialize)]
+        struct HeartbeatResponse {
+            acknowledged: bool,
+            message: Option<String>,
+        }
 
-        if !response.status().is_success() {
+        let result: HeartbeatResponse = self
+            .send_with_failover("Heartbeat", |primary_url| {
+                self.http_client
+                    .post(format!("{}/api/v1/discovery/heartbeat", primary_url))
+                    .json(&body)
+            })
+            .await?;
+        if !result.acknowledged {
             return Err(HubError::FederationError(format!(
-                "Heartbeat failed: {}",
-                response.status()
+                "Heartbeat not acknowledged: {}",
+                result.message.unwrap_or_default()
             )));
         }
 
@@ -131,26 +261,11 @@ impl DiscoveryClient {
 
     /// Refresh the hub list from primary hub
     pub async fn refresh_hub_list(&self) -> HubResult<HubList> {
-        let url = format!("{}/api/v1/discovery/hubs", self.primary_hub_url);
-
-        let response = self
-            .http_client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| HubError::NetworkError(e.to_string()))?;
-
-        if !response.status().is_success() {
-            return Err(HubError::FederationError(format!(
-                "Failed to get hub list: {}",
-                response.status()
-            )));
-        }
-
-        let list: HubList = response
-            .json()
-            .await
-            .map_err(|e| HubError::NetworkError(e.to_string()))?;
+        let list: HubList = self
+            .send_with_failover("Hub list refresh", |primary_url| {
+                self.http_client.get(format!("{}/api/v1/discovery/hubs", primary_url))
+            })
+            .await?;
 
         *self.cached_hub_list.write() = Some(list.clone());
 
@@ -162,7 +277,7 @@ impl DiscoveryClient {
         self.cached_hub_list.read().clone()
     }
 
-    /// Get other healthy hubs (excluding self)
+    /// Get other healthy or degraded hubs (excluding self)
     pub fn get_other_hubs(&self) -> Vec<HubInfo> {
         self.cached_hub_list
             .read()
@@ -170,13 +285,26 @@ impl DiscoveryClient {
             .map(|list| {
                 list.hubs
                     .iter()
-                    .filter(|h| h.hub_id != self.hub_id && h.status == HubStatus::Healthy)
+                    .filter(|h| {
+                        h.hub_id != self.hub_id
+                            && matches!(h.status, HubStatus::Healthy | HubStatus::Degraded)
+                    })
                     .cloned()
                     .collect()
             })
             .unwrap_or_default()
     }
 
+    /// When this hub last registered with the primary
+    pub fn last_registration(&self) -> Option<DateTime<Utc>> {
+        *self.last_registration.read()
+    }
+
+    /// Forget the registration so the next check registers again
+    pub fn reset_registration(&self) {
+        *self.last_registration.write() = None;
+    }
+
     /// Check if we need to re-register
     pub fn needs_registration(&self, registration_interval_sec: u64) -> bool {
         match *self.last_registration.read() {
@@ -192,13 +320,16 @@ impl DiscoveryClient {
 impl Clone for DiscoveryClient {
     fn clone(&self) -> Self {
         Self {
-            primary_hub_url: self.primary_hub_url.clone(),
+            primary_hub_urls: self.primary_hub_urls.clone(),
+            active_primary: Arc::clone(&self.active_primary),
             hub_id: self.hub_id.clone(),
+            identity: Arc::clone(&self.identity),
             public_url: self.public_url.clone(),
             capabilities: self.capabilities.clone(),
             http_client: self.http_client.clone(),
             cached_hub_list: Arc::clone(&self.cached_hub_list),
             last_registration: Arc::clone(&self.last_registration),
+            primary_public_keys: Arc::clone(&self.primary_public_keys),
         }
     }
 }
@@ -206,7 +337,7 @@ impl Clone for DiscoveryClient {
 impl std::fmt::Debug for DiscoveryClient {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("DiscoveryClient")
-            .field("primary_hub_url", &self.primary_hub_url)
+            .field("primary_hub_urls", &self.primary_hub_urls)
             .field("hub_id", &self.hub_id)
             .finish()
     }
@@ -0,0 +1,141 @@
+//! Signed responses received from other hubs
+
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::crypto::HubSignature;
+use crate::models::{HubError, HubResult};
+
+/// Signed requests and responses older or further in the future than this
+/// are rejected
+pub const MAX_REQUEST_AGE_SECS: i64 = 300;
+
+/// Reject signatures outside the accepted clock window
+pub fn check_request_time(timestamp: DateTime<Utc>) -> HubResult<()> {
+    let age = Utc::now().signed_duration_since(timestamp).num_seconds();
+    if age.abs() > MAX_REQUEST_AGE_SECS {
+        return Err(HubError::Unauthorized(format!(
+            "Request timestamp is outside the {}s window",
+            MAX_REQUEST_AGE_SECS
+        )));
+    }
+    Ok(())
+}
+
+/// The `ApiResponse` envelope as read by a peer hub
+#[derive(Debug, Deserialize)]
+pub struct SignedResponse {
+    pub success: bool,
+    #[serde(default)]
+    pub data: Option<Value>,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub signature: Option<HubSignature>,
+}
+
+impl SignedResponse {
+    /// Parse a response body
+    pub fn from_slice(body: &[u8]) -> HubResult<Self> {
+        serde_json::from_slice(body)
+            .map_err(|e| HubError::FederationError(format!("Malformed hub response: {}", e)))
+    }
+
+    /// Verify the hub signature and extract the data.
+    ///
+    /// `hub_id` and `public_key` are what the sender is known by, if anything;
+    /// a response signed under another identity is rejected, as is one signed
+    /// outside the clock window (a replayed response).
+    pub fn verified_data<T: DeserializeOwned>(
+        self,
+        hub_id: Option<&str>,
+        public_key: Option<&str>,
+    ) -> HubResult<(T, HubSignature)> {
+        if !self.success {
+            return Err(HubError::FederationError(
+                self.error.unwrap_or_else(|| "Hub returned an error".to_string()),
+            ));
+        }
+        let data = self.data
+            .ok_or_else(|| HubError::FederationError("No data in hub response".to_string()))?;
+        let signature = self.signature
+            .ok_or_else(|| HubError::invalid_signature("hub response"))?;
+
+        if hub_id.is_some_and(|id| id != signature.hub_id)
+            || public_key.is_some_and(|key| key != signature.public_key)
+            || !signature.verify(&data)?
+        {
+            return Err(HubError::invalid_signature("hub response"));
+        }
+        check_request_time(signature.signed_at)?;
+
+        let data = serde_json::from_value(data)
+            .map_err(|e| HubError::FederationError(format!("Malformed hub response: {}", e)))?;
+        Ok((data, signature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{canonical_json, HubIdentity};
+    use serde_json::json;
+
+    fn envelope(identity: &HubIdentity, data: Value) -> Vec<u8> {
+        let signature = identity.sign(&data).unwrap();
+        serde_json::to_vec(&json!({
+            "success": true,
+            "data": data,
+            "signature": signature,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_verified_data() {
+        let identity = HubIdentity::generate("hub-1");
+        let body = envelope(&identity, json!({ "count": 3 }));
+        let key = identity.public_key();
+
+        let (data, signature) = SignedResponse::from_slice(&body)
+            .unwrap()
+            .verified_data::<Value>(Some("hub-1"), Some(&key))
+            .unwrap();
+        assert_eq!(data["count"], 3);
+        assert_eq!(signature.hub_id, "hub-1");
+
+        // Signed by another hub than expected
+        let other = HubIdentity::generate("hub-1");
+        let result = SignedResponse::from_slice(&body)
+            .unwrap()
+            .verified_data::<Value>(None, Some(&other.public_key()));
+        assert!(result.is_err());
+
+        // Data altered after signing
+        let mut forged: Value = serde_json::from_slice(&body).unwrap();
+        forged["data"]["count"] = json!(4);
+        let result = SignedResponse::from_slice(&serde_json::to_vec(&forged).unwrap())
+            .unwrap()
+            .verified_data::<Value>(None, None);
+        assert!(result.is_err());
+
+        // Replayed long after signing
+        let data = json!({ "count": 3 });
+        let mut signature = identity.sign(&data).unwrap();
+        signature.signed_at -= chrono::Duration::seconds(MAX_REQUEST_AGE_SECS + 60);
+        let payload = HubSignature::signing_payload("hub-1", signature.signed_at, &data);
+        signature.signature = identity.sign_message(&canonical_json(&payload));
+        let body = serde_json::to_vec(&json!({
+            "success": true,
+            "data": data,
+            "signature": signature,
+        }))
+        .unwrap();
+        let result = SignedResponse::from_slice(&body)
+            .unwrap()
+            .verified_data::<Value>(Some("hub-1"), Some(&key));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,589 @@
+//! Peer table for gossip discovery
+//!
+//! Without a primary, every hub signs its own `HubInfo` and passes on the
+//! entries it knows about. Entries are only accepted with a valid signature
+//! by the hub they describe, so peers can relay but not forge them.
+
+use chrono::Utc;
+use parking_lot::RwLock;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+use super::{HubInfo, HubList, HubPin, HubStatus};
+use crate::crypto::{HubIdentity, HubSignature};
+use crate::models::{HubError, HubResult};
+use crate::store::EntityStore;
+
+/// Entries dated further in the future than this are rejected
+const MAX_CLOCK_SKEW_SECS: i64 = 300;
+/// Most entries accepted from or sent to a peer in one exchange
+pub const MAX_GOSSIP_ENTRIES: usize = 1000;
+/// Most peers kept in a peer table
+const MAX_PEERS: usize = 1000;
+/// Most peers kept whose URL is not verified; a new one replaces the one
+/// seen longest ago
+const MAX_UNVERIFIED_PEERS: usize = 100;
+
+/// A hub's description of itself, signed with its own key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "SignedEntry", into = "SignedEntry")]
+pub struct SignedHubInfo {
+    /// `last_seen` is when the hub signed the entry
+    pub info: HubInfo,
+    pub signature: HubSignature,
+    /// `info` as the hub signed it. Verified and relayed unchanged, so
+    /// fields one build adds or drops do not break the signature for another.
+    signed_info: serde_json::Value,
+}
+
+/// Wire form of a `SignedHubInfo`
+#[derive(Serialize, Deserialize)]
+struct SignedEntry {
+    info: serde_json::Value,
+    signature: HubSignature,
+}
+
+impl TryFrom<SignedEntry> for SignedHubInfo {
+    type Error = serde_json::Error;
+
+    fn try_from(entry: SignedEntry) -> Result<Self, Self::Error> {
+        Ok(Self {
+            info: serde_json::from_value(entry.info.clone())?,
+            signature: entry.signature,
+            signed_info: entry.info,
+        })
+    }
+}
+
+impl From<SignedHubInfo> for SignedEntry {
+    fn from(signed: SignedHubInfo) -> Self {
+        Self { info: signed.signed_info, signature: signed.signature }
+    }
+}
+
+impl SignedHubInfo {
+    /// Sign this hub's own info
+    pub fn sign(mut info: HubInfo, identity: &HubIdentity) -> HubResult<Self> {
+        info.status = HubStatus::Healthy;
+        info.public_key = Some(identity.public_key());
+        let signed_info = serde_json::to_value(&info)
+            .map_err(|e| HubError::SerializationError(e.to_string()))?;
+        let signature = identity.sign(&signed_info)?;
+        Ok(Self { info, signature, signed_info })
+    }
+
+    /// Check that the entry is signed by the hub it describes and that
+    /// `info` is what it signed
+    pub fn verify(&self) -> HubResult<()> {
+        let parsed: HubInfo = serde_json::from_value(self.signed_info.clone())
+            .map_err(|e| HubError::SerializationError(e.to_string()))?;
+        let matches = serde_json::to_value(&parsed).ok() == serde_json::to_value(&self.info).ok();
+        if !matches
+            || self.signature.hub_id != self.info.hub_id
+            || self.info.public_key.as_deref() != Some(self.signature.public_key.as_str())
+            || !self.signature.verify(&self.signed_info)?
+        {
+            return Err(HubError::invalid_signature("hub info"));
+        }
+        Ok(())
+    }
+}
+
+/// Body of a gossip exchange, in both directions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GossipMessage {
+    /// The sender's own entry first, then the entries it knows about
+    pub entries: Vec<SignedHubInfo>,
+}
+
+/// A peer as known to this hub
+#[derive(Debug, Clone)]
+struct Peer {
+    signed: SignedHubInfo,
+    /// Whether this hub exchanged with the peer at its URL directly
+    verified: bool,
+}
+
+/// Peers known to a hub in gossip mode
+///
+/// Anyone can send entries, so the table is capped and unverified peers
+/// only get a small share of it.
+#[derive(Debug)]
+pub struct PeerTable {
+    self_id: String,
+    peers: RwLock<HashMap<String, Peer>>,
+    /// Keys by hub_id, with the `last_seen` of the newest entry. Pins of
+    /// verified peers outlive their entry so a hub_id cannot be taken over
+    /// once proven, and are persisted with a store attached.
+    pins: RwLock<HashMap<String, HubPin>>,
+    version: RwLock<u64>,
+    peer_timeout_sec: u64,
+    store: Option<Arc<EntityStore>>,
+}
+
+impl PeerTable {
+    /// Create an empty peer table for the hub `self_id`
+    pub fn new(self_id: impl Into<String>, peer_timeout_sec: u64) -> Self {
+        Self {
+            self_id: self_id.into(),
+            peers: RwLock::new(HashMap::new()),
+            pins: RwLock::new(HashMap::new()),
+            version: RwLock::new(0),
+            peer_timeout_sec,
+            store: None,
+        }
+    }
+
+    /// Restore the pins of verified peers and keep persisting them to the
+    /// store
+    pub fn load(self_id: impl Into<String>, peer_timeout_sec: u64, store: Arc<EntityStore>) -> HubResult<Self> {
+        let mut pins = HashMap::new();
+        for (hub_id, value) in store.list_hub_pins()? {
+            let pin: HubPin = serde_json::from_slice(&value)?;
+            if pin.verified {
+                pins.insert(hub_id, pin);
+            }
+        }
+        debug!("Restored {} peer key pins", pins.len());
+
+        Ok(Self {
+            pins: RwLock::new(pins),
+            store: Some(store),
+            ..Self::new(self_id, peer_timeout_sec)
+        })
+    }
+
+    /// Drop the pin of a removed peer unless it was verified
+    fn forget_unverified_pin(&self, pins: &mut HashMap<String, HubPin>, hub_id: &str) {
+        if pins.get(hub_id).is_some_and(|pin| !pin.verified) {
+            pins.remove(hub_id);
+        }
+    }
+
+    /// Make room for a new, unverified peer.
+    ///
+    /// Removes the unverified peer seen longest ago once their limit or the
+    /// table's is reached. Returns false if the table is full of verified
+    /// peers.
+    fn make_room(&self, peers: &mut HashMap<String, Peer>, pins: &mut HashMap<String, HubPin>) -> bool {
+        let unverified = peers.values().filter(|peer| !peer.verified).count();
+        if unverified < MAX_UNVERIFIED_PEERS && peers.len() < MAX_PEERS {
+            return true;
+        }
+        let oldest = peers
+            .values()
+            .filter(|peer| !peer.verified)
+            .min_by_key(|peer| peer.signed.info.last_seen)
+            .map(|peer| peer.signed.info.hub_id.clone());
+        match oldest {
+            Some(hub_id) => {
+                peers.remove(&hub_id);
+                self.forget_unverified_pin(pins, &hub_id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Merge an entry received from a peer.
+    ///
+    /// Returns whether it was newer than the known one. Rejects forged
+    /// entries, keys other than the pinned one and entries from the future;
+    /// skips new peers while the table is full.
+    pub fn merge(&self, entry: SignedHubInfo) -> HubResult<bool> {
+        if entry.info.hub_id == self.self_id {
+            return Ok(false);
+        }
+        entry.verify()?;
+        if entry.info.last_seen > Utc::now() + chrono::Duration::seconds(MAX_CLOCK_SKEW_SECS) {
+            return Err(HubError::ValidationError(format!(
+                "Entry for hub {} is dated in the future",
+                entry.info.hub_id
+            )));
+        }
+
+        let mut pins = self.pins.write();
+        let mut peers = self.peers.write();
+        let hub_id = entry.info.hub_id.clone();
+        let pinned_verified = match pins.get(&hub_id) {
+            Some(pin) if pin.public_key != entry.signature.public_key => {
+                return Err(HubError::Unauthorized(format!(
+                    "Hub {} is known with a different key",
+                    hub_id
+                )));
+            }
+            Some(pin) => pin.verified,
+            None => false,
+        };
+
+        let verified = match peers.get(&hub_id) {
+            Some(known) if entry.info.last_seen <= known.signed.info.last_seen => return Ok(false),
+            // A moved hub has to prove its new URL again
+            Some(known) => known.verified && known.signed.info.public_url == entry.info.public_url,
+            // Relays do not bring back a pruned peer's old entries
+            None if pins.get(&hub_id).is_some_and(|pin| entry.info.last_seen <= pin.last_request_at) => {
+                return Ok(false)
+            }
+            None => {
+                if !self.make_room(&mut peers, &mut pins) {
+                    return Ok(false);
+                }
+                false
+            }
+        };
+        pins.insert(hub_id.clone(), HubPin {
+            public_key: entry.signature.public_key.clone(),
+            last_request_at: entry.info.last_seen,
+            verified: pinned_verified,
+        });
+        peers.insert(hub_id, Peer { signed: entry, verified });
+        *self.version.write() += 1;
+        Ok(true)
+    }
+
+    /// Merge entries received from a peer, skipping invalid ones.
+    ///
+    /// Returns the number of entries that changed this hub's view.
+    pub fn merge_all(&self, entries: Vec<SignedHubInfo>) -> usize {
+        let mut updated = 0;
+        for entry in entries.into_iter().take(MAX_GOSSIP_ENTRIES) {
+            let hub_id = entry.info.hub_id.clone();
+            match self.merge(entry) {
+                Ok(true) => updated += 1,
+                Ok(false) => {}
+                Err(e) => debug!("Ignoring gossip entry for hub {}: {}", hub_id, e),
+            }
+        }
+        updated
+    }
+
+    /// Record that a peer answered at its URL with a response signed by its key
+    pub fn mark_verified(&self, hub_id: &str, public_url: &str, public_key: &str) -> bool {
+        let mut pins = self.pins.write();
+        let mut peers = self.peers.write();
+        match peers.get_mut(hub_id) {
+            Some(peer)
+                if same_url(&peer.signed.info.public_url, public_url)
+                    && peer.signed.signature.public_key == public_key =>
+            {
+                peer.verified = true;
+                if let Some(pin) = pins.get_mut(hub_id).filter(|pin| !pin.verified) {
+                    pin.verified = true;
+                    self.persist_pin(hub_id, pin);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Write a verified pin through to the store
+    fn persist_pin(&self, hub_id: &str, pin: &HubPin) {
+        if let Some(ref store) = self.store {
+            let result = serde_json::to_vec(pin)
+                .map_err(HubError::from)
+                .and_then(|value| store.put_hub_pin(hub_id, &value));
+            if let Err(e) = result {
+                warn!("Failed to persist key pin of peer {}: {}", hub_id, e);
+            }
+        }
+    }
+
+    /// Signed entries to pass on to peers
+    pub fn entries(&self) -> Vec<SignedHubInfo> {
+        self.peers
+            .read()
+            .values()
+            .take(MAX_GOSSIP_ENTRIES - 1)
+            .map(|peer| peer.signed.clone())
+            .collect()
+    }
+
+    /// Known peers with their status as seen from this hub.
+    ///
+    /// Peers whose URL is not verified yet are unknown, peers without a fresh
+    /// entry within the timeout inactive (e.g. across a partition).
+    pub fn hubs(&self) -> Vec<HubInfo> {
+        let cutoff = Utc::now() - chrono::Duration::seconds(self.peer_timeout_sec as i64);
+        self.peers
+            .read()
+            .values()
+            .map(|peer| {
+                let mut info = peer.signed.info.clone();
+                info.status = if !peer.verified {
+                    HubStatus::Unknown
+                } else if info.last_seen < cutoff {
+                    HubStatus::Inactive
+                } else {
+                    HubStatus::Healthy
+                };
+                info
+            })
+            .collect()
+    }
+
+    /// Get only healthy peers
+    pub fn list_healthy(&self) -> Vec<HubInfo> {
+        self.hubs()
+            .into_iter()
+            .filter(|h| h.status == HubStatus::Healthy)
+            .collect()
+    }
+
+    /// Known peers as a hub list
+    pub fn list(&self) -> HubList {
+        HubList {
+            hubs: self.hubs(),
+            version: *self.version.read(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Pick up to `count` random peers to gossip with, verified ones first.
+    ///
+    /// One of several slots is left to an unverified peer, if any, so new
+    /// hubs get verified while entries flooded in by one sender cannot crowd
+    /// out the known peers. Inactive peers stay candidates so the network
+    /// heals once a partition ends.
+    pub fn sample(&self, count: usize) -> Vec<HubInfo> {
+        let mut rng = rand::thread_rng();
+        let (mut verified, mut unverified): (Vec<HubInfo>, Vec<HubInfo>) = self
+            .hubs()
+            .into_iter()
+            .partition(|hub| hub.status != HubStatus::Unknown);
+        verified.shuffle(&mut rng);
+        unverified.shuffle(&mut rng);
+
+        let reserved = usize::from(count > 1 && !unverified.is_empty());
+        verified.truncate(count - reserved);
+        let missing = count - verified.len();
+        verified.extend(unverified.into_iter().take(missing));
+        verified
+    }
+
+    /// Number of known peers
+    pub fn len(&self) -> usize {
+        self.peers.read().len()
+    }
+
+    /// Check if no peers are known
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove peers not seen within the retention period, and unverified
+    /// ones not seen within the peer timeout.
+    ///
+    /// Key pins of verified peers are kept. Returns the removed hub IDs.
+    pub fn prune(&self, retention_sec: u64) -> Vec<String> {
+        let now = Utc::now();
+        let cutoff = now - chrono::Duration::seconds(retention_sec as i64);
+        let unverified_cutoff = now - chrono::Duration::seconds(retention_sec.min(self.peer_timeout_sec) as i64);
+        let mut pins = self.pins.write();
+        let mut peers = self.peers.write();
+        let expired: Vec<String> = peers
+            .values()
+            .filter(|peer| {
+                let last_seen = peer.signed.info.last_seen;
+                last_seen < cutoff || (!peer.verified && last_seen < unverified_cutoff)
+            })
+            .map(|peer| peer.signed.info.hub_id.clone())
+            .collect();
+        for hub_id in &expired {
+            peers.remove(hub_id);
+            self.forget_unverified_pin(&mut pins, hub_id);
+        }
+        if !expired.is_empty() {
+            *self.version.write() += 1;
+        }
+        expired
+    }
+}
+
+/// Compare hub URLs ignoring a trailing slash
+pub fn same_url(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/') == b.trim_end_matches('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+    use crate::discovery::{CapabilitySet, HubStats, PROTOCOL_VERSION};
+
+    fn entry(identity: &HubIdentity, url: &str, last_seen: DateTime<Utc>) -> SignedHubInfo {
+        let info = HubInfo {
+            hub_id: identity.hub_id().to_string(),
+            public_url: url.to_string(),
+            role: "secondary".to_string(),
+            status: HubStatus::Healthy,
+            last_seen,
+            capabilities: CapabilitySet::from_names(["search"]),
+            stats: HubStats::default(),
+            public_key: None,
+            protocol_version: PROTOCOL_VERSION,
+            version: None,
+            verified: false,
+        };
+        SignedHubInfo::sign(info, identity).unwrap()
+    }
+
+    #[test]
+    fn test_merge_rejects_forged_and_stale_entries() {
+        let table = PeerTable::new("hub-self", 900);
+        let identity = HubIdentity::generate("hub-1");
+        let now = Utc::now();
+
+        assert!(table.merge(entry(&identity, "http://hub-1", now)).unwrap());
+        // Older or repeated entries do not replace the known one
+        assert!(!table.merge(entry(&identity, "http://hub-1", now - chrono::Duration::seconds(10))).unwrap());
+
+        // Altered by a relaying peer
+        let mut forged = entry(&identity, "http://hub-1", now + chrono::Duration::seconds(1));
+        forged.info.public_url = "http://evil".to_string();
+        assert!(table.merge(forged).is_err());
+
+        // Same hub_id under another key
+        let impostor = HubIdentity::generate("hub-1");
+        assert!(table.merge(entry(&impostor, "http://evil", now + chrono::Duration::seconds(1))).is_err());
+
+        // Own entry is never taken from peers
+        let own = HubIdentity::generate("hub-self");
+        assert!(!table.merge(entry(&own, "http://evil", now)).unwrap());
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.hubs()[0].public_url, "http://hub-1");
+    }
+
+    #[test]
+    fn test_peer_status() {
+        let table = PeerTable::new("hub-self", 60);
+        let identity = HubIdentity::generate("hub-1");
+        let key = identity.public_key();
+        let now = Utc::now();
+
+        // Learned through gossip only
+        table.merge(entry(&identity, "http://hub-1", now)).unwrap();
+        assert_eq!(table.hubs()[0].status, HubStatus::Unknown);
+        assert!(table.list_healthy().is_empty());
+
+        assert!(!table.mark_verified("hub-1", "http://other", &key));
+        assert!(table.mark_verified("hub-1", "http://hub-1/", &key));
+        assert_eq!(table.list_healthy().len(), 1);
+
+        // Moving to another URL needs a new verification
+        table.merge(entry(&identity, "http://hub-1b", now + chrono::Duration::seconds(1))).unwrap();
+        assert_eq!(table.hubs()[0].status, HubStatus::Unknown);
+        table.mark_verified("hub-1", "http://hub-1b", &key);
+
+        // No fresh entry within the timeout, e.g. during a partition
+        let stale = HubIdentity::generate("hub-2");
+        table.merge(entry(&stale, "http://hub-2", now - chrono::Duration::seconds(120))).unwrap();
+        table.mark_verified("hub-2", "http://hub-2", &stale.public_key());
+        let status = |id: &str| table.hubs().into_iter().find(|h| h.hub_id == id).unwrap().status;
+        assert_eq!(status("hub-2"), HubStatus::Inactive);
+        assert_eq!(table.sample(10).len(), 2);
+
+        // A fresh entry after the partition heals makes it healthy again
+        table.merge(entry(&stale, "http://hub-2", now)).unwrap();
+        assert_eq!(status("hub-2"), HubStatus::Healthy);
+
+        let expired = HubIdentity::generate("hub-3");
+        table.merge(entry(&expired, "http://hub-3", now - chrono::Duration::seconds(120))).unwrap();
+        table.mark_verified("hub-3", "http://hub-3", &expired.public_key());
+        // Unverified and past the peer timeout
+        let unproven = HubIdentity::generate("hub-4");
+        table.merge(entry(&unproven, "http://hub-4", now - chrono::Duration::seconds(70))).unwrap();
+
+        let mut pruned = table.prune(90);
+        pruned.sort();
+        assert_eq!(pruned, vec!["hub-3".to_string(), "hub-4".to_string()]);
+        assert_eq!(table.len(), 2);
+        // The verified pin outlives the entry, and relays cannot bring the
+        // entry back
+        let impostor = HubIdentity::generate("hub-3");
+        assert!(table.merge(entry(&impostor, "http://evil", now)).is_err());
+        assert!(!table.merge(entry(&expired, "http://hub-3", now - chrono::Duration::seconds(120))).unwrap());
+        // The unverified one goes with its entry
+        let successor = HubIdentity::generate("hub-4");
+        assert!(table.merge(entry(&successor, "http://hub-4", now)).unwrap());
+    }
+
+    #[test]
+    fn test_table_is_capped() {
+        let table = PeerTable::new("hub-self", 900);
+        let now = Utc::now();
+
+        for i in 0..3 {
+            let known = HubIdentity::generate(format!("known-{}", i));
+            let url = format!("http://known-{}", i);
+            table.merge(entry(&known, &url, now)).unwrap();
+            table.mark_verified(known.hub_id(), &url, &known.public_key());
+        }
+
+        // One sender flooding the table with self-signed hubs
+        for i in 0..MAX_UNVERIFIED_PEERS + 10 {
+            let identity = HubIdentity::generate(format!("flood-{}", i));
+            let last_seen = now - chrono::Duration::seconds((MAX_UNVERIFIED_PEERS + 10 - i) as i64);
+            assert!(table.merge(entry(&identity, "http://evil", last_seen)).unwrap());
+        }
+        assert_eq!(table.len(), MAX_UNVERIFIED_PEERS + 3);
+        // The oldest unverified peers made room, the verified ones stayed
+        assert!(table.hubs().iter().all(|hub| hub.hub_id != "flood-0"));
+        assert_eq!(table.list_healthy().len(), 3);
+
+        // Verified peers come first, with one slot left to an unverified one
+        for _ in 0..10 {
+            let sample = table.sample(3);
+            assert_eq!(sample.len(), 3);
+            assert!(sample[..2].iter().all(|hub| hub.hub_id.starts_with("known-")));
+            assert_eq!(sample[2].status, HubStatus::Unknown);
+            assert_eq!(table.sample(1)[0].status, HubStatus::Healthy);
+        }
+    }
+
+    #[test]
+    fn test_verified_pins_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let rocks = crate::store::RocksStore::open(dir.path().to_str().unwrap()).unwrap();
+        let store = Arc::new(EntityStore::new(rocks));
+        let now = Utc::now();
+
+        let verified = HubIdentity::generate("hub-1");
+        let unverified = HubIdentity::generate("hub-2");
+        {
+            let table = PeerTable::load("hub-self", 900, Arc::clone(&store)).unwrap();
+            table.merge(entry(&verified, "http://hub-1", now)).unwrap();
+            table.merge(entry(&unverified, "http://hub-2", now)).unwrap();
+            table.mark_verified("hub-1", "http://hub-1", &verified.public_key());
+        }
+
+        let table = PeerTable::load("hub-self", 900, store).unwrap();
+        assert!(table.is_empty());
+        let impostor = HubIdentity::generate("hub-1");
+        assert!(table.merge(entry(&impostor, "http://evil", now)).is_err());
+        let other = HubIdentity::generate("hub-2");
+        assert!(table.merge(entry(&other, "http://hub-2", now)).unwrap());
+    }
+
+    #[test]
+    fn test_entries_from_other_builds_verify_and_relay_unchanged() {
+        let identity = HubIdentity::generate("hub-1");
+        let mut info = serde_json::to_value(entry(&identity, "http://hub-1", Utc::now()).info).unwrap();
+        // Signed by a build without `version` and with a field unknown here
+        info.as_object_mut().unwrap().remove("version");
+        info["region"] = serde_json::json!("eu-west");
+        let signature = identity.sign(&info).unwrap();
+        let wire = serde_json::json!({ "info": info, "signature": signature });
+
+        let received: SignedHubInfo = serde_json::from_value(wire.clone()).unwrap();
+        received.verify().unwrap();
+        assert_eq!(received.info.version, None);
+
+        let relayed: SignedHubInfo = serde_json::from_value(serde_json::to_value(&received).unwrap()).unwrap();
+        relayed.verify().unwrap();
+        assert_eq!(serde_json::to_value(&relayed).unwrap(), wire);
+    }
+}
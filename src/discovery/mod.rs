@@ -4,9 +4,17 @@
 //! Will be fully implemented in Phase 5.
 
 mod registry;
+mod capabilities;
 mod client;
 mod federation;
+mod envelope;
+mod gossip;
+mod probe;
 
 pub use registry::*;
+pub use capabilities::*;
 pub use client::*;
 pub use federation::*;
+pub use envelope::*;
+pub use gossip::*;
+pub use probe::*;
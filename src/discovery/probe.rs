@@ -0,0 +1,210 @@
+//! Active health probing of federation targets
+//!
+//! Keeps a sliding window of `/health` probe results per hub and derives a
+//! status from latency percentiles and the error rate.
+
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+
+use super::HubStatus;
+
+/// Probe results kept per hub
+pub const PROBE_WINDOW: usize = 20;
+/// Consecutive failed probes after which a hub counts as inactive
+pub const INACTIVE_AFTER_FAILURES: u32 = 3;
+/// Share of failed probes in the window from which a hub is degraded
+pub const DEGRADED_ERROR_RATE: f64 = 0.2;
+
+/// One probe result; `None` latency for a failed probe
+#[derive(Debug, Clone, Copy)]
+struct ProbeSample {
+    latency: Option<Duration>,
+}
+
+#[derive(Debug, Default)]
+struct ProbeWindow {
+    samples: VecDeque<ProbeSample>,
+    consecutive_failures: u32,
+    last_probe_at: Option<DateTime<Utc>>,
+}
+
+/// Probe statistics of one hub
+#[derive(Debug, Clone, Serialize)]
+pub struct HubHealth {
+    pub hub_id: String,
+    pub status: HubStatus,
+    /// Probes in the window
+    pub probes: usize,
+    pub error_rate: f64,
+    pub consecutive_failures: u32,
+    /// Latency percentiles of successful probes in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p50_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p95_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p99_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_probe_at: Option<DateTime<Utc>>,
+}
+
+/// Probe results of the hubs this hub federates with
+#[derive(Debug)]
+pub struct HubHealthTracker {
+    windows: RwLock<HashMap<String, ProbeWindow>>,
+    /// p95 latency above which a hub is degraded
+    degraded_latency: Duration,
+}
+
+impl HubHealthTracker {
+    /// Create a tracker marking hubs degraded above `degraded_latency` (p95)
+    pub fn new(degraded_latency: Duration) -> Self {
+        Self {
+            windows: RwLock::new(HashMap::new()),
+            degraded_latency,
+        }
+    }
+
+    /// Record a probe: its latency, or `None` if it failed
+    pub fn record(&self, hub_id: &str, latency: Option<Duration>) {
+        let mut windows = self.windows.write();
+        let window = windows.entry(hub_id.to_string()).or_default();
+        if window.samples.len() == PROBE_WINDOW {
+            window.samples.pop_front();
+        }
+        window.samples.push_back(ProbeSample { latency });
+        window.consecutive_failures = match latency {
+            Some(_) => 0,
+            None => window.consecutive_failures + 1,
+        };
+        window.last_probe_at = Some(Utc::now());
+    }
+
+    /// Statistics of a probed hub
+    pub fn health(&self, hub_id: &str) -> Option<HubHealth> {
+        self.windows
+            .read()
+            .get(hub_id)
+            .map(|window| self.summarize(hub_id, window))
+    }
+
+    /// Statistics of all probed hubs, by hub_id
+    pub fn all(&self) -> Vec<HubHealth> {
+        let mut all: Vec<HubHealth> = self.windows
+            .read()
+            .iter()
+            .map(|(hub_id, window)| self.summarize(hub_id, window))
+            .collect();
+        all.sort_by(|a, b| a.hub_id.cmp(&b.hub_id));
+        all
+    }
+
+    /// Forget hubs other than `hub_ids`
+    pub fn retain(&self, hub_ids: &HashSet<String>) {
+        self.windows.write().retain(|hub_id, _| hub_ids.contains(hub_id));
+    }
+
+    fn summarize(&self, hub_id: &str, window: &ProbeWindow) -> HubHealth {
+        let mut latencies: Vec<f64> = window.samples
+            .iter()
+            .filter_map(|s| s.latency)
+            .map(|l| l.as_secs_f64() * 1000.0)
+            .collect();
+        latencies.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        let probes = window.samples.len();
+        let error_rate = if probes == 0 {
+            0.0
+        } else {
+            (probes - latencies.len()) as f64 / probes as f64
+        };
+        let p95_ms = percentile(&latencies, 95.0);
+
+        let status = if window.consecutive_failures >= INACTIVE_AFTER_FAILURES {
+            HubStatus::Inactive
+        } else if error_rate >= DEGRADED_ERROR_RATE
+            || p95_ms.is_some_and(|p95| p95 > self.degraded_latency.as_secs_f64() * 1000.0)
+        {
+            HubStatus::Degraded
+        } else {
+            HubStatus::Healthy
+        };
+
+        HubHealth {
+            hub_id: hub_id.to_string(),
+            status,
+            probes,
+            error_rate,
+            consecutive_failures: window.consecutive_failures,
+            p50_ms: percentile(&latencies, 50.0),
+            p95_ms,
+            p99_ms: percentile(&latencies, 99.0),
+            last_probe_at: window.last_probe_at,
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[f64], p: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        let values: Vec<f64> = (1..=20).map(f64::from).collect();
+        assert_eq!(percentile(&values, 50.0), Some(10.0));
+        assert_eq!(percentile(&values, 95.0), Some(19.0));
+        assert_eq!(percentile(&values, 99.0), Some(20.0));
+        assert_eq!(percentile(&[], 50.0), None);
+    }
+
+    #[test]
+    fn test_status_from_probes() {
+        let tracker = HubHealthTracker::new(Duration::from_millis(500));
+        let ms = Duration::from_millis;
+
+        for _ in 0..10 {
+            tracker.record("fast", Some(ms(20)));
+            tracker.record("slow", Some(ms(800)));
+        }
+        assert_eq!(tracker.health("fast").unwrap().status, HubStatus::Healthy);
+        assert_eq!(tracker.health("fast").unwrap().p50_ms, Some(20.0));
+        assert_eq!(tracker.health("slow").unwrap().status, HubStatus::Degraded);
+
+        // Occasional errors degrade, a run of them makes the hub inactive
+        tracker.record("fast", None);
+        tracker.record("fast", None);
+        let health = tracker.health("fast").unwrap();
+        assert_eq!(health.status, HubStatus::Healthy);
+        tracker.record("fast", None);
+        let health = tracker.health("fast").unwrap();
+        assert_eq!(health.status, HubStatus::Inactive);
+        assert_eq!(health.consecutive_failures, 3);
+
+        // Recovers, but stays degraded while errors fill the window
+        tracker.record("fast", Some(ms(20)));
+        assert_eq!(tracker.health("fast").unwrap().status, HubStatus::Degraded);
+        for _ in 0..PROBE_WINDOW {
+            tracker.record("fast", Some(ms(20)));
+        }
+        let health = tracker.health("fast").unwrap();
+        assert_eq!(health.status, HubStatus::Healthy);
+        assert_eq!(health.probes, PROBE_WINDOW);
+        assert_eq!(health.error_rate, 0.0);
+
+        tracker.retain(&HashSet::from(["slow".to_string()]));
+        assert!(tracker.health("fast").is_none());
+        assert_eq!(tracker.all().len(), 1);
+    }
+}
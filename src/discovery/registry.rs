@@ -1,12 +1,19 @@
-//! Hub registry for primary hub
+//! Hub registry for primary hubs
 //!
-//! Will be fully implemented in Phase 5.
+//! Keeps the registered hubs and the keys their hub_ids are pinned to,
+//! persisted in the store when one is attached. A vector clock over the
+//! primaries' versions tells which registry snapshots hold news.
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, SubsecRound, Utc};
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
+use tracing::{debug, warn};
+
+use super::{legacy_protocol_version, CapabilitySet};
+use crate::models::{HubError, HubResult};
+use crate::store::EntityStore;
 
 /// Information about a registered hub
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,9 +23,19 @@ pub struct HubInfo {
     pub role: String,
     pub status: HubStatus,
     pub last_seen: DateTime<Utc>,
-    pub capabilities: Vec<String>,
+    pub capabilities: CapabilitySet,
     pub stats: HubStats,
     pub public_key: Option<String>,
+    /// Federation protocol version the hub speaks
+    #[serde(default = "legacy_protocol_version")]
+    pub protocol_version: u32,
+    /// Software version the hub reported
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Whether the hub proved it serves `public_url` under `public_key`.
+    /// Only verified hubs become healthy again after going inactive.
+    #[serde(default)]
+    pub verified: bool,
 }
 
 /// Hub status
@@ -54,12 +71,45 @@ pub struct HubList {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Key a hub_id is bound to, and the newest signed request seen from it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HubPin {
+    pub public_key: String,
+    pub last_request_at: DateTime<Utc>,
+    /// Whether the hub proved its URL under this key; unverified pins expire
+    /// with their registry entry
+    #[serde(default)]
+    pub verified: bool,
+}
+
+/// Registry state a primary hub serves to the other primaries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistrySnapshot {
+    /// The primary serving the snapshot
+    pub origin: HubInfo,
+    /// Vector clock: the origin's own version and those it merged, by hub_id
+    pub clock: BTreeMap<String, u64>,
+    pub hubs: Vec<HubInfo>,
+    pub pins: BTreeMap<String, HubPin>,
+}
+
 /// Hub registry (for primary hub)
+///
+/// With a store attached, entries, key pins and the version are written
+/// through so a restarted primary keeps its federation.
 #[derive(Debug)]
 pub struct HubRegistry {
     hubs: Arc<RwLock<HashMap<String, HubInfo>>>,
+    /// Counts local changes; this primary's component of the vector clock
     version: Arc<RwLock<u64>>,
+    /// Versions last merged from other primaries, by hub_id
+    clock: Arc<RwLock<BTreeMap<String, u64>>>,
+    /// Outlive registry entries so a hub_id cannot be taken over once seen
+    pins: Arc<RwLock<HashMap<String, HubPin>>>,
+    /// `last_seen` of each hub as of the last version bump for it
+    announced: Arc<RwLock<HashMap<String, DateTime<Utc>>>>,
     heartbeat_timeout_sec: u64,
+    store: Option<Arc<EntityStore>>,
 }
 
 impl HubRegistry {
@@ -68,17 +118,133 @@ impl HubRegistry {
         Self {
             hubs: Arc::new(RwLock::new(HashMap::new())),
             version: Arc::new(RwLock::new(0)),
+            clock: Arc::new(RwLock::new(BTreeMap::new())),
+            pins: Arc::new(RwLock::new(HashMap::new())),
+            announced: Arc::new(RwLock::new(HashMap::new())),
             heartbeat_timeout_sec,
+            store: None,
+        }
+    }
+
+    /// Restore a persisted registry and keep persisting changes to the store
+    pub fn load(store: Arc<EntityStore>, heartbeat_timeout_sec: u64) -> HubResult<Self> {
+        let mut hubs = HashMap::new();
+        for (hub_id, value) in store.list_registered_hubs()? {
+            hubs.insert(hub_id, serde_json::from_slice::<HubInfo>(&value)?);
+        }
+        let mut pins = HashMap::new();
+        for (hub_id, value) in store.list_hub_pins()? {
+            pins.insert(hub_id, serde_json::from_slice::<HubPin>(&value)?);
+        }
+        let version = store.get_hub_registry_version()?;
+        let clock = store.get_hub_registry_clock()?;
+
+        debug!("Restored {} hubs and {} key pins (version {})", hubs.len(), pins.len(), version);
+
+        Ok(Self {
+            hubs: Arc::new(RwLock::new(hubs)),
+            version: Arc::new(RwLock::new(version)),
+            clock: Arc::new(RwLock::new(clock)),
+            pins: Arc::new(RwLock::new(pins)),
+            announced: Arc::new(RwLock::new(HashMap::new())),
+            heartbeat_timeout_sec,
+            store: Some(store),
+        })
+    }
+
+    /// Write a hub entry through to the store
+    fn persist_hub(&self, hub: &HubInfo) {
+        if let Some(ref store) = self.store {
+            let result = serde_json::to_vec(hub)
+                .map_err(HubError::from)
+                .and_then(|value| store.put_registered_hub(&hub.hub_id, &value));
+            if let Err(e) = result {
+                warn!("Failed to persist hub {}: {}", hub.hub_id, e);
+            }
+        }
+    }
+
+    /// Write a key pin through to the store
+    fn persist_pin(&self, hub_id: &str, pin: &HubPin) -> HubResult<()> {
+        match self.store {
+            Some(ref store) => store.put_hub_pin(hub_id, &serde_json::to_vec(pin)?),
+            None => Ok(()),
+        }
+    }
+
+    /// Increment the hub list version
+    fn bump_version(&self) {
+        let mut version = self.version.write();
+        *version += 1;
+        if let Some(ref store) = self.store {
+            if let Err(e) = store.put_hub_registry_version(*version) {
+                warn!("Failed to persist hub list version: {}", e);
+            }
+        }
+    }
+
+    /// Accept a signed request from a hub, pinning its key on first contact.
+    ///
+    /// Rejects a key other than the pinned one, and requests not newer than
+    /// the last accepted one (replays). The signature must already be verified.
+    pub fn authenticate(&self, hub_id: &str, public_key: &str, timestamp: DateTime<Utc>) -> HubResult<()> {
+        // Signatures cover milliseconds only, and so does a persisted pin
+        let timestamp = timestamp.trunc_subsecs(3);
+        let mut pins = self.pins.write();
+        if let Some(pin) = pins.get(hub_id) {
+            if pin.public_key != public_key {
+                return Err(HubError::Unauthorized(format!(
+                    "Hub {} is registered with a different key",
+                    hub_id
+                )));
+            }
+            if timestamp <= pin.last_request_at {
+                return Err(HubError::Unauthorized(format!(
+                    "Replayed or out-of-order request from hub {}",
+                    hub_id
+                )));
+            }
+        }
+
+        let pin = HubPin {
+            public_key: public_key.to_string(),
+            last_request_at: timestamp,
+            verified: pins.get(hub_id).is_some_and(|pin| pin.verified),
+        };
+        // A pin that is not durable would let a replay through after a restart
+        self.persist_pin(hub_id, &pin)?;
+        pins.insert(hub_id.to_string(), pin);
+        Ok(())
+    }
+
+    /// Get the key a hub is pinned to
+    pub fn pinned_key(&self, hub_id: &str) -> Option<String> {
+        self.pins.read().get(hub_id).map(|pin| pin.public_key.clone())
+    }
+
+    /// Bump the version for a hub whose listing `changed`, or whose
+    /// `last_seen` was last passed on half a heartbeat timeout ago.
+    ///
+    /// Heartbeats and renewed registrations thus do not cost every other
+    /// primary a snapshot merge, while those primaries still see the hub
+    /// alive.
+    fn announce(&self, hub_id: &str, changed: bool, last_seen: DateTime<Utc>) {
+        let refresh = chrono::Duration::seconds(self.heartbeat_timeout_sec as i64 / 2);
+        let mut announced = self.announced.write();
+        let recent = announced.get(hub_id).is_some_and(|at| last_seen - *at < refresh);
+        if changed || !recent {
+            announced.insert(hub_id.to_string(), last_seen);
+            self.bump_version();
         }
     }
 
     /// Register a hub
     pub fn register(&self, hub: HubInfo) {
         let mut hubs = self.hubs.write();
+        self.persist_hub(&hub);
+        let changed = !matches!(hubs.get(&hub.hub_id), Some(known) if same_listing(known, &hub));
+        self.announce(&hub.hub_id, changed, hub.last_seen);
         hubs.insert(hub.hub_id.clone(), hub);
-
-        let mut version = self.version.write();
-        *version += 1;
     }
 
     /// Update hub heartbeat
@@ -86,14 +252,87 @@ impl HubRegistry {
         let mut hubs = self.hubs.write();
         if let Some(hub) = hubs.get_mut(hub_id) {
             hub.last_seen = Utc::now();
-            hub.status = HubStatus::Healthy;
+            // Heartbeats do not vouch for a URL that failed or awaits verification
+            let status = if hub.verified { HubStatus::Healthy } else { hub.status };
+            let changed = hub.status != status;
+            hub.status = status;
             hub.stats = stats;
+            self.persist_hub(hub);
+            self.announce(hub_id, changed, hub.last_seen);
             true
         } else {
             false
         }
     }
 
+    /// Record the outcome of a URL ownership check.
+    ///
+    /// Ignored if the hub re-registered under another URL in the meantime.
+    pub fn mark_verified(&self, hub_id: &str, public_url: &str, verified: bool) -> bool {
+        let updated = {
+            let mut hubs = self.hubs.write();
+            match hubs.get_mut(hub_id) {
+                Some(hub) if hub.public_url == public_url => {
+                    let status = if verified { HubStatus::Healthy } else { HubStatus::Unknown };
+                    if hub.status != status || hub.verified != verified {
+                        hub.status = status;
+                        hub.verified = verified;
+                        self.persist_hub(hub);
+                        self.bump_version();
+                    }
+                    true
+                }
+                _ => false,
+            }
+        };
+        // Pins are locked before hubs elsewhere
+        if updated && verified {
+            self.verify_pin(hub_id);
+        }
+        updated
+    }
+
+    /// Keep the pin of a hub that proved its URL beyond its registry entry
+    fn verify_pin(&self, hub_id: &str) {
+        let mut pins = self.pins.write();
+        if let Some(pin) = pins.get_mut(hub_id) {
+            if !pin.verified {
+                pin.verified = true;
+                if let Err(e) = self.persist_pin(hub_id, pin) {
+                    warn!("Failed to persist key pin of hub {}: {}", hub_id, e);
+                }
+            }
+        }
+    }
+
+    /// Drop the pins of hubs without an entry that never proved their URL:
+    /// those of the `removed` hubs and those last used before `cutoff`.
+    ///
+    /// A registration that failed verification thus neither holds its hub_id
+    /// nor stays in the store.
+    fn prune_pins(&self, removed: &[String], cutoff: DateTime<Utc>) {
+        let mut pins = self.pins.write();
+        let stale: Vec<String> = {
+            let hubs = self.hubs.read();
+            pins.iter()
+                .filter(|(hub_id, pin)| {
+                    !pin.verified
+                        && !hubs.contains_key(*hub_id)
+                        && (pin.last_request_at < cutoff || removed.contains(*hub_id))
+                })
+                .map(|(hub_id, _)| hub_id.clone())
+                .collect()
+        };
+        for hub_id in stale {
+            pins.remove(&hub_id);
+            if let Some(ref store) = self.store {
+                if let Err(e) = store.delete_hub_pin(&hub_id) {
+                    warn!("Failed to delete key pin of hub {}: {}", hub_id, e);
+                }
+            }
+        }
+    }
+
     /// Get a hub by ID
     pub fn get(&self, hub_id: &str) -> Option<HubInfo> {
         let hubs = self.hubs.read();
@@ -103,7 +342,8 @@ impl HubRegistry {
     /// Get all hubs
     pub fn list(&self) -> HubList {
         let hubs = self.hubs.read();
-        let version = *self.version.read();
+        // Grows with local and merged changes alike
+        let version = *self.version.read() + self.clock.read().values().sum::<u64>();
 
         HubList {
             hubs: hubs.values().cloned().collect(),
@@ -121,17 +361,154 @@ impl HubRegistry {
             .collect()
     }
 
-    /// Check for inactive hubs and update their status
+    /// Check for inactive hubs and update their status.
+    ///
+    /// Hubs still awaiting URL verification stay `Unknown` until pruned.
     pub fn check_inactive(&self) {
         let mut hubs = self.hubs.write();
         let now = Utc::now();
         let timeout = chrono::Duration::seconds(self.heartbeat_timeout_sec as i64);
 
+        let mut changed = false;
         for hub in hubs.values_mut() {
-            if now.signed_duration_since(hub.last_seen) > timeout {
+            if !matches!(hub.status, HubStatus::Inactive | HubStatus::Unknown)
+                && now.signed_duration_since(hub.last_seen) > timeout
+            {
                 hub.status = HubStatus::Inactive;
+                self.persist_hub(hub);
+                changed = true;
+            }
+        }
+        if changed {
+            self.bump_version();
+        }
+    }
+
+    /// Registry state to serve to other primaries, with `origin` describing
+    /// this hub
+    pub fn snapshot(&self, origin: HubInfo) -> RegistrySnapshot {
+        let mut clock = self.clock.read().clone();
+        clock.insert(origin.hub_id.clone(), *self.version.read());
+
+        RegistrySnapshot {
+            origin,
+            clock,
+            hubs: self.hubs.read().values().cloned().collect(),
+            pins: self.pins.read().iter().map(|(id, pin)| (id.clone(), pin.clone())).collect(),
+        }
+    }
+
+    /// Merge the snapshot of another primary, whose key the caller checked.
+    ///
+    /// The origin is recorded as a healthy hub. Unless the snapshot's clock
+    /// is covered by what was merged before, entries newer by `last_seen`
+    /// replace known ones and pins are merged; entries of a hub pinned to
+    /// another key here are skipped, as are entries not seen within
+    /// `retention_sec`, which `prune` removed here or is about to.
+    /// Returns the number of updated entries.
+    pub fn merge_snapshot(&self, local_id: &str, snapshot: RegistrySnapshot, retention_sec: u64) -> HubResult<usize> {
+        let origin_id = snapshot.origin.hub_id.clone();
+        if origin_id == local_id {
+            return Err(HubError::ValidationError("Snapshot of this hub's own registry".to_string()));
+        }
+
+        let mut pins = self.pins.write();
+        let mut hubs = self.hubs.write();
+
+        // The origin answered just now, from its URL
+        let mut origin = snapshot.origin;
+        origin.status = HubStatus::Healthy;
+        origin.verified = true;
+        origin.last_seen = Utc::now();
+        self.persist_hub(&origin);
+        hubs.insert(origin_id, origin);
+
+        let covered = {
+            let clock = self.clock.read();
+            snapshot.clock.iter().all(|(id, version)| {
+                id == local_id || clock.get(id).is_some_and(|known| known >= version)
+            })
+        };
+        if covered {
+            return Ok(0);
+        }
+
+        let mut conflicts = HashSet::new();
+        for (hub_id, pin) in snapshot.pins {
+            if hub_id == local_id {
+                continue;
+            }
+            match pins.get_mut(&hub_id) {
+                Some(known) if known.public_key != pin.public_key => {
+                    warn!("Hub {} is pinned to a different key on another primary", hub_id);
+                    conflicts.insert(hub_id);
+                }
+                Some(known) if pin.last_request_at > known.last_request_at || (pin.verified && !known.verified) => {
+                    known.last_request_at = known.last_request_at.max(pin.last_request_at);
+                    known.verified |= pin.verified;
+                    self.persist_pin(&hub_id, known)?;
+                }
+                Some(_) => {}
+                None => {
+                    self.persist_pin(&hub_id, &pin)?;
+                    pins.insert(hub_id, pin);
+                }
+            }
+        }
+
+        let cutoff = Utc::now() - chrono::Duration::seconds(retention_sec as i64);
+        let mut updated = 0;
+        for hub in snapshot.hubs {
+            let pinned = pins.get(&hub.hub_id).map(|pin| pin.public_key.as_str());
+            if hub.hub_id == local_id
+                || conflicts.contains(&hub.hub_id)
+                || pinned != hub.public_key.as_deref()
+                || hub.last_seen < cutoff
+            {
+                continue;
+            }
+            if hubs.get(&hub.hub_id).is_some_and(|known| known.last_seen >= hub.last_seen) {
+                continue;
             }
+            self.persist_hub(&hub);
+            hubs.insert(hub.hub_id.clone(), hub);
+            updated += 1;
         }
+
+        let mut clock = self.clock.write();
+        for (hub_id, version) in snapshot.clock {
+            if hub_id != local_id {
+                let known = clock.entry(hub_id).or_insert(0);
+                *known = (*known).max(version);
+            }
+        }
+        if let Some(ref store) = self.store {
+            if let Err(e) = store.put_hub_registry_clock(&clock) {
+                warn!("Failed to persist hub registry clock: {}", e);
+            }
+        }
+
+        debug!("Merged registry snapshot, {} entries updated", updated);
+        Ok(updated)
+    }
+
+    /// Remove inactive and unverified hubs not seen within the retention period.
+    ///
+    /// Key pins are kept unless the hub never verified its URL, in which case
+    /// they go with the entry, or expire on their own if there is none.
+    /// Returns the removed hub IDs.
+    pub fn prune(&self, retention_sec: u64) -> Vec<String> {
+        let cutoff = Utc::now() - chrono::Duration::seconds(retention_sec as i64);
+        let expired: Vec<String> = self.hubs
+            .read()
+            .values()
+            .filter(|h| matches!(h.status, HubStatus::Inactive | HubStatus::Unknown) && h.last_seen < cutoff)
+            .map(|h| h.hub_id.clone())
+            .collect();
+
+        let removed: Vec<String> = expired.into_iter().filter(|hub_id| self.remove(hub_id)).collect();
+        self.prune_pins(&removed, cutoff);
+        removed
     }
 
     /// Remove a hub
@@ -140,20 +517,41 @@ impl HubRegistry {
         let removed = hubs.remove(hub_id).is_some();
 
         if removed {
-            let mut version = self.version.write();
-            *version += 1;
+            if let Some(ref store) = self.store {
+                if let Err(e) = store.delete_registered_hub(hub_id) {
+                    warn!("Failed to delete hub {}: {}", hub_id, e);
+                }
+            }
+            self.bump_version();
         }
 
         removed
     }
 }
 
+/// Check if two entries of a hub list it alike, apart from `last_seen` and
+/// the reported stats
+fn same_listing(a: &HubInfo, b: &HubInfo) -> bool {
+    a.public_url == b.public_url
+        && a.role == b.role
+        && a.status == b.status
+        && a.capabilities == b.capabilities
+        && a.public_key == b.public_key
+        && a.protocol_version == b.protocol_version
+        && a.version == b.version
+        && a.verified == b.verified
+}
+
 impl Clone for HubRegistry {
     fn clone(&self) -> Self {
         Self {
             hubs: Arc::clone(&self.hubs),
             version: Arc::clone(&self.version),
+            clock: Arc::clone(&self.clock),
+            pins: Arc::clone(&self.pins),
+            announced: Arc::clone(&self.announced),
             heartbeat_timeout_sec: self.heartbeat_timeout_sec,
+            store: self.store.clone(),
         }
     }
 }
@@ -167,6 +565,7 @@ impl Default for HubRegistry {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::discovery::PROTOCOL_VERSION;
 
     #[test]
     fn test_hub_registry() {
@@ -178,9 +577,12 @@ mod tests {
             role: "secondary".to_string(),
             status: HubStatus::Healthy,
             last_seen: Utc::now(),
-            capabilities: vec!["entities".to_string()],
+            capabilities: CapabilitySet::from_names(["entities"]),
             stats: HubStats::default(),
             public_key: None,
+            protocol_version: PROTOCOL_VERSION,
+            version: None,
+            verified: false,
         };
 
         registry.register(hub);
@@ -200,11 +602,14 @@ mod tests {
             hub_id: "test-hub".to_string(),
             public_url: "https://test.example.com".to_string(),
             role: "secondary".to_string(),
-            status: HubStatus::Unknown,
+            status: HubStatus::Inactive,
             last_seen: Utc::now() - chrono::Duration::minutes(5),
-            capabilities: vec![],
+            capabilities: CapabilitySet::default(),
             stats: HubStats::default(),
             public_key: None,
+            protocol_version: PROTOCOL_VERSION,
+            version: None,
+            verified: true,
         };
 
         registry.register(hub);
@@ -222,5 +627,160 @@ mod tests {
         let updated = registry.get("test-hub").unwrap();
         assert_eq!(updated.status, HubStatus::Healthy);
         assert_eq!(updated.stats.entities_count, 100);
+
+        // A hub whose URL failed verification stays unknown
+        registry.mark_verified("test-hub", "https://test.example.com", false);
+        registry.heartbeat("test-hub", HubStats::default());
+        assert_eq!(registry.get("test-hub").unwrap().status, HubStatus::Unknown);
+    }
+
+    #[test]
+    fn test_authenticate_pins_key() {
+        let registry = HubRegistry::new(60);
+        let now = Utc::now();
+
+        registry.authenticate("test-hub", "key-1", now).unwrap();
+        assert_eq!(registry.pinned_key("test-hub").as_deref(), Some("key-1"));
+
+        // Another key for the same hub_id
+        assert!(registry.authenticate("test-hub", "key-2", now + chrono::Duration::seconds(1)).is_err());
+        // Replay of an accepted request
+        assert!(registry.authenticate("test-hub", "key-1", now).is_err());
+
+        registry.authenticate("test-hub", "key-1", now + chrono::Duration::seconds(1)).unwrap();
+    }
+
+    #[test]
+    fn test_prune_expires_unverified_pins() {
+        let registry = HubRegistry::new(60);
+        let long_ago = Utc::now() - chrono::Duration::days(2);
+
+        for (hub_id, key) in [("squatter", "key-1"), ("verified", "key-2")] {
+            registry.authenticate(hub_id, key, Utc::now()).unwrap();
+            let mut info = hub(hub_id, key, long_ago);
+            info.status = HubStatus::Unknown;
+            registry.register(info);
+        }
+        registry.mark_verified("verified", "https://verified.example.com", true);
+        registry.check_inactive();
+        // Pinned by a request that never led to an entry
+        registry.authenticate("orphan", "key-3", long_ago).unwrap();
+
+        let mut pruned = registry.prune(3600);
+        pruned.sort();
+        assert_eq!(pruned, vec!["squatter", "verified"]);
+        // The hub_id of a registration that never proved its URL is free again
+        assert!(registry.pinned_key("squatter").is_none());
+        assert!(registry.pinned_key("orphan").is_none());
+        assert_eq!(registry.pinned_key("verified").as_deref(), Some("key-2"));
+    }
+
+    fn hub(hub_id: &str, key: &str, last_seen: DateTime<Utc>) -> HubInfo {
+        HubInfo {
+            hub_id: hub_id.to_string(),
+            public_url: format!("https://{}.example.com", hub_id),
+            role: "secondary".to_string(),
+            status: HubStatus::Healthy,
+            last_seen,
+            capabilities: CapabilitySet::default(),
+            stats: HubStats::default(),
+            public_key: Some(key.to_string()),
+            protocol_version: PROTOCOL_VERSION,
+            version: None,
+            verified: false,
+        }
+    }
+
+    #[test]
+    fn test_merge_snapshot() {
+        let now = Utc::now();
+        let local = HubRegistry::new(60);
+        local.authenticate("hub-1", "key-1", now).unwrap();
+        local.register(hub("hub-1", "key-1", now));
+        local.authenticate("hub-2", "key-2", now).unwrap();
+        local.register(hub("hub-2", "key-2", now));
+
+        let remote = HubRegistry::new(60);
+        let later = now + chrono::Duration::seconds(5);
+        // Newer entry for hub-1, older for hub-2
+        remote.authenticate("hub-1", "key-1", later).unwrap();
+        remote.register(hub("hub-1", "key-1", later));
+        remote.authenticate("hub-2", "key-2", now).unwrap();
+        remote.register(hub("hub-2", "key-2", now - chrono::Duration::seconds(5)));
+        // Pinned to another key on the remote primary
+        remote.authenticate("hub-3", "key-3", now).unwrap();
+        remote.register(hub("hub-3", "key-3", now));
+        local.authenticate("hub-3", "other-key", now).unwrap();
+        // The local primary is not taken from the snapshot
+        remote.authenticate("primary-a", "key-a", now).unwrap();
+        remote.register(hub("primary-a", "key-a", now));
+
+        let version = local.list().version;
+        let snapshot = remote.snapshot(hub("primary-b", "key-b", now));
+        assert_eq!(local.merge_snapshot("primary-a", snapshot.clone(), 3600).unwrap(), 1);
+
+        assert_eq!(local.get("hub-1").unwrap().last_seen, later);
+        assert_eq!(local.get("hub-2").unwrap().last_seen, now);
+        assert!(local.get("hub-3").is_none());
+        assert!(local.get("primary-a").is_none());
+        assert_eq!(local.get("primary-b").unwrap().status, HubStatus::Healthy);
+        // Pins merged with the newest accepted request
+        assert!(local.authenticate("hub-1", "key-1", later).is_err());
+        assert!(local.list().version > version);
+
+        // Nothing new since the last merge
+        assert_eq!(local.merge_snapshot("primary-a", snapshot, 3600).unwrap(), 0);
+        assert!(local.merge_snapshot("primary-b", remote.snapshot(hub("primary-b", "key-b", now)), 3600).is_err());
+    }
+
+    #[test]
+    fn test_merge_snapshot_skips_pruned_hubs() {
+        let now = Utc::now();
+        let long_ago = now - chrono::Duration::hours(2);
+        let local = HubRegistry::new(60);
+        local.authenticate("hub-1", "key-1", long_ago).unwrap();
+        let mut expired = hub("hub-1", "key-1", long_ago);
+        expired.status = HubStatus::Inactive;
+        local.register(expired.clone());
+        local.mark_verified("hub-1", "https://hub-1.example.com", true);
+        local.check_inactive();
+        assert_eq!(local.prune(3600), vec!["hub-1".to_string()]);
+
+        // Another primary that has not pruned the hub yet
+        let remote = HubRegistry::new(60);
+        remote.authenticate("hub-1", "key-1", long_ago).unwrap();
+        remote.register(expired);
+        let snapshot = remote.snapshot(hub("primary-b", "key-b", now));
+        assert_eq!(local.merge_snapshot("primary-a", snapshot, 3600).unwrap(), 0);
+        assert!(local.get("hub-1").is_none());
+    }
+
+    #[test]
+    fn test_heartbeat_bumps_version_on_changes() {
+        let registry = HubRegistry::new(600);
+        let mut info = hub("hub-1", "key-1", Utc::now());
+        info.status = HubStatus::Inactive;
+        info.verified = true;
+        registry.register(info.clone());
+        let version = registry.list().version;
+
+        // Back to healthy
+        assert!(registry.heartbeat("hub-1", HubStats::default()));
+        let version_healthy = registry.list().version;
+        assert!(version_healthy > version);
+
+        // Heartbeats and renewed registrations that change nothing but
+        // last_seen and stats
+        registry.heartbeat("hub-1", HubStats { entities_count: 5, ..Default::default() });
+        registry.register(HubInfo { status: HubStatus::Healthy, last_seen: Utc::now(), ..info.clone() });
+        assert_eq!(registry.list().version, version_healthy);
+
+        // A new URL
+        registry.register(HubInfo {
+            status: HubStatus::Healthy,
+            public_url: "https://moved.example.com".to_string(),
+            ..info
+        });
+        assert!(registry.list().version > version_healthy);
     }
 }
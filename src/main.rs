@@ -10,9 +10,11 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilte
 
 use wisdom_hub::api::{configure_routes, create_grpc_service, AppState, ApiState};
 use wisdom_hub::config::Settings;
+use wisdom_hub::crypto::HubIdentity;
+use wisdom_hub::discovery::CapabilitySet;
 use wisdom_hub::metrics::{init_metrics, metrics_endpoint};
 use wisdom_hub::resources::ResourceMonitor;
-use wisdom_hub::services::{EntityService, DiscoveryConfig};
+use wisdom_hub::services::{DiscoveryConfig, DiscoveryTasks, TrustConfig};
 use wisdom_hub::store::{RocksStore, EntityStore};
 
 #[actix_web::main]
@@ -59,9 +61,33 @@ async fn main() -> std::io::Result<()> {
 
     info!("Database initialized at: {}", settings.database.data_dir);
 
-    // Create entity store and service
+    // Create entity store
     let entity_store = Arc::new(EntityStore::new(rocks_store));
-    let entity_service = Arc::new(EntityService::new(Arc::clone(&entity_store)));
+    let indexed = entity_store.ensure_indexes().expect("Failed to index stored entities");
+    if indexed > 0 {
+        info!("Indexed {} stored entities", indexed);
+    }
+
+    // Trust calculation settings (validated up front so bad values fail fast)
+    let trust_config = TrustConfig::try_from(&settings.trust).unwrap_or_else(|e| {
+        error!("Invalid trust settings: {}", e);
+        std::process::exit(1);
+    });
+    info!(
+        "Trust: max_depth={}, damping={}, min_threshold={}",
+        trust_config.max_depth, trust_config.damping_factor, trust_config.min_trust_threshold
+    );
+
+    // Load the hub's signing identity, generating it on first start
+    let key_path = settings.hub.private_key_path.clone().unwrap_or_else(|| {
+        std::path::Path::new(&settings.database.data_dir)
+            .join("hub.key")
+            .to_string_lossy()
+            .into_owned()
+    });
+    let identity = HubIdentity::load_or_generate(&settings.hub.hub_id, &key_path)
+        .expect("Failed to load hub identity key");
+    info!("Hub public key: {} ({})", identity.public_key(), key_path);
 
     // Create discovery configuration
     let heartbeat_timeout = settings.discovery.registration_interval_sec
@@ -72,9 +98,21 @@ async fn main() -> std::io::Result<()> {
         hub_id: settings.hub.hub_id.clone(),
         public_url: settings.hub.public_url.clone(),
         primary_hub_url: settings.discovery.primary_hub_url.clone(),
+        primary_hub_urls: settings.discovery.primary_hub_urls.clone(),
         heartbeat_timeout_sec: heartbeat_timeout,
         registration_interval_sec: settings.discovery.registration_interval_sec,
         hub_list_refresh_sec: settings.discovery.hub_list_refresh_sec,
+        hub_retention_sec: settings.discovery.hub_retention_sec,
+        mode: settings.discovery.mode,
+        seed_hubs: settings.discovery.seed_hubs.clone(),
+        gossip_interval_sec: settings.discovery.gossip_interval_sec,
+        gossip_fanout: settings.discovery.gossip_fanout,
+        probe_interval_sec: settings.discovery.probe_interval_sec,
+        degraded_latency_ms: settings.discovery.degraded_latency_ms,
+        federation_max_hubs: settings.discovery.federation_max_hubs,
+        degraded_relevance: settings.discovery.degraded_relevance,
+        allow_private_hub_urls: settings.discovery.allow_private_hub_urls,
+        capabilities: CapabilitySet::from_names(&settings.hub.capabilities),
     };
 
     // Initialize resource monitor
@@ -88,11 +126,33 @@ async fn main() -> std::io::Result<()> {
     );
 
     // Create application state for HTTP server
-    let app_state = AppState::new(&settings.hub.hub_id);
-    let api_state = ApiState::new(Arc::clone(&entity_store), discovery_config, Arc::clone(&resource_monitor));
-
-    // Create gRPC service
-    let grpc_service = create_grpc_service(Arc::clone(&entity_service), Arc::clone(&entity_store));
+    let api_state = ApiState::new(
+        Arc::clone(&entity_store),
+        trust_config,
+        discovery_config,
+        identity,
+        Arc::clone(&resource_monitor),
+    )
+    .expect("Failed to restore hub registry");
+
+    // Registration, heartbeats and hub list upkeep
+    let discovery_tasks = settings.discovery.enabled
+        .then(|| DiscoveryTasks::start(Arc::clone(&api_state.discovery_service)));
+    let mut app_state = AppState::new(&settings.hub.hub_id);
+    if let Some(ref tasks) = discovery_tasks {
+        app_state = app_state.with_discovery(tasks.monitor());
+    }
+
+    // Periodic Sybil analysis used to down-weight suspicious trust paths
+    let sybil_handle = Arc::clone(&api_state.trust_service).start_sybil_analysis();
+
+    // Create gRPC service (shares trust and discovery state with the REST API)
+    let grpc_service = create_grpc_service(
+        Arc::clone(&api_state.service),
+        Arc::clone(&api_state.trust_service),
+        Arc::clone(&api_state.validity_service),
+        Arc::clone(&api_state.discovery_service),
+    );
 
     // Start gRPC server in a separate task
     let grpc_addr = format!("{}:{}", settings.server.host, settings.server.grpc_port);
@@ -132,8 +192,13 @@ async fn main() -> std::io::Result<()> {
     .bind(&http_addr)?
     .run();
 
-    // Keep the monitor handle alive for the lifetime of the server
+    // Keep the background task handles alive for the lifetime of the server
     let _monitor_handle = monitor_handle;
+    let _sybil_handle = sybil_handle;
 
-    server.await
+    let result = server.await;
+    if let Some(tasks) = discovery_tasks {
+        tasks.shutdown().await;
+    }
+    result
 }
@@ -27,6 +27,7 @@ pub enum Domain {
     Fragment,
     Relation,
     Transformation,
+    Vote,
     Hub,
 }
 
@@ -44,6 +45,7 @@ impl fmt::Display for Domain {
             Domain::Fragment => write!(f, "FRAGMENT"),
             Domain::Relation => write!(f, "RELATION"),
             Domain::Transformation => write!(f, "TRANSFORMATION"),
+            Domain::Vote => write!(f, "VOTE"),
             Domain::Hub => write!(f, "HUB"),
         }
     }
@@ -59,6 +61,7 @@ impl FromStr for Domain {
             "FRAGMENT" => Ok(Domain::Fragment),
             "RELATION" => Ok(Domain::Relation),
             "TRANSFORMATION" => Ok(Domain::Transformation),
+            "VOTE" => Ok(Domain::Vote),
             "HUB" => Ok(Domain::Hub),
             _ => Err(AddressError::InvalidDomain(s.to_string())),
         }
@@ -112,6 +115,11 @@ impl Address {
         Self::new(server_port, Domain::Transformation, transform_id)
     }
 
+    /// Create an address for a vote
+    pub fn vote(server_port: impl Into<String>, vote_id: impl Into<String>) -> Self {
+        Self::new(server_port, Domain::Vote, vote_id)
+    }
+
     /// Create an address for a hub
     pub fn hub(server_port: impl Into<String>) -> Self {
         Self::new(server_port, Domain::Hub, "")
@@ -157,25 +165,22 @@ impl FromStr for Address {
     type Err = AddressError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = s.splitn(3, ':').collect();
+        let parts: Vec<&str> = s.split(':').collect();
 
         if parts.len() < 2 {
             return Err(AddressError::InvalidFormat(s.to_string()));
         }
 
-        let server_port = if parts.len() >= 2 && parts[1].chars().all(|c| c.is_ascii_digit()) {
-            // server:port format
-            format!("{}:{}", parts[0], parts[1])
+        // server:port:DOMAIN[:entity] or server:DOMAIN[:entity]
+        let (server_port, rest) = if parts.len() >= 3 && parts[1].chars().all(|c| c.is_ascii_digit()) {
+            (format!("{}:{}", parts[0], parts[1]), &parts[2..])
         } else {
-            parts[0].to_string()
+            (parts[0].to_string(), &parts[1..])
         };
 
-        let (domain_str, entity) = if parts.len() == 3 {
-            (parts[1], parts[2].to_string())
-        } else if parts.len() == 2 {
-            (parts[1], String::new())
-        } else {
-            return Err(AddressError::InvalidFormat(s.to_string()));
+        let (domain_str, entity) = match rest.split_first() {
+            Some((domain, entity)) => (*domain, entity.join(":")),
+            None => return Err(AddressError::InvalidFormat(s.to_string())),
         };
 
         let domain = Domain::from_str(domain_str)?;
@@ -213,6 +218,16 @@ mod tests {
         assert_eq!(addr.to_string(), "hub.wisdom.net:8080:HUB");
     }
 
+    #[test]
+    fn test_address_roundtrip_with_port() {
+        let addr = Address::tag("hub.wisdom.net:8080", "abc123");
+        let parsed = Address::parse(&addr.to_string()).unwrap();
+        assert_eq!(parsed, addr);
+
+        let hub = Address::parse("hub.wisdom.net:8080:HUB").unwrap();
+        assert_eq!(hub, Address::hub("hub.wisdom.net:8080"));
+    }
+
     #[test]
     fn test_domain_parsing() {
         assert_eq!(Domain::from_str("AGENT").unwrap(), Domain::Agent);
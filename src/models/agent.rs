@@ -2,8 +2,11 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 
 use super::Address;
 
@@ -41,6 +44,16 @@ impl ExpertiseDomain {
     pub fn custom(name: impl Into<String>) -> Self {
         ExpertiseDomain::Custom(name.into())
     }
+
+    /// The name within the domain kind (e.g., "rust" for programming:rust)
+    pub fn name(&self) -> &str {
+        match self {
+            ExpertiseDomain::Programming(s)
+            | ExpertiseDomain::Science(s)
+            | ExpertiseDomain::Business(s)
+            | ExpertiseDomain::Custom(s) => s,
+        }
+    }
 }
 
 impl std::fmt::Display for ExpertiseDomain {
@@ -54,6 +67,57 @@ impl std::fmt::Display for ExpertiseDomain {
     }
 }
 
+impl FromStr for ExpertiseDomain {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, name) = s.split_once(':')
+            .ok_or_else(|| format!("Invalid expertise domain: {}", s))?;
+        if name.is_empty() {
+            return Err(format!("Invalid expertise domain: {}", s));
+        }
+        match kind.to_lowercase().as_str() {
+            "programming" => Ok(ExpertiseDomain::programming(name)),
+            "science" => Ok(ExpertiseDomain::science(name)),
+            "business" => Ok(ExpertiseDomain::business(name)),
+            "custom" => Ok(ExpertiseDomain::custom(name)),
+            _ => Err(format!("Invalid expertise domain: {}", s)),
+        }
+    }
+}
+
+/// Context a trust relationship applies to
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrustScope {
+    /// Trust limited to content carrying this tag
+    Tag(Address),
+    /// Trust limited to an area of expertise
+    Domain(ExpertiseDomain),
+}
+
+impl fmt::Display for TrustScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrustScope::Tag(addr) => write!(f, "tag:{}", addr),
+            TrustScope::Domain(domain) => write!(f, "{}", domain),
+        }
+    }
+}
+
+impl FromStr for TrustScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(addr) = s.strip_prefix("tag:") {
+            let addr = Address::parse(addr)
+                .ok_or_else(|| format!("Invalid tag address in scope: {}", addr))?;
+            return Ok(TrustScope::Tag(addr));
+        }
+        s.parse().map(TrustScope::Domain)
+    }
+}
+
 /// A known bias or tendency of an agent
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Bias {
@@ -89,6 +153,14 @@ pub struct AgentProfile {
     pub fragment_count: u64,
     /// Historical accuracy score (0.0 to 1.0)
     pub historical_accuracy: f32,
+    /// Summed weight of verifying votes on the agent's fragments, counting
+    /// each other agent once per fragment and weighted by the hub's trust in them
+    #[serde(default)]
+    pub verified_weight: f32,
+    /// Summed weight of contesting votes and CONTRADICTS relations against the
+    /// agent's fragments, counted like `verified_weight`
+    #[serde(default)]
+    pub contested_weight: f32,
 }
 
 impl AgentProfile {
@@ -118,6 +190,63 @@ impl AgentProfile {
         self.fragment_count += 1;
         self.avg_confidence = total / self.fragment_count as f32;
     }
+
+    /// Update statistics after creating a fragment with the given tags.
+    ///
+    /// Specializations hold the share of the agent's fragments carrying each
+    /// tag, so they can be maintained without revisiting old fragments.
+    pub fn record_fragment(&mut self, confidence: f32, tags: &[String]) {
+        let previous = self.fragment_count as f32;
+        self.update_stats(confidence);
+        let count = self.fragment_count as f32;
+
+        for share in self.specializations.values_mut() {
+            *share = *share * previous / count;
+        }
+        for tag in tags {
+            let share = self.specializations.entry(tag.clone()).or_insert(0.0);
+            *share = (*share + 1.0 / count).min(1.0);
+        }
+        self.update_accuracy();
+    }
+
+    /// Update statistics after deleting a fragment with the given tags
+    pub fn forget_fragment(&mut self, confidence: f32, tags: &[String]) {
+        if self.fragment_count == 0 {
+            return;
+        }
+        let previous = self.fragment_count as f32;
+        self.fragment_count -= 1;
+        let count = self.fragment_count as f32;
+
+        if self.fragment_count == 0 {
+            self.avg_confidence = 0.0;
+            self.specializations.clear();
+            return;
+        }
+        self.avg_confidence = ((previous * self.avg_confidence - confidence) / count).clamp(0.0, 1.0);
+        for (tag, share) in self.specializations.iter_mut() {
+            let tagged = if tags.contains(tag) { 1.0 } else { 0.0 };
+            *share = ((*share * previous - tagged) / count).clamp(0.0, 1.0);
+        }
+        // Drop tags no remaining fragment carries (allowing for rounding)
+        self.specializations.retain(|_, share| *share * count >= 0.5);
+    }
+
+    /// Add verification and contestation weight received by the agent's fragments
+    /// (negative values retract earlier weight)
+    pub fn record_evidence(&mut self, verified: f32, contested: f32) {
+        self.verified_weight = (self.verified_weight + verified).max(0.0);
+        self.contested_weight = (self.contested_weight + contested).max(0.0);
+        self.update_accuracy();
+    }
+
+    /// Share of verification among all verification and contestation weight,
+    /// starting at 0.5 without evidence (Laplace smoothing)
+    fn update_accuracy(&mut self) {
+        self.historical_accuracy =
+            (self.verified_weight + 1.0) / (self.verified_weight + self.contested_weight + 2.0);
+    }
 }
 
 /// An agent participating in the wisdom network
@@ -137,6 +266,10 @@ pub struct Agent {
     pub primary_hub: String,
     /// Ed25519 signature over the agent data
     pub signature: String,
+    /// Signature by the current key over `trust_state_payload`, set by trust
+    /// updates and key rotations (None while the agent is as created)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trust_signature: Option<String>,
     /// When the agent was created
     pub created_at: DateTime<Utc>,
     /// When the agent was last updated
@@ -144,6 +277,12 @@ pub struct Agent {
     /// Agent's expertise profile
     #[serde(default)]
     pub profile: AgentProfile,
+    /// Pre-registered key that may sign a rotation when the current key is lost
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery_key: Option<String>,
+    /// Keys the agent used before the current one, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub key_history: Vec<KeyRecord>,
 }
 
 /// Contains an agent's direct trust relationships
@@ -162,6 +301,88 @@ pub struct Trust {
     pub agent: Address,
     /// Trust level: -1.0 (distrust) to 1.0 (full trust), 0 = neutral
     pub trust: f32,
+    /// Context this trust applies to (None = general trust)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<TrustScope>,
+    /// When the trust was asserted (None for entries predating this field)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asserted_at: Option<DateTime<Utc>>,
+}
+
+impl Trust {
+    /// When the trust was asserted, falling back to the owning agent's creation time
+    pub fn asserted_or(&self, fallback: DateTime<Utc>) -> DateTime<Utc> {
+        self.asserted_at.unwrap_or(fallback)
+    }
+}
+
+/// Which key signed a key rotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationSigner {
+    /// The key being retired
+    Previous,
+    /// The agent's recovery key (the previous key is lost)
+    Recovery,
+}
+
+/// Signed statement delegating an agent's identity to a new key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyRotation {
+    /// Base64-encoded Ed25519 public key taking over
+    pub new_public_key: String,
+    /// Recovery key registered along with the new key (None keeps the current one)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_recovery_key: Option<String>,
+    /// When the new key takes over
+    pub rotated_at: DateTime<Utc>,
+    /// Version the agent has after the rotation
+    pub version: u32,
+    /// Key that signed the statement
+    pub signed_by: RotationSigner,
+    /// Signature by the previous or recovery key over `statement_payload`
+    pub signature: String,
+}
+
+impl KeyRotation {
+    /// Payload signed by the previous or recovery key
+    pub fn statement_payload(&self, agent_uuid: &str, previous_public_key: &str) -> serde_json::Value {
+        json!({
+            "agent": agent_uuid,
+            "new_public_key": self.new_public_key,
+            "new_recovery_key": self.new_recovery_key.as_deref().unwrap_or(""),
+            "previous_public_key": previous_public_key,
+            "rotated_at": self.rotated_at.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            "signed_by": self.signed_by,
+            "version": self.version,
+        })
+    }
+}
+
+/// A retired key and the rotation that retired it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyRecord {
+    /// Base64-encoded Ed25519 public key
+    pub public_key: String,
+    /// Recovery key registered while this key was current
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery_key: Option<String>,
+    /// When the key became current
+    pub valid_from: DateTime<Utc>,
+    /// When the key was rotated out
+    pub valid_until: DateTime<Utc>,
+    /// The rotation to the next key
+    pub rotation: KeyRotation,
+}
+
+/// Signed request to rotate an agent's key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RotateKeyRequest {
+    /// Continuity proof by the previous or recovery key
+    pub rotation: KeyRotation,
+    /// Ed25519 signature by the new key over `Agent::trust_state_payload` of the
+    /// rotated agent, so other hubs can verify the agent as served
+    pub signature: String,
 }
 
 impl Agent {
@@ -176,9 +397,12 @@ impl Agent {
             trust: TrustStore::default(),
             primary_hub: String::new(),
             signature: String::new(),
+            trust_signature: None,
             created_at: now,
             updated_at: now,
             profile: AgentProfile::default(),
+            recovery_key: None,
+            key_history: Vec::new(),
         }
     }
 
@@ -200,31 +424,184 @@ impl Agent {
         self
     }
 
+    /// Set recovery key
+    pub fn with_recovery_key(mut self, recovery_key: impl Into<String>) -> Self {
+        self.recovery_key = Some(recovery_key.into());
+        self
+    }
+
     /// Add a trust relationship
     pub fn add_trust(&mut self, agent: Address, trust_level: f32) {
+        self.add_scoped_trust(agent, trust_level, None);
+    }
+
+    /// Add a trust relationship limited to a scope (None = general trust)
+    pub fn add_scoped_trust(&mut self, agent: Address, trust_level: f32, scope: Option<TrustScope>) {
         let clamped = trust_level.clamp(-1.0, 1.0);
         self.trust.trusts.push(Trust {
             agent,
             trust: clamped,
+            scope,
+            asserted_at: Some(Utc::now()),
         });
         self.trust.num_trusts = self.trust.trusts.len() as u64;
     }
 
-    /// Get trust for a specific agent
+    /// Set trust for an agent within a scope, replacing any existing entry
+    pub fn set_trust(&mut self, agent: Address, trust_level: f32, scope: Option<TrustScope>) {
+        self.set_trust_at(agent, trust_level, scope, Utc::now());
+    }
+
+    /// Set trust as asserted at a given time, replacing any existing entry
+    pub fn set_trust_at(
+        &mut self,
+        agent: Address,
+        trust_level: f32,
+        scope: Option<TrustScope>,
+        asserted_at: DateTime<Utc>,
+    ) {
+        let clamped = trust_level.clamp(-1.0, 1.0);
+        match self.trust.trusts.iter_mut().find(|t| t.agent == agent && t.scope == scope) {
+            Some(existing) => {
+                existing.trust = clamped;
+                existing.asserted_at = Some(asserted_at);
+            }
+            None => self.trust.trusts.push(Trust {
+                agent,
+                trust: clamped,
+                scope,
+                asserted_at: Some(asserted_at),
+            }),
+        }
+        self.trust.num_trusts = self.trust.trusts.len() as u64;
+    }
+
+    /// Remove the trust entry for an agent within a scope, returning whether one existed
+    pub fn remove_trust(&mut self, agent: &Address, scope: Option<&TrustScope>) -> bool {
+        let before = self.trust.trusts.len();
+        self.trust.trusts.retain(|t| !(t.agent == *agent && t.scope.as_ref() == scope));
+        self.trust.num_trusts = self.trust.trusts.len() as u64;
+        self.trust.trusts.len() != before
+    }
+
+    /// Apply a single trust update (set or remove)
+    pub fn apply_trust_update(&mut self, update: &TrustUpdate) {
+        match update.trust {
+            Some(level) => self.set_trust_at(
+                update.agent.clone(),
+                level,
+                update.scope.clone(),
+                update.asserted_at.unwrap_or_else(Utc::now),
+            ),
+            None => {
+                self.remove_trust(&update.agent, update.scope.as_ref());
+            }
+        }
+    }
+
+    /// Get general (unscoped) trust for a specific agent
     pub fn get_trust_for(&self, agent_addr: &Address) -> f32 {
         for t in &self.trust.trusts {
-            if t.agent == *agent_addr {
+            if t.agent == *agent_addr && t.scope.is_none() {
                 return t.trust;
             }
         }
         0.0
     }
 
+    /// Payload the agent signed at creation (see `CreateAgentRequest::signing_payload`)
+    pub fn creation_payload(&self) -> serde_json::Value {
+        let mut payload = json!({
+            "description": self.description,
+            "primary_hub": self.primary_hub,
+            "public_key": self.public_key,
+            "trust": serde_json::to_value(&self.trust).unwrap_or_default(),
+            "uuid": self.uuid,
+        });
+        if let Some(ref recovery_key) = self.recovery_key {
+            payload["recovery_key"] = json!(recovery_key);
+        }
+        payload
+    }
+
+    /// Payload signed for trust updates: the resulting trust entries with the
+    /// times they were asserted, the creation time entries without one are
+    /// dated by, and the version
+    pub fn trust_state_payload(&self) -> serde_json::Value {
+        let trusts: Vec<serde_json::Value> = self.trust.trusts
+            .iter()
+            .map(|t| json!({
+                "agent": t.agent,
+                "asserted_at": t.asserted_at,
+                "scope": t.scope,
+                "trust": t.trust,
+            }))
+            .collect();
+
+        json!({
+            "agent": self.uuid,
+            "created_at": self.created_at,
+            "trusts": trusts,
+            "version": self.version,
+        })
+    }
+
+    /// Payload covered by `state_signature`
+    pub fn signing_payload(&self) -> serde_json::Value {
+        if self.version <= 1 && self.trust_signature.is_none() {
+            self.creation_payload()
+        } else {
+            self.trust_state_payload()
+        }
+    }
+
+    /// Signature over the agent's current state.
+    ///
+    /// Agents stored before `trust_signature` existed carry the latest trust
+    /// update signature in `signature`.
+    pub fn state_signature(&self) -> &str {
+        self.trust_signature.as_deref().unwrap_or(&self.signature)
+    }
+
     /// Increment version
     pub fn increment_version(&mut self) {
         self.version += 1;
     }
 
+    /// When the current key became valid
+    pub fn key_valid_from(&self) -> DateTime<Utc> {
+        self.key_history.last().map_or(self.created_at, |record| record.valid_until)
+    }
+
+    /// Key that was current at a point in time
+    pub fn key_at(&self, when: DateTime<Utc>) -> &str {
+        self.key_history
+            .iter()
+            .find(|record| when < record.valid_until)
+            .map_or(self.public_key.as_str(), |record| record.public_key.as_str())
+    }
+
+    /// Check if a key is or was one of the agent's keys
+    pub fn has_used_key(&self, public_key: &str) -> bool {
+        self.public_key == public_key || self.key_history.iter().any(|r| r.public_key == public_key)
+    }
+
+    /// Retire the current key in favour of the rotation's new key.
+    ///
+    /// Does not check the rotation's signature.
+    pub fn apply_rotation(&mut self, rotation: KeyRotation) {
+        let recovery_key = rotation.new_recovery_key.clone().or_else(|| self.recovery_key.clone());
+        let record = KeyRecord {
+            public_key: std::mem::replace(&mut self.public_key, rotation.new_public_key.clone()),
+            recovery_key: std::mem::replace(&mut self.recovery_key, recovery_key),
+            valid_from: self.key_valid_from(),
+            valid_until: rotation.rotated_at,
+            rotation,
+        };
+        self.version = record.rotation.version;
+        self.key_history.push(record);
+    }
+
     /// Validate the agent data
     pub fn validate(&self) -> Result<(), String> {
         if self.uuid.is_empty() {
@@ -261,9 +638,63 @@ pub struct CreateAgentRequest {
     pub description: Option<String>,
     pub trust: Option<TrustStore>,
     pub primary_hub: Option<String>,
+    /// Key that may sign a rotation if the agent's key is lost
+    #[serde(default)]
+    pub recovery_key: Option<String>,
     pub signature: String,
 }
 
+/// A single change to an agent's trust store
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustUpdate {
+    /// The agent being trusted/distrusted
+    pub agent: Address,
+    /// New trust level (-1.0 to 1.0), or None to remove the entry
+    #[serde(default)]
+    pub trust: Option<f32>,
+    /// Scope of the entry to change (None = general trust)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<TrustScope>,
+    /// When the agent asserted this trust; required when setting trust, as the
+    /// signature covers it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asserted_at: Option<DateTime<Utc>>,
+}
+
+/// Signed request to add, change or remove trust entries of an existing agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateTrustRequest {
+    /// Changes to apply, in order
+    pub updates: Vec<TrustUpdate>,
+    /// Version the agent will have after this update (current version + 1)
+    pub version: u32,
+    /// Ed25519 signature by the agent's key over `Agent::trust_state_payload`
+    /// of the resulting agent, so other hubs can verify the trust store as served
+    pub signature: String,
+}
+
+impl CreateAgentRequest {
+    /// Canonical payload the agent signs on creation
+    pub fn signing_payload(&self) -> serde_json::Value {
+        let trust_val = self.trust.as_ref()
+            .map(|t| serde_json::to_value(t).unwrap_or_default())
+            .unwrap_or_else(|| json!({"num_trusts": 0, "trusts": []}));
+
+        let mut payload = json!({
+            "description": self.description.as_deref().unwrap_or(""),
+            "primary_hub": self.primary_hub.as_deref().unwrap_or(""),
+            "public_key": self.public_key,
+            "trust": trust_val,
+            "uuid": self.uuid.as_deref().unwrap_or(""),
+        });
+        // Only signed when set, so agents created without one keep verifying
+        if let Some(ref recovery_key) = self.recovery_key {
+            payload["recovery_key"] = json!(recovery_key);
+        }
+        payload
+    }
+}
+
 impl From<CreateAgentRequest> for Agent {
     fn from(req: CreateAgentRequest) -> Self {
         let uuid = req.uuid.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
@@ -274,11 +705,13 @@ impl From<CreateAgentRequest> for Agent {
             agent = agent.with_description(desc);
         }
         if let Some(trust) = req.trust {
+            // Entries without asserted_at fall back to created_at when evaluated
             agent.trust = trust;
         }
         if let Some(hub) = req.primary_hub {
             agent = agent.with_primary_hub(hub);
         }
+        agent.recovery_key = req.recovery_key;
         agent
     }
 }
@@ -318,4 +751,98 @@ mod tests {
 
         assert_eq!(agent.get_trust_for(&other), 1.0);
     }
+
+    #[test]
+    fn test_set_and_remove_trust() {
+        let mut agent = Agent::new("test", "key")
+            .with_signature("sig");
+
+        let other = Address::agent("hub:8080", "other");
+        agent.set_trust(other.clone(), 0.4, None);
+        agent.set_trust(other.clone(), -0.6, None);
+
+        assert_eq!(agent.trust.num_trusts, 1);
+        assert_eq!(agent.get_trust_for(&other), -0.6);
+
+        assert!(agent.remove_trust(&other, None));
+        assert!(!agent.remove_trust(&other, None));
+        assert_eq!(agent.trust.num_trusts, 0);
+    }
+
+    #[test]
+    fn test_scoped_trust_entries() {
+        let mut agent = Agent::new("agent-1", "key");
+        let other = Address::agent("hub:8080", "agent-2");
+        let rust = TrustScope::Domain(ExpertiseDomain::programming("rust"));
+
+        agent.set_trust(other.clone(), 0.2, None);
+        agent.set_trust(other.clone(), 0.9, Some(rust.clone()));
+        assert_eq!(agent.trust.num_trusts, 2);
+        assert_eq!(agent.get_trust_for(&other), 0.2);
+
+        assert!(agent.remove_trust(&other, Some(&rust)));
+        assert_eq!(agent.trust.num_trusts, 1);
+    }
+
+    #[test]
+    fn test_trust_scope_parse() {
+        let scope: TrustScope = "programming:rust".parse().unwrap();
+        assert_eq!(scope, TrustScope::Domain(ExpertiseDomain::programming("rust")));
+
+        let scope: TrustScope = "tag:hub:8080:TAG:rust".parse().unwrap();
+        assert_eq!(scope, TrustScope::Tag(Address::tag("hub:8080", "rust")));
+        assert_eq!(scope.to_string(), "tag:hub:8080:TAG:rust");
+
+        assert!("astrology".parse::<TrustScope>().is_err());
+    }
+
+    #[test]
+    fn test_key_history() {
+        let mut agent = Agent::new("agent-1", "key-1").with_recovery_key("recovery-1");
+        let rotated_at = agent.created_at + chrono::Duration::days(10);
+        agent.apply_rotation(KeyRotation {
+            new_public_key: "key-2".to_string(),
+            new_recovery_key: None,
+            rotated_at,
+            version: 2,
+            signed_by: RotationSigner::Previous,
+            signature: "sig".to_string(),
+        });
+
+        assert_eq!(agent.public_key, "key-2");
+        assert_eq!(agent.version, 2);
+        assert_eq!(agent.recovery_key.as_deref(), Some("recovery-1"));
+        assert_eq!(agent.key_valid_from(), rotated_at);
+        assert_eq!(agent.key_history[0].valid_from, agent.created_at);
+        assert_eq!(agent.key_at(rotated_at - chrono::Duration::seconds(1)), "key-1");
+        assert_eq!(agent.key_at(rotated_at), "key-2");
+        assert!(agent.has_used_key("key-1"));
+        assert!(!agent.has_used_key("key-3"));
+    }
+
+    #[test]
+    fn test_profile_statistics() {
+        let mut profile = AgentProfile::new();
+        let rust = vec!["rust".to_string()];
+        let both = vec!["rust".to_string(), "async".to_string()];
+
+        profile.record_fragment(0.8, &rust);
+        profile.record_fragment(0.4, &both);
+        assert_eq!(profile.fragment_count, 2);
+        assert!((profile.avg_confidence - 0.6).abs() < 0.001);
+        assert!((profile.get_specialization("rust") - 1.0).abs() < 0.001);
+        assert!((profile.get_specialization("async") - 0.5).abs() < 0.001);
+        assert!((profile.historical_accuracy - 0.5).abs() < 0.001);
+
+        profile.record_evidence(2.0, 0.0);
+        assert!((profile.historical_accuracy - 0.75).abs() < 0.001);
+        profile.record_evidence(-1.0, 1.0);
+        assert!((profile.historical_accuracy - 0.5).abs() < 0.001);
+
+        profile.forget_fragment(0.4, &both);
+        assert_eq!(profile.fragment_count, 1);
+        assert!((profile.avg_confidence - 0.8).abs() < 0.001);
+        assert!((profile.get_specialization("rust") - 1.0).abs() < 0.001);
+        assert!(!profile.specializations.contains_key("async"));
+    }
 }
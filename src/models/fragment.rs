@@ -3,7 +3,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use super::Address;
+use super::{Address, Quarantine, VoteSummary};
 
 /// Evidence type indicating how the fragment's content was derived.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -22,6 +22,19 @@ pub enum EvidenceType {
     Unknown,
 }
 
+impl EvidenceType {
+    /// Relative strength of this kind of evidence when weighing claims (0.0 to 1.0)
+    pub fn weight(&self) -> f32 {
+        match self {
+            EvidenceType::Empirical => 1.0,
+            EvidenceType::Logical => 0.9,
+            EvidenceType::Consensus => 0.8,
+            EvidenceType::Unknown => 0.5,
+            EvidenceType::Speculation => 0.3,
+        }
+    }
+}
+
 impl std::fmt::Display for EvidenceType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -49,6 +62,53 @@ impl std::str::FromStr for EvidenceType {
     }
 }
 
+/// The validity status of a reasoning chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainValidity {
+    /// All premises exist and are not contested
+    Valid,
+    /// Depends on unverified premises
+    Conditional,
+    /// At least one element is contested
+    Contested,
+    /// A premise is missing or was revoked
+    Broken,
+}
+
+impl std::fmt::Display for ChainValidity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChainValidity::Valid => write!(f, "valid"),
+            ChainValidity::Conditional => write!(f, "conditional"),
+            ChainValidity::Contested => write!(f, "contested"),
+            ChainValidity::Broken => write!(f, "broken"),
+        }
+    }
+}
+
+/// Cached result of the last reasoning-chain analysis of a fragment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedValidity {
+    /// Validity of the fragment's chain, as seen by its creator
+    pub status: ChainValidity,
+    /// Confidence-weighted validity score (0.0 to 1.0)
+    pub score: f32,
+    /// Number of issues found in the chain
+    pub issue_count: usize,
+    /// When the chain was last analyzed
+    pub computed_at: DateTime<Utc>,
+}
+
+/// Evidence a fragment received, as counted towards its creator's profile
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CountedEvidence {
+    /// Trust-weighted verification
+    pub verified: f32,
+    /// Trust-weighted contestation
+    pub contested: f32,
+}
+
 /// A knowledge fragment in the wisdom network.
 /// Fragments are minimal - typing and state are expressed through Relations.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -81,9 +141,23 @@ pub struct Fragment {
     /// How the content was derived
     #[serde(default)]
     pub evidence_type: EvidenceType,
+    /// Cached chain validity, maintained by the hub as premises change
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validity: Option<CachedValidity>,
+    /// Aggregate verification/contestation votes
+    #[serde(default)]
+    pub votes: VoteSummary,
+    /// Set if the content was signed with a revoked key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quarantine: Option<Quarantine>,
+    /// Evidence counted towards the creator's profile (None for fragments
+    /// stored before it was recorded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counted_evidence: Option<CountedEvidence>,
 }
 
-fn default_confidence() -> f32 {
+/// Confidence assumed for fragments that do not state one
+pub(crate) fn default_confidence() -> f32 {
     0.5
 }
 
@@ -107,6 +181,10 @@ impl Fragment {
             updated_at: now,
             confidence: 0.5,
             evidence_type: EvidenceType::Unknown,
+            validity: None,
+            votes: VoteSummary::default(),
+            quarantine: None,
+            counted_evidence: None,
         }
     }
 
@@ -129,9 +207,18 @@ impl Fragment {
             updated_at: now,
             confidence: 0.5,
             evidence_type: EvidenceType::Unknown,
+            validity: None,
+            votes: VoteSummary::default(),
+            quarantine: None,
+            counted_evidence: None,
         }
     }
 
+    /// Check if the fragment is quarantined
+    pub fn is_quarantined(&self) -> bool {
+        self.quarantine.is_some()
+    }
+
     /// Compute SHA-256 hash of content
     fn compute_hash(content: &str) -> String {
         use sha2::{Sha256, Digest};
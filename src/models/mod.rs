@@ -9,6 +9,8 @@ mod relation;
 mod tag;
 mod transform;
 mod trust;
+mod vote;
+mod revocation;
 mod error;
 
 pub use address::*;
@@ -18,4 +20,6 @@ pub use relation::*;
 pub use tag::*;
 pub use transform::*;
 pub use trust::*;
+pub use vote::*;
+pub use revocation::*;
 pub use error::*;
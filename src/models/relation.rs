@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::str::FromStr;
 
-use super::Address;
+use super::{Address, Quarantine};
 
 /// Known relation types
 /// Note: Fragment typing (QUESTION, HYPOTHESIS, etc.) now uses TYPE tags instead.
@@ -114,6 +114,9 @@ pub struct Relation {
     /// Strength of this relationship (0.0 to 1.0)
     #[serde(default = "default_relation_confidence")]
     pub confidence: f32,
+    /// Set if the content was signed with a revoked key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quarantine: Option<Quarantine>,
 }
 
 fn default_relation_confidence() -> f32 {
@@ -137,9 +140,20 @@ impl Relation {
             when: now,
             created_at: now,
             confidence: 1.0,
+            quarantine: None,
         }
     }
 
+    /// Check if the relation is quarantined
+    pub fn is_quarantined(&self) -> bool {
+        self.quarantine.is_some()
+    }
+
+    /// Agent asserting the relation: `by`, or the creator if `by` is not set
+    pub fn asserter(&self) -> &Address {
+        if self.by.entity.is_empty() { &self.creator } else { &self.by }
+    }
+
     /// Create a self-referencing relation (for typing fragments)
     pub fn self_reference(from: Address, creator: Address, relation_type: RelationType) -> Self {
         Self::new(from, Address::default(), creator, relation_type)
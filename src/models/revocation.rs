@@ -0,0 +1,151 @@
+//! Key revocations and quarantine of content signed with compromised keys
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::Address;
+
+/// Which key signed a revocation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RevocationSigner {
+    /// The agent's current key
+    Current,
+    /// The agent's recovery key
+    Recovery,
+}
+
+/// Signed declaration that one of an agent's keys is compromised as of a point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyRevocation {
+    /// UUID of the agent owning the key
+    pub agent: String,
+    /// Base64-encoded Ed25519 public key that is revoked
+    pub public_key: String,
+    /// Content signed with the key from this time on is untrusted
+    pub compromised_at: DateTime<Utc>,
+    /// Optional explanation
+    #[serde(default)]
+    pub reason: String,
+    /// Key that signed the revocation
+    pub signed_by: RevocationSigner,
+    /// Signature over `signing_payload`
+    pub signature: String,
+    /// When the hub recorded the revocation
+    pub revoked_at: DateTime<Utc>,
+}
+
+impl KeyRevocation {
+    /// Payload signed by the agent's current or recovery key
+    pub fn signing_payload(&self) -> serde_json::Value {
+        json!({
+            "agent": self.agent,
+            "compromised_at": self.compromised_at.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            "public_key": self.public_key,
+            "reason": self.reason,
+            "signed_by": self.signed_by,
+        })
+    }
+
+    /// Check if content signed with a key is affected.
+    ///
+    /// Content counts as compromised if it claims to be signed at or after
+    /// the compromise, or reached the hub afterwards (a stolen key can
+    /// backdate its signatures).
+    pub fn covers(&self, public_key: &str, signed_at: DateTime<Utc>, received_at: DateTime<Utc>) -> bool {
+        self.public_key == public_key
+            && (signed_at >= self.compromised_at || received_at >= self.compromised_at)
+    }
+}
+
+/// Marks content signed with a revoked key; it is left out of search and
+/// trust calculations until its creator confirms it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Quarantine {
+    /// The revoked key that signed the content
+    pub key: String,
+    /// When the content was quarantined
+    pub since: DateTime<Utc>,
+}
+
+/// Request to revoke one of an agent's keys
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevokeKeyRequest {
+    /// Key to revoke (the current or a retired key)
+    pub public_key: String,
+    pub compromised_at: DateTime<Utc>,
+    #[serde(default)]
+    pub reason: Option<String>,
+    pub signed_by: RevocationSigner,
+    pub signature: String,
+}
+
+/// Signed request releasing quarantined content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfirmContentRequest {
+    /// Fragments, relations or tags created by the agent
+    pub entities: Vec<Address>,
+    /// When the agent confirmed the content
+    pub confirmed_at: DateTime<Utc>,
+    /// Signature by the agent's current key over `signing_payload`
+    pub signature: String,
+}
+
+impl ConfirmContentRequest {
+    /// Payload signed by the agent's current key
+    pub fn signing_payload(&self, agent_uuid: &str) -> serde_json::Value {
+        json!({
+            "agent": agent_uuid,
+            "confirmed_at": self.confirmed_at.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            "entities": self.entities,
+        })
+    }
+}
+
+/// Content quarantined or released by a revocation or confirmation
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QuarantineChange {
+    pub fragments: Vec<String>,
+    pub relations: Vec<String>,
+    pub tags: Vec<String>,
+}
+
+impl QuarantineChange {
+    /// Number of affected entities
+    pub fn len(&self) -> usize {
+        self.fragments.len() + self.relations.len() + self.tags.len()
+    }
+
+    /// Check if nothing was affected
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_revocation_covers() {
+        let compromised_at = Utc::now();
+        let revocation = KeyRevocation {
+            agent: "agent-1".to_string(),
+            public_key: "key-1".to_string(),
+            compromised_at,
+            reason: String::new(),
+            signed_by: RevocationSigner::Current,
+            signature: "sig".to_string(),
+            revoked_at: compromised_at,
+        };
+        let before = compromised_at - chrono::Duration::hours(1);
+        let after = compromised_at + chrono::Duration::hours(1);
+
+        assert!(!revocation.covers("key-1", before, before));
+        assert!(revocation.covers("key-1", after, after));
+        // Backdated but received after the compromise
+        assert!(revocation.covers("key-1", before, after));
+        assert!(!revocation.covers("key-2", after, after));
+    }
+}
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::str::FromStr;
 
-use super::Address;
+use super::{Address, Quarantine};
 
 /// Tag categories for classification and filtering
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -117,6 +117,9 @@ pub struct Tag {
     pub signature: String,
     /// When the tag was created
     pub created_at: DateTime<Utc>,
+    /// Set if the content was signed with a revoked key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quarantine: Option<Quarantine>,
 }
 
 impl Tag {
@@ -131,9 +134,15 @@ impl Tag {
             creator,
             signature: String::new(),
             created_at: Utc::now(),
+            quarantine: None,
         }
     }
 
+    /// Check if the tag is quarantined
+    pub fn is_quarantined(&self) -> bool {
+        self.quarantine.is_some()
+    }
+
     /// Set content/description
     pub fn with_content(mut self, content: impl Into<String>) -> Self {
         self.content = content.into();
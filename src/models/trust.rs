@@ -3,9 +3,10 @@
 //! Note: Direct trust relationships are now embedded in Agent (TrustStore).
 //! This module provides types for trust path queries and results.
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use super::Address;
+use super::{Address, TrustScope};
 
 /// A hop in a trust path
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,6 +15,9 @@ pub struct TrustPathHop {
     pub agent: Address,
     /// Trust level to this agent (-1.0 to 1.0)
     pub trust_level: f32,
+    /// Hub the trust store asserting this hop was read from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hub: Option<String>,
 }
 
 /// A trust path from one agent to another
@@ -51,6 +55,7 @@ impl TrustPath {
             hops: vec![TrustPathHop {
                 agent: to,
                 trust_level,
+                hub: None,
             }],
             effective_trust: trust_level,
             depth: 1,
@@ -70,6 +75,7 @@ impl TrustPath {
         self.hops.push(TrustPathHop {
             agent,
             trust_level: clamped_trust,
+            hub: None,
         });
         self.effective_trust = new_trust.clamp(-1.0, 1.0);
         self.depth = self.hops.len();
@@ -105,6 +111,9 @@ pub struct TrustScore {
     /// Best path found (if any)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub best_path: Option<TrustPath>,
+    /// How the score was derived (only when requested)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<TrustExplanation>,
 }
 
 impl TrustScore {
@@ -116,6 +125,7 @@ impl TrustScore {
             score: score.clamp(-1.0, 1.0),
             path_count,
             best_path: None,
+            explanation: None,
         }
     }
 
@@ -129,6 +139,90 @@ impl TrustScore {
     pub fn neutral(entity: Address, viewer: Address) -> Self {
         Self::new(entity, viewer, 0.0, 0)
     }
+
+    /// Attach an explanation
+    pub fn with_explanation(mut self, explanation: TrustExplanation) -> Self {
+        self.explanation = Some(explanation);
+        self
+    }
+}
+
+/// Breakdown of how a trust score was derived
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustExplanation {
+    /// Aggregation applied to the contributing paths
+    pub formula: String,
+    /// Entity the paths lead to (a fragment's creator for fragments)
+    pub target: Address,
+    /// Damping factor applied per hop after the first
+    pub damping_factor: f32,
+    /// Paths whose cumulative trust fell below this were cut
+    pub min_trust_threshold: f32,
+    /// Maximum path depth searched
+    pub max_depth: u8,
+    /// Strongest contributing paths, best first
+    pub paths: Vec<ExplainedPath>,
+    /// Relation endorsements of the entity that were folded in
+    pub endorsements: Vec<ExplainedEndorsement>,
+    /// Edges not followed because cumulative trust dropped below the threshold
+    pub cut_edges: Vec<CutTrustEdge>,
+}
+
+/// A contributing path with the math applied at each hop
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExplainedPath {
+    /// Hops from the viewer to the target
+    pub hops: Vec<ExplainedHop>,
+    /// Product of all hop factors
+    pub effective_trust: f32,
+    /// Relation UUID if the path ends in an endorsement
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endorsement: Option<String>,
+}
+
+/// One hop of an explained path
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExplainedHop {
+    /// Agent (or endorsed entity) reached by this hop
+    pub agent: Address,
+    /// Hub the asserting trust store was read from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hub: Option<String>,
+    /// Trust level of the edge after decay
+    pub trust_level: f32,
+    /// Damping applied (1.0 for the first hop)
+    pub damping: f32,
+    /// Sybil down-weighting of the agent the hop leaves from (1.0 if not suspected)
+    pub sybil_factor: f32,
+    /// Trust accumulated up to and including this hop
+    pub cumulative_trust: f32,
+}
+
+/// A TRUST relation asserting trust in the scored entity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExplainedEndorsement {
+    /// UUID of the relation
+    pub relation: String,
+    /// Agent that asserted the relation
+    pub endorser: Address,
+    /// Confidence of the relation, used as the trust level of the final hop
+    pub confidence: f32,
+    /// Viewer's trust in the endorser (None if unreachable)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endorser_trust: Option<f32>,
+    /// Resulting contribution (0.0 if the endorser is unreachable)
+    pub contribution: f32,
+}
+
+/// A trust edge pruned because the path's cumulative trust fell below the threshold
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CutTrustEdge {
+    pub from: Address,
+    pub to: Address,
+    /// Trust level of the edge after decay
+    pub trust_level: f32,
+    /// Cumulative trust the path would have had
+    pub cumulative_trust: f32,
 }
 
 /// Request for trust path calculation
@@ -156,6 +250,39 @@ pub struct TrustScoreRequest {
     pub viewer: Address,
 }
 
+/// A trust edge that has aged past the configured staleness threshold
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaleTrustEdge {
+    /// The trusted agent
+    pub agent: Address,
+    /// Scope of the edge (None = general trust)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<TrustScope>,
+    /// Trust level as asserted
+    pub trust: f32,
+    /// When the trust was asserted
+    pub asserted_at: DateTime<Utc>,
+    /// Age in days
+    pub age_days: u32,
+    /// Remaining weight after decay (0.0 when expired)
+    pub weight: f32,
+    /// Whether the edge is no longer used for trust calculations
+    pub expired: bool,
+}
+
+/// Stale trust edges of one agent, so it can be prompted to refresh them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaleTrustReport {
+    /// Agent owning the trust store
+    pub agent: String,
+    /// Edges older than the staleness threshold, oldest first
+    pub stale: Vec<StaleTrustEdge>,
+    /// Total number of trust edges of the agent
+    pub total: usize,
+    /// When the report was generated
+    pub generated_at: DateTime<Utc>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
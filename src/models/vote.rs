@@ -0,0 +1,240 @@
+//! Vote model for agents verifying or contesting fragments
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use super::Address;
+
+/// Whether a vote verifies or contests a fragment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum VoteKind {
+    /// The agent confirms the fragment
+    Verify,
+    /// The agent disputes the fragment
+    Contest,
+}
+
+impl fmt::Display for VoteKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VoteKind::Verify => write!(f, "VERIFY"),
+            VoteKind::Contest => write!(f, "CONTEST"),
+        }
+    }
+}
+
+impl FromStr for VoteKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "VERIFY" => Ok(VoteKind::Verify),
+            "CONTEST" => Ok(VoteKind::Contest),
+            _ => Err(format!("Invalid vote kind: {}", s)),
+        }
+    }
+}
+
+/// An agent's verification or contestation of a fragment.
+/// Each agent has at most one vote per fragment; casting again replaces it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Vote {
+    /// Unique identifier (kept when the vote is updated)
+    pub uuid: String,
+    /// Fragment voted on
+    pub fragment: Address,
+    /// Agent who cast the vote
+    pub voter: Address,
+    /// Verify or contest
+    pub kind: VoteKind,
+    /// Strength of the vote (0.0 to 1.0)
+    #[serde(default = "default_vote_weight")]
+    pub weight: f32,
+    /// Optional reasoning
+    #[serde(default)]
+    pub comment: String,
+    /// Version number (incremented on updates)
+    pub version: u32,
+    /// Ed25519 signature over the vote data
+    pub signature: String,
+    /// When the vote was cast by the agent
+    pub when: DateTime<Utc>,
+    /// When the vote was first stored
+    pub created_at: DateTime<Utc>,
+    /// When the vote was last updated
+    pub updated_at: DateTime<Utc>,
+}
+
+fn default_vote_weight() -> f32 {
+    1.0
+}
+
+impl Vote {
+    /// Create a new vote
+    pub fn new(fragment: Address, voter: Address, kind: VoteKind) -> Self {
+        let now = Utc::now();
+        Self {
+            uuid: uuid::Uuid::new_v4().to_string(),
+            fragment,
+            voter,
+            kind,
+            weight: 1.0,
+            comment: String::new(),
+            version: 1,
+            signature: String::new(),
+            when: now,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Set weight (clamped to 0.0 - 1.0)
+    pub fn with_weight(mut self, weight: f32) -> Self {
+        self.weight = weight.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set comment
+    pub fn with_comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = comment.into();
+        self
+    }
+
+    /// Set signature
+    pub fn with_signature(mut self, signature: impl Into<String>) -> Self {
+        self.signature = signature.into();
+        self
+    }
+
+    /// Validate the vote data
+    pub fn validate(&self) -> Result<(), String> {
+        if self.uuid.is_empty() {
+            return Err("uuid is required".to_string());
+        }
+        if self.fragment.entity.is_empty() {
+            return Err("fragment is required".to_string());
+        }
+        if self.voter.entity.is_empty() {
+            return Err("voter is required".to_string());
+        }
+        if self.signature.is_empty() {
+            return Err("signature is required".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Aggregate vote counters kept on a fragment
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VoteSummary {
+    /// Total votes received
+    pub votes_count: u32,
+    /// Verifying votes
+    pub verifications: u32,
+    /// Contesting votes
+    pub contestations: u32,
+    /// Sum of verifying vote weights
+    pub verification_weight: f32,
+    /// Sum of contesting vote weights
+    pub contestation_weight: f32,
+}
+
+impl VoteSummary {
+    /// Summarize a fragment's votes
+    pub fn from_votes<'a>(votes: impl IntoIterator<Item = &'a Vote>) -> Self {
+        let mut summary = Self::default();
+        for vote in votes {
+            summary.votes_count += 1;
+            match vote.kind {
+                VoteKind::Verify => {
+                    summary.verifications += 1;
+                    summary.verification_weight += vote.weight;
+                }
+                VoteKind::Contest => {
+                    summary.contestations += 1;
+                    summary.contestation_weight += vote.weight;
+                }
+            }
+        }
+        summary
+    }
+
+    /// Verification and contestation weight
+    pub fn weights(&self) -> (f32, f32) {
+        (self.verification_weight, self.contestation_weight)
+    }
+
+    /// Check if no votes were cast
+    pub fn is_empty(&self) -> bool {
+        self.votes_count == 0
+    }
+}
+
+/// Request to cast or update a vote
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateVoteRequest {
+    pub uuid: Option<String>,
+    pub fragment: Address,
+    pub voter: Address,
+    pub kind: VoteKind,
+    /// Strength of the vote (0.0 to 1.0, default 1.0)
+    #[serde(default)]
+    pub weight: Option<f32>,
+    /// Optional reasoning
+    #[serde(default)]
+    pub comment: Option<String>,
+    /// When the vote was cast (required, as the signature covers it); an
+    /// update must be newer than the vote it replaces
+    #[serde(default)]
+    pub when: Option<DateTime<Utc>>,
+    pub signature: String,
+}
+
+impl From<CreateVoteRequest> for Vote {
+    fn from(req: CreateVoteRequest) -> Self {
+        let uuid = req.uuid.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let mut vote = Vote::new(req.fragment, req.voter, req.kind)
+            .with_comment(req.comment.unwrap_or_default())
+            .with_signature(req.signature);
+        vote.uuid = uuid;
+        if let Some(weight) = req.weight {
+            vote = vote.with_weight(weight);
+        }
+        if let Some(when) = req.when {
+            vote.when = when;
+        }
+        vote
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vote_summary() {
+        let fragment = Address::fragment("hub:8080", "frag-1");
+        let votes = vec![
+            Vote::new(fragment.clone(), Address::agent("hub:8080", "a"), VoteKind::Verify),
+            Vote::new(fragment.clone(), Address::agent("hub:8080", "b"), VoteKind::Verify).with_weight(0.5),
+            Vote::new(fragment, Address::agent("hub:8080", "c"), VoteKind::Contest).with_weight(2.0),
+        ];
+
+        let summary = VoteSummary::from_votes(&votes);
+        assert_eq!(summary.votes_count, 3);
+        assert_eq!(summary.verifications, 2);
+        assert_eq!(summary.contestations, 1);
+        assert!((summary.verification_weight - 1.5).abs() < 0.001);
+        assert!((summary.contestation_weight - 1.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_vote_kind_parse() {
+        assert_eq!("verify".parse::<VoteKind>().unwrap(), VoteKind::Verify);
+        assert_eq!("CONTEST".parse::<VoteKind>().unwrap(), VoteKind::Contest);
+        assert!("abstain".parse::<VoteKind>().is_err());
+    }
+}
@@ -2,24 +2,57 @@
 //!
 //! Manages hub registration for both primary and secondary hubs.
 
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
 use std::sync::Arc;
-use chrono::Utc;
-use parking_lot::RwLock;
-use tracing::{info, warn, error};
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use parking_lot::{Mutex, RwLock};
+use tracing::{debug, info, warn, error};
 
-use crate::config::HubRole;
-use crate::discovery::{HubRegistry, HubInfo, HubList, HubStats, HubStatus, DiscoveryClient};
-use crate::models::{HubResult, HubError};
+use crate::config::{DiscoveryMode, HubRole};
+use crate::crypto::{verify_with_key, HubIdentity};
+use crate::discovery::{
+    HubRegistry, HubInfo, HubList, HubStats, HubStatus, DiscoveryClient, SignedResponse,
+    GossipMessage, HubHealthTracker, Capability, CapabilitySet, legacy_protocol_version,
+    negotiate_protocol, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, PeerTable, RegistrySnapshot, SignedHubInfo, registration_message,
+    heartbeat_message, same_url, check_request_time,
+};
+use crate::models::{Address, HubResult, HubError};
 use crate::store::EntityStore;
+use crate::trust::{server_port_from_url, HubDirectory};
+
+/// Health probes taking longer than this count as failed
+const PROBE_TIMEOUT_SECS: u64 = 5;
+/// Window URL ownership challenges are counted in
+const CHALLENGE_WINDOW_SECS: u64 = 60;
+/// Challenges per hub_id and window
+const MAX_CHALLENGES_PER_HUB: u32 = 3;
+/// Challenges per requesting address and window
+const MAX_CHALLENGES_PER_SOURCE: u32 = 10;
+/// Hub_ids and addresses tracked for challenge limits at most
+const MAX_CHALLENGE_KEYS: usize = 10_000;
 
 /// Request to register a hub
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct RegisterHubRequest {
     pub hub_id: String,
     pub public_url: String,
-    pub capabilities: Vec<String>,
+    pub capabilities: CapabilitySet,
     pub version: Option<String>,
-    pub public_key: Option<String>,
+    /// Federation protocol version the hub speaks
+    #[serde(default = "legacy_protocol_version")]
+    pub protocol_version: u32,
+    /// Key the hub_id gets pinned to on first registration
+    pub public_key: String,
+    pub timestamp: DateTime<Utc>,
+    /// Signature over `hub_id|public_url|timestamp`
+    pub signature: String,
+    /// Address the request came from, set by the API layer to limit URL
+    /// challenges per source
+    #[serde(skip)]
+    pub source: Option<IpAddr>,
 }
 
 /// Response from hub registration
@@ -36,6 +69,9 @@ pub struct HeartbeatRequest {
     pub hub_id: String,
     pub status: String,
     pub stats: HubStats,
+    pub timestamp: DateTime<Utc>,
+    /// Signature over `heartbeat|hub_id|status|timestamp` with the pinned key
+    pub signature: String,
 }
 
 /// Response from heartbeat
@@ -45,6 +81,33 @@ pub struct HeartbeatResponse {
     pub message: Option<String>,
 }
 
+/// Notice that an agent rotated its key.
+///
+/// Sent as the data of a hub-signed envelope (see `SignedResponse`). Carries
+/// no keys: receiving hubs refetch the agent from its home hub and check the
+/// rotation against the key they pinned.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct KeyRotationNotice {
+    pub agent: Address,
+}
+
+/// Hub self-description published at `/.well-known/wisdom-hub`
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct HubDescriptor {
+    pub hub_id: String,
+    /// Base64-encoded Ed25519 key the hub signs its responses with
+    pub public_key: String,
+    pub public_url: String,
+    pub role: String,
+    pub version: String,
+    #[serde(default = "legacy_protocol_version")]
+    pub protocol_version: u32,
+    pub capabilities: CapabilitySet,
+    /// Challenge echoed back when a primary verifies the hub's URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+}
+
 /// Discovery service configuration
 #[derive(Debug, Clone)]
 pub struct DiscoveryConfig {
@@ -56,12 +119,39 @@ pub struct DiscoveryConfig {
     pub public_url: String,
     /// Primary hub URL (for secondary hubs)
     pub primary_hub_url: Option<String>,
+    /// Further primary hubs: failover targets for secondary hubs, replication
+    /// peers for primary hubs
+    pub primary_hub_urls: Vec<String>,
     /// Heartbeat timeout in seconds
     pub heartbeat_timeout_sec: u64,
     /// Registration interval in seconds (for secondary hubs)
     pub registration_interval_sec: u64,
     /// Hub list refresh interval in seconds
     pub hub_list_refresh_sec: u64,
+    /// How long inactive or unverified hubs stay registered (primary hubs)
+    pub hub_retention_sec: u64,
+    /// Registry (primary and secondaries) or gossip between all hubs
+    pub mode: DiscoveryMode,
+    /// Hub URLs contacted in gossip mode while no peer answers
+    pub seed_hubs: Vec<String>,
+    /// Gossip round interval in seconds
+    pub gossip_interval_sec: u64,
+    /// Peers contacted per gossip round
+    pub gossip_fanout: usize,
+    /// Interval between health probes of federation targets (0 disables)
+    pub probe_interval_sec: u64,
+    /// p95 probe latency above which a hub is degraded
+    pub degraded_latency_ms: u64,
+    /// Most hubs queried per federated search
+    pub federation_max_hubs: usize,
+    /// Relevance score of results from degraded hubs (healthy hubs: 0.9)
+    pub degraded_relevance: f64,
+    /// Let primaries verify hub URLs on loopback, private and link-local
+    /// addresses (local test federations only)
+    pub allow_private_hub_urls: bool,
+    /// Capabilities to advertise, limited to what the hub serves in its
+    /// role and mode
+    pub capabilities: CapabilitySet,
 }
 
 impl Default for DiscoveryConfig {
@@ -71,44 +161,112 @@ impl Default for DiscoveryConfig {
             hub_id: "default-hub".to_string(),
             public_url: "http://localhost:8080".to_string(),
             primary_hub_url: None,
+            primary_hub_urls: Vec::new(),
             heartbeat_timeout_sec: 900, // 15 minutes
             registration_interval_sec: 300, // 5 minutes
             hub_list_refresh_sec: 60, // 1 minute
+            hub_retention_sec: 7 * 24 * 3600, // 7 days
+            mode: DiscoveryMode::Registry,
+            seed_hubs: Vec::new(),
+            gossip_interval_sec: 30,
+            gossip_fanout: 3,
+            probe_interval_sec: 60,
+            degraded_latency_ms: 1000,
+            federation_max_hubs: 8,
+            degraded_relevance: 0.8,
+            allow_private_hub_urls: false,
+            capabilities: CapabilitySet::supported(),
         }
     }
 }
 
+impl DiscoveryConfig {
+    /// Configured capabilities this hub serves in its role and mode
+    pub fn served_capabilities(&self) -> CapabilitySet {
+        let mut served = CapabilitySet::supported();
+        if !(self.mode == DiscoveryMode::Registry && self.role == HubRole::Primary) {
+            served.remove(&Capability::Replication);
+        }
+        if self.mode != DiscoveryMode::Gossip {
+            served.remove(&Capability::Gossip);
+        }
+        self.capabilities.intersect(&served)
+    }
+
+    /// Configured primary hubs other than this hub, in order of preference
+    pub fn primary_urls(&self) -> Vec<String> {
+        let mut urls: Vec<String> = Vec::new();
+        for url in self.primary_hub_url.iter().chain(&self.primary_hub_urls) {
+            let url = url.trim_end_matches('/');
+            if !same_url(url, &self.public_url) && !urls.iter().any(|known| known == url) {
+                urls.push(url.to_string());
+            }
+        }
+        urls
+    }
+}
+
 /// Discovery service for managing hub federation
 pub struct DiscoveryService {
     config: DiscoveryConfig,
     registry: Option<HubRegistry>,
     client: Option<DiscoveryClient>,
+    /// Peers known in gossip mode, which has neither registry nor client
+    peers: Option<Arc<PeerTable>>,
     store: Arc<EntityStore>,
     self_info: Arc<RwLock<HubInfo>>,
+    identity: Arc<HubIdentity>,
+    /// Probe results overriding the status of federation targets
+    health: Arc<HubHealthTracker>,
+    /// URL challenges started per hub_id or source in the current window
+    challenges: Arc<Mutex<HashMap<String, (Instant, u32)>>>,
+    http_client: reqwest::Client,
 }
 
 impl DiscoveryService {
-    /// Create a new discovery service
-    pub fn new(config: DiscoveryConfig, store: Arc<EntityStore>) -> Self {
-        let (registry, client) = match config.role {
-            HubRole::Primary => {
+    /// Create a new discovery service.
+    ///
+    /// Fails if a primary hub cannot restore its registry from the store.
+    pub fn new(config: DiscoveryConfig, store: Arc<EntityStore>) -> HubResult<Self> {
+        let identity = Arc::new(HubIdentity::generate(&config.hub_id));
+        let capabilities = config.served_capabilities();
+        let ignored: Vec<&str> = config.capabilities
+            .iter()
+            .filter(|c| !capabilities.contains(c))
+            .map(Capability::name)
+            .collect();
+        if !ignored.is_empty() {
+            warn!("Not advertising capabilities this hub does not serve: {}", ignored.join(", "));
+        }
+
+        let (registry, client) = match (config.mode, config.role) {
+            (DiscoveryMode::Gossip, _) => (None, None),
+            (DiscoveryMode::Registry, HubRole::Primary) => {
                 // Primary hub has a registry, no client
-                let registry = HubRegistry::new(config.heartbeat_timeout_sec);
+                let registry = HubRegistry::load(Arc::clone(&store), config.heartbeat_timeout_sec)?;
                 (Some(registry), None)
             }
-            HubRole::Secondary => {
+            (DiscoveryMode::Registry, HubRole::Secondary) => {
                 // Secondary hub has a client, no registry
-                let primary_url = config.primary_hub_url.as_ref()
-                    .expect("Secondary hub requires primary_hub_url");
+                let primary_urls = config.primary_urls();
+                assert!(!primary_urls.is_empty(), "Secondary hub requires primary_hub_url");
                 let client = DiscoveryClient::new(
-                    primary_url,
-                    &config.hub_id,
+                    primary_urls,
+                    Arc::clone(&identity),
                     &config.public_url,
-                    vec!["entities".to_string(), "trust".to_string(), "search".to_string()],
+                    capabilities.clone(),
                 );
                 (None, Some(client))
             }
         };
+        let peers = match config.mode {
+            DiscoveryMode::Gossip => Some(Arc::new(PeerTable::load(
+                &config.hub_id,
+                config.heartbeat_timeout_sec,
+                Arc::clone(&store),
+            )?)),
+            DiscoveryMode::Registry => None,
+        };
 
         let self_info = HubInfo {
             hub_id: config.hub_id.clone(),
@@ -116,18 +274,46 @@ impl DiscoveryService {
             role: format!("{:?}", config.role).to_lowercase(),
             status: HubStatus::Healthy,
             last_seen: Utc::now(),
-            capabilities: vec!["entities".to_string(), "trust".to_string(), "search".to_string()],
+            capabilities,
             stats: HubStats::default(),
-            public_key: None,
+            public_key: Some(identity.public_key()),
+            protocol_version: PROTOCOL_VERSION,
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            verified: false,
         };
 
-        Self {
+        let http_client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("Failed to create HTTP client");
+
+        let health = Arc::new(HubHealthTracker::new(Duration::from_millis(config.degraded_latency_ms)));
+
+        let service = Self {
             config,
             registry,
             client,
+            peers,
             store,
             self_info: Arc::new(RwLock::new(self_info)),
-        }
+            identity,
+            health,
+            challenges: Arc::new(Mutex::new(HashMap::new())),
+            http_client,
+        };
+        // Drop hubs that expired while this hub was down
+        service.check_inactive_hubs();
+        Ok(service)
+    }
+
+    /// Use a persistent identity instead of the throwaway one from `new`
+    pub fn with_identity(mut self, identity: HubIdentity) -> Self {
+        let identity = Arc::new(identity);
+        self.self_info.write().public_key = Some(identity.public_key());
+        self.client = self.client.map(|c| c.with_identity(Arc::clone(&identity)));
+        self.identity = identity;
+        self
     }
 
     /// Get current stats for this hub
@@ -159,17 +345,49 @@ impl DiscoveryService {
         let registry = self.registry.as_ref()
             .ok_or_else(|| HubError::FederationError("Not a primary hub".to_string()))?;
 
+        if req.hub_id.is_empty() || req.hub_id.contains('|') {
+            return Err(HubError::ValidationError("Invalid hub_id".to_string()));
+        }
+        if !(req.public_url.starts_with("http://") || req.public_url.starts_with("https://")) {
+            return Err(HubError::ValidationError("public_url must be an http(s) URL".to_string()));
+        }
+        if req.protocol_version < MIN_PROTOCOL_VERSION {
+            return Err(HubError::ValidationError(format!(
+                "Protocol version {} is not supported (minimum {})",
+                req.protocol_version, MIN_PROTOCOL_VERSION
+            )));
+        }
+        check_request_time(req.timestamp)?;
+        let message = registration_message(&req.hub_id, &req.public_url, req.timestamp);
+        if !verify_with_key(&req.public_key, message.as_bytes(), &req.signature)? {
+            return Err(HubError::invalid_signature("hub registration"));
+        }
+        registry.authenticate(&req.hub_id, &req.public_key, req.timestamp)?;
+        // Each registration is followed by a URL challenge
+        self.reserve_challenge(&req.hub_id, req.source)?;
+
         info!("Registering hub: {} at {}", req.hub_id, req.public_url);
 
+        // Until `verify_hub_url` succeeds the hub is listed but not federated
+        // with; a re-registration of an already verified URL and key keeps it
+        let verified = registry.get(&req.hub_id).is_some_and(|hub| {
+            hub.verified
+                && hub.public_url == req.public_url
+                && hub.public_key.as_deref() == Some(req.public_key.as_str())
+        });
+
         let hub_info = HubInfo {
             hub_id: req.hub_id,
             public_url: req.public_url,
             role: "secondary".to_string(),
-            status: HubStatus::Healthy,
+            status: if verified { HubStatus::Healthy } else { HubStatus::Unknown },
             last_seen: Utc::now(),
             capabilities: req.capabilities,
             stats: HubStats::default(),
-            public_key: req.public_key,
+            public_key: Some(req.public_key),
+            protocol_version: req.protocol_version,
+            version: req.version,
+            verified,
         };
 
         registry.register(hub_info);
@@ -179,16 +397,162 @@ impl DiscoveryService {
 
         Ok(RegisterHubResponse {
             registered: true,
-            message: Some("Hub registered successfully".to_string()),
+            message: Some(if verified {
+                "Hub registered successfully".to_string()
+            } else {
+                "Hub registered, URL verification pending".to_string()
+            }),
             hub_list: Some(hub_list),
         })
     }
 
+    /// Count a URL challenge against the limits for `hub_id` and the
+    /// requesting address, rejecting it once either is used up
+    fn reserve_challenge(&self, hub_id: &str, source: Option<IpAddr>) -> HubResult<()> {
+        let window = Duration::from_secs(CHALLENGE_WINDOW_SECS);
+        let now = Instant::now();
+        let mut challenges = self.challenges.lock();
+        if challenges.len() >= MAX_CHALLENGE_KEYS {
+            challenges.retain(|_, (started, _)| now.duration_since(*started) < window);
+            if challenges.len() >= MAX_CHALLENGE_KEYS {
+                return Err(HubError::RateLimitExceeded);
+            }
+        }
+
+        let mut limits = vec![(format!("hub\0{}", hub_id), MAX_CHALLENGES_PER_HUB)];
+        if let Some(ip) = source {
+            limits.push((format!("source\0{}", ip), MAX_CHALLENGES_PER_SOURCE));
+        }
+        let exhausted = limits.iter().any(|(key, max)| {
+            challenges
+                .get(key)
+                .is_some_and(|(started, count)| now.duration_since(*started) < window && count >= max)
+        });
+        if exhausted {
+            return Err(HubError::RateLimitExceeded);
+        }
+        for (key, _) in limits {
+            let (started, count) = challenges.entry(key).or_insert((now, 0));
+            if now.duration_since(*started) >= window {
+                *started = now;
+                *count = 0;
+            }
+            *count += 1;
+        }
+        Ok(())
+    }
+
+    /// Resolve the host of a hub URL to the address a challenge connects to.
+    ///
+    /// Refuses hosts resolving to loopback, private or link-local addresses
+    /// unless `allow_private_hub_urls` is set, so registrations cannot aim
+    /// the primary at its own network.
+    async fn challenge_address(&self, public_url: &str) -> HubResult<(String, std::net::SocketAddr)> {
+        let url = reqwest::Url::parse(public_url)
+            .map_err(|e| HubError::ValidationError(format!("Invalid hub URL: {}", e)))?;
+        let host = url.host_str()
+            .ok_or_else(|| HubError::ValidationError("Hub URL has no host".to_string()))?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string();
+        let port = url.port_or_known_default()
+            .ok_or_else(|| HubError::ValidationError("Hub URL has no port".to_string()))?;
+
+        let addrs: Vec<_> = tokio::net::lookup_host((host.as_str(), port))
+            .await
+            .map_err(|e| HubError::NetworkError(format!("Failed to resolve {}: {}", host, e)))?
+            .collect();
+        if !self.config.allow_private_hub_urls && addrs.iter().any(|addr| !is_public_address(addr.ip())) {
+            return Err(HubError::ValidationError(format!(
+                "Hub URL {} resolves to a non-public address",
+                public_url
+            )));
+        }
+        let addr = addrs.into_iter().next()
+            .ok_or_else(|| HubError::NetworkError(format!("No address for {}", host)))?;
+        Ok((host, addr))
+    }
+
+    /// Send a URL challenge to the address checked by `challenge_address`,
+    /// without following redirects
+    async fn send_challenge(&self, hub: &HubInfo, nonce: &str) -> HubResult<()> {
+        let (host, addr) = self.challenge_address(&hub.public_url).await?;
+        // Connect to the checked address rather than resolving again
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(PROBE_TIMEOUT_SECS))
+            .redirect(reqwest::redirect::Policy::none())
+            .resolve(&host, addr)
+            .build()
+            .map_err(|e| HubError::NetworkError(e.to_string()))?;
+
+        let url = format!(
+            "{}/.well-known/wisdom-hub?nonce={}",
+            hub.public_url.trim_end_matches('/'),
+            urlencoding::encode(nonce)
+        );
+        let response = client.get(&url).send().await
+            .map_err(|e| HubError::NetworkError(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(HubError::FederationError(format!(
+                "Challenge returned {}",
+                response.status()
+            )));
+        }
+        let body = response.bytes().await
+            .map_err(|e| HubError::NetworkError(e.to_string()))?;
+        check_challenge_response(&body, hub, nonce)
+    }
+
+    /// Check that a registered hub controls its claimed URL (primary hub only).
+    ///
+    /// Calls `/.well-known/wisdom-hub` at the URL with a fresh nonce and
+    /// expects it echoed back signed by the hub's registered key. The hub is
+    /// marked healthy on success and unknown otherwise.
+    pub async fn verify_hub_url(&self, hub_id: &str) -> HubResult<bool> {
+        let registry = self.registry.as_ref()
+            .ok_or_else(|| HubError::FederationError("Not a primary hub".to_string()))?;
+        let hub = registry.get(hub_id)
+            .ok_or_else(|| HubError::not_found("hub", hub_id))?;
+
+        let nonce = uuid::Uuid::new_v4().to_string();
+        let result = self.send_challenge(&hub, &nonce).await;
+
+        let verified = match result {
+            Ok(()) => {
+                info!("Verified URL of hub {}: {}", hub.hub_id, hub.public_url);
+                true
+            }
+            Err(e) => {
+                warn!("Hub {} failed URL verification for {}: {}", hub.hub_id, hub.public_url, e);
+                false
+            }
+        };
+        registry.mark_verified(&hub.hub_id, &hub.public_url, verified);
+        Ok(verified)
+    }
+
     /// Process heartbeat from a hub (primary hub only)
     pub fn process_heartbeat(&self, req: HeartbeatRequest) -> HubResult<HeartbeatResponse> {
         let registry = self.registry.as_ref()
             .ok_or_else(|| HubError::FederationError("Not a primary hub".to_string()))?;
 
+        let public_key = match registry.pinned_key(&req.hub_id) {
+            Some(key) => key,
+            None => {
+                warn!("Heartbeat from unknown hub: {}", req.hub_id);
+                return Ok(HeartbeatResponse {
+                    acknowledged: false,
+                    message: Some("Hub not registered".to_string()),
+                });
+            }
+        };
+        check_request_time(req.timestamp)?;
+        let message = heartbeat_message(&req.hub_id, &req.status, &req.stats, req.timestamp);
+        if !verify_with_key(&public_key, message.as_bytes(), &req.signature)? {
+            return Err(HubError::invalid_signature("hub heartbeat"));
+        }
+        registry.authenticate(&req.hub_id, &public_key, req.timestamp)?;
+
         let success = registry.heartbeat(&req.hub_id, req.stats);
 
         if success {
@@ -208,7 +572,8 @@ impl DiscoveryService {
     /// Get list of all known hubs (primary hub only)
     pub fn get_known_hubs(&self) -> HubResult<HubList> {
         if let Some(ref registry) = self.registry {
-            // Primary hub: return from registry
+            // Primary hub: return from registry. Probe results stay local:
+            // other hubs probe for themselves
             let mut list = registry.list();
 
             // Add self to the list
@@ -220,15 +585,110 @@ impl DiscoveryService {
             // Secondary hub: return cached list
             client.get_cached_hub_list()
                 .ok_or_else(|| HubError::FederationError("Hub list not available".to_string()))
+        } else if let Some(ref peers) = self.peers {
+            let mut list = peers.list();
+            list.hubs.insert(0, self.self_info.read().clone());
+            Ok(list)
         } else {
             Err(HubError::FederationError("Discovery not configured".to_string()))
         }
     }
 
-    /// Check for inactive hubs (primary hub only)
+    /// Registry state for the other primaries (primary hub only)
+    pub fn registry_snapshot(&self) -> HubResult<RegistrySnapshot> {
+        let registry = self.registry.as_ref()
+            .ok_or_else(|| HubError::FederationError("Not a primary hub".to_string()))?;
+        if !self.self_info.read().capabilities.contains(&Capability::Replication) {
+            return Err(HubError::FederationError("Replication is not enabled".to_string()));
+        }
+
+        self.update_self_info();
+        Ok(registry.snapshot(self.self_info()))
+    }
+
+    /// Fetch the registry snapshot of the primary at `url`.
+    ///
+    /// It must be signed by the primary it describes, served from that
+    /// primary's URL, and by the key its hub_id is pinned to.
+    async fn pull_snapshot(&self, registry: &HubRegistry, url: &str) -> HubResult<RegistrySnapshot> {
+        // A primary registered here is held to its pinned key right away
+        let known = registry.list().hubs.into_iter().find(|hub| same_url(&hub.public_url, url));
+        if let Some(ref hub) = known {
+            self.require_capability(hub, Capability::Replication)?;
+        }
+
+        let response = self.http_client
+            .get(format!("{}/api/v1/discovery/registry", url))
+            .send()
+            .await
+            .map_err(|e| HubError::NetworkError(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(HubError::FederationError(format!(
+                "Registry snapshot returned {}",
+                response.status()
+            )));
+        }
+        let body = response.bytes().await
+            .map_err(|e| HubError::NetworkError(e.to_string()))?;
+
+        let known_key = known.as_ref()
+            .and_then(|hub| registry.pinned_key(&hub.hub_id).or_else(|| hub.public_key.clone()));
+        let (snapshot, signature): (RegistrySnapshot, _) = SignedResponse::from_slice(&body)?
+            .verified_data(known.as_ref().map(|hub| hub.hub_id.as_str()), known_key.as_deref())?;
+        if snapshot.origin.hub_id != signature.hub_id
+            || snapshot.origin.public_key.as_deref() != Some(signature.public_key.as_str())
+            || !same_url(&snapshot.origin.public_url, url)
+        {
+            return Err(HubError::invalid_signature("registry snapshot"));
+        }
+        registry.authenticate(&signature.hub_id, &signature.public_key, signature.signed_at)?;
+        self.require_capability(&snapshot.origin, Capability::Replication)?;
+        Ok(snapshot)
+    }
+
+    /// Merge the registries of the other configured primaries (primary hub
+    /// only).
+    ///
+    /// Returns the number of primaries replicated from.
+    pub async fn replicate_registry(&self) -> HubResult<usize> {
+        let registry = self.registry.as_ref()
+            .ok_or_else(|| HubError::FederationError("Not a primary hub".to_string()))?;
+
+        let peers = self.config.primary_urls();
+        if peers.is_empty() {
+            return Ok(0);
+        }
+
+        let results = join_all(peers.iter().map(|url| self.pull_snapshot(registry, url))).await;
+        let mut reached = 0;
+        for (url, result) in peers.iter().zip(results) {
+            match result.and_then(|snapshot| registry.merge_snapshot(&self.config.hub_id, snapshot, self.config.hub_retention_sec)) {
+                Ok(updated) => {
+                    reached += 1;
+                    debug!("Replicated registry from primary hub {}, {} entries updated", url, updated);
+                }
+                Err(e) => warn!("Registry replication from primary hub {} failed: {}", url, e),
+            }
+        }
+        if reached == 0 {
+            return Err(HubError::FederationError("No other primary hub reachable".to_string()));
+        }
+        Ok(reached)
+    }
+
+    /// Mark hubs without recent heartbeats inactive and drop those past the
+    /// retention period (primary hubs and gossip mode)
     pub fn check_inactive_hubs(&self) {
-        if let Some(ref registry) = self.registry {
+        let pruned = if let Some(ref registry) = self.registry {
             registry.check_inactive();
+            registry.prune(self.config.hub_retention_sec)
+        } else if let Some(ref peers) = self.peers {
+            peers.prune(self.config.hub_retention_sec)
+        } else {
+            return;
+        };
+        if !pruned.is_empty() {
+            info!("Pruned {} hubs past retention: {}", pruned.len(), pruned.join(", "));
         }
     }
 
@@ -237,13 +697,13 @@ impl DiscoveryService {
     // ========================================================================
 
     /// Register with primary hub (secondary hub only)
-    pub async fn register_with_primary(&self, public_key: Option<&str>) -> HubResult<HubList> {
+    pub async fn register_with_primary(&self) -> HubResult<HubList> {
         let client = self.client.as_ref()
             .ok_or_else(|| HubError::FederationError("Not a secondary hub".to_string()))?;
 
-        info!("Registering with primary hub: {}", self.config.primary_hub_url.as_ref().unwrap());
+        info!("Registering with primary hub: {}", client.active_primary_url().unwrap_or_default());
 
-        let result = client.register(public_key).await;
+        let result = client.register().await;
 
         match &result {
             Ok(_) => info!("Successfully registered with primary hub"),
@@ -277,17 +737,355 @@ impl DiscoveryService {
             .unwrap_or(false)
     }
 
-    /// Get other healthy hubs for federation
-    pub fn get_federation_targets(&self) -> Vec<HubInfo> {
+    /// Register or send a heartbeat, whichever is due (secondary hub only).
+    ///
+    /// A failed heartbeat (e.g. the primary no longer knows this hub) makes
+    /// the next call register again.
+    pub async fn maintain_membership(&self) -> HubResult<()> {
+        let client = self.client.as_ref()
+            .ok_or_else(|| HubError::FederationError("Not a secondary hub".to_string()))?;
+
+        if self.needs_registration() {
+            return self.register_with_primary().await.map(|_| ());
+        }
+
+        let result = self.send_heartbeat().await;
+        if result.is_err() {
+            client.reset_registration();
+        }
+        result
+    }
+
+    /// The primary hub currently talked to (secondary hub only)
+    pub fn active_primary_url(&self) -> Option<String> {
+        self.client.as_ref().and_then(|c| c.active_primary_url()).map(str::to_string)
+    }
+
+    /// When this hub last registered with the primary (secondary hub only)
+    pub fn last_registration(&self) -> Option<DateTime<Utc>> {
+        self.client.as_ref().and_then(|c| c.last_registration())
+    }
+
+    // ========================================================================
+    // Gossip Operations
+    // ========================================================================
+
+    /// This hub's entry and the entries it knows about, to send to a peer
+    fn gossip_message(&self) -> HubResult<GossipMessage> {
+        let peers = self.peers.as_ref()
+            .ok_or_else(|| HubError::FederationError("Gossip mode not enabled".to_string()))?;
+
+        self.update_self_info();
+        let own = SignedHubInfo::sign(self.self_info(), &self.identity)?;
+        let mut entries = vec![own];
+        entries.extend(peers.entries());
+        Ok(GossipMessage { entries })
+    }
+
+    /// Merge a peer's entries and answer with this hub's (gossip mode only)
+    pub fn handle_gossip(&self, message: GossipMessage) -> HubResult<GossipMessage> {
+        let peers = self.peers.as_ref()
+            .ok_or_else(|| HubError::FederationError("Gossip mode not enabled".to_string()))?;
+
+        let updated = peers.merge_all(message.entries);
+        debug!("Gossip from peer updated {} entries", updated);
+        self.gossip_message()
+    }
+
+    /// Exchange entries with the hub at `url`.
+    ///
+    /// The answer is signed by the hub serving the URL, which verifies that
+    /// hub's URL. `expected` is the hub_id and key of a known peer.
+    async fn exchange(&self, url: &str, expected: Option<&HubInfo>) -> HubResult<usize> {
+        let peers = self.peers.as_ref()
+            .ok_or_else(|| HubError::FederationError("Gossip mode not enabled".to_string()))?;
+        if let Some(hub) = expected {
+            self.require_capability(hub, Capability::Gossip)?;
+        }
+
+        let message = self.gossip_message()?;
+        let response = self.http_client
+            .post(format!("{}/api/v1/discovery/gossip", url.trim_end_matches('/')))
+            .json(&message)
+            .send()
+            .await
+            .map_err(|e| HubError::NetworkError(e.to_string()))?;
+        let body = response.bytes().await
+            .map_err(|e| HubError::NetworkError(e.to_string()))?;
+
+        let (reply, signature): (GossipMessage, _) = SignedResponse::from_slice(&body)?.verified_data(
+            expected.map(|hub| hub.hub_id.as_str()),
+            expected.and_then(|hub| hub.public_key.as_deref()),
+        )?;
+        let updated = peers.merge_all(reply.entries);
+        peers.mark_verified(&signature.hub_id, url, &signature.public_key);
+        Ok(updated)
+    }
+
+    /// Gossip with random peers, or the seed hubs while no peer answers
+    /// (gossip mode only).
+    ///
+    /// Returns the number of hubs exchanged with.
+    pub async fn gossip_round(&self) -> HubResult<usize> {
+        let peers = self.peers.as_ref()
+            .ok_or_else(|| HubError::FederationError("Gossip mode not enabled".to_string()))?;
+
+        let targets = peers.sample(self.config.gossip_fanout);
+        let results = join_all(targets.iter().map(|hub| self.exchange(&hub.public_url, Some(hub)))).await;
+        let mut reached = 0;
+        for (hub, result) in targets.iter().zip(results) {
+            match result {
+                Ok(updated) => {
+                    reached += 1;
+                    debug!("Gossiped with hub {}, {} entries updated", hub.hub_id, updated);
+                }
+                Err(e) => debug!("Gossip with hub {} failed: {}", hub.hub_id, e),
+            }
+        }
+        if reached > 0 {
+            return Ok(reached);
+        }
+
+        let own_url = self.config.public_url.clone();
+        let seeds: Vec<&String> = self.config.seed_hubs
+            .iter()
+            .filter(|url| !same_url(url, &own_url))
+            .collect();
+        if seeds.is_empty() {
+            return if targets.is_empty() {
+                Ok(0)
+            } else {
+                Err(HubError::FederationError("No peer reachable".to_string()))
+            };
+        }
+
+        let results = join_all(seeds.iter().map(|url| self.exchange(url, None))).await;
+        for (url, result) in seeds.iter().zip(results) {
+            match result {
+                Ok(_) => {
+                    reached += 1;
+                    info!("Gossiped with seed hub {}", url);
+                }
+                Err(e) => warn!("Gossip with seed hub {} failed: {}", url, e),
+            }
+        }
+        if reached == 0 {
+            return Err(HubError::FederationError("No peer or seed hub reachable".to_string()));
+        }
+        Ok(reached)
+    }
+
+    /// Hubs to federate with by registration, hub list or gossip alone
+    fn federation_candidates(&self) -> Vec<HubInfo> {
         if let Some(ref client) = self.client {
             client.get_other_hubs()
         } else if let Some(ref registry) = self.registry {
             registry.list_healthy()
+        } else if let Some(ref peers) = self.peers {
+            peers.list_healthy()
         } else {
             vec![]
         }
     }
 
+    /// Capabilities this hub and `hub` share, or `None` if their protocol
+    /// versions do not overlap
+    pub fn negotiate(&self, hub: &HubInfo) -> Option<CapabilitySet> {
+        let protocol = negotiate_protocol(hub.protocol_version)?;
+        let mut remote = hub.capabilities.clone();
+        // Protocol 1 predates `search.signed`; its hubs sign search
+        // responses all the same
+        if protocol == 1 && remote.contains(&Capability::Search) {
+            remote.insert(Capability::SearchSigned);
+        }
+        Some(self.self_info.read().capabilities.intersect(&remote))
+    }
+
+    /// Check that this hub and `hub` share `capability` before calling it
+    fn require_capability(&self, hub: &HubInfo, capability: Capability) -> HubResult<()> {
+        match self.negotiate(hub) {
+            Some(common) if common.contains(&capability) => Ok(()),
+            _ => Err(HubError::FederationError(format!(
+                "Hub {} does not share {} (protocol {})",
+                hub.hub_id, capability, hub.protocol_version
+            ))),
+        }
+    }
+
+    /// Federation targets sharing all of `required` with this hub, with the
+    /// shared capabilities
+    pub fn compatible_targets(&self, required: &[Capability]) -> Vec<(HubInfo, CapabilitySet)> {
+        self.get_federation_targets()
+            .into_iter()
+            .filter_map(|hub| match self.negotiate(&hub) {
+                Some(common) if common.contains_all(required) => Some((hub, common)),
+                _ => {
+                    debug!("Skipping hub {}: lacks {:?} or speaks protocol {}", hub.hub_id, required, hub.protocol_version);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Downgrade a healthy hub to the status its probes indicate
+    fn apply_probe_status(&self, hub: &mut HubInfo) {
+        if hub.status == HubStatus::Healthy {
+            if let Some(health) = self.health.health(&hub.hub_id) {
+                hub.status = health.status;
+            }
+        }
+    }
+
+    /// Get other hubs for federation, fastest healthy hubs first.
+    ///
+    /// Degraded hubs come after healthy ones; hubs whose probes keep failing
+    /// are left out until a probe succeeds again.
+    pub fn get_federation_targets(&self) -> Vec<HubInfo> {
+        let mut targets: Vec<(HubInfo, f64)> = self
+            .federation_candidates()
+            .into_iter()
+            .filter_map(|mut hub| {
+                self.apply_probe_status(&mut hub);
+                if !matches!(hub.status, HubStatus::Healthy | HubStatus::Degraded) {
+                    return None;
+                }
+                // Hubs not probed yet rank after probed ones of the same status
+                let p50 = self.health.health(&hub.hub_id)
+                    .and_then(|h| h.p50_ms)
+                    .unwrap_or(f64::INFINITY);
+                Some((hub, p50))
+            })
+            .collect();
+
+        targets.sort_by(|(a, a_p50), (b, b_p50)| {
+            (a.status == HubStatus::Degraded)
+                .cmp(&(b.status == HubStatus::Degraded))
+                .then(a_p50.partial_cmp(b_p50).unwrap_or(std::cmp::Ordering::Equal))
+        });
+        targets.into_iter().map(|(hub, _)| hub).collect()
+    }
+
+    /// Probe `/health` of every federation candidate and record latency or
+    /// failure.
+    ///
+    /// Returns the number of hubs that answered.
+    pub async fn probe_hubs(&self) -> HubResult<usize> {
+        let candidates: Vec<HubInfo> = self
+            .federation_candidates()
+            .into_iter()
+            .filter(|hub| hub.hub_id != self.config.hub_id && self.negotiate(hub).is_some())
+            .collect();
+        self.health.retain(&candidates.iter().map(|hub| hub.hub_id.clone()).collect::<HashSet<_>>());
+
+        let probes = candidates.iter().map(|hub| async move {
+            let started = Instant::now();
+            let result = self.http_client
+                .get(format!("{}/health", hub.public_url.trim_end_matches('/')))
+                .timeout(Duration::from_secs(PROBE_TIMEOUT_SECS))
+                .send()
+                .await;
+            match result {
+                Ok(response) if response.status().is_success() => Some(started.elapsed()),
+                Ok(response) => {
+                    debug!("Health probe of hub {} returned {}", hub.hub_id, response.status());
+                    None
+                }
+                Err(e) => {
+                    debug!("Health probe of hub {} failed: {}", hub.hub_id, e);
+                    None
+                }
+            }
+        });
+        let results = join_all(probes).await;
+
+        let mut answered = 0;
+        for (hub, latency) in candidates.iter().zip(results) {
+            answered += usize::from(latency.is_some());
+            self.health.record(&hub.hub_id, latency);
+        }
+        debug!("Probed {} hubs, {} answered", candidates.len(), answered);
+        Ok(answered)
+    }
+
+    /// Probe statistics of the federation targets
+    pub fn hub_health(&self) -> &HubHealthTracker {
+        &self.health
+    }
+
+    /// Tell the other known hubs that a local agent rotated its key.
+    ///
+    /// Best effort; returns the number of hubs that accepted the notice.
+    pub async fn announce_key_rotation(&self, agent: &Address) -> usize {
+        let notice = KeyRotationNotice { agent: agent.clone() };
+        let signature = match self.identity.sign(&notice) {
+            Ok(signature) => signature,
+            Err(e) => {
+                warn!("Failed to sign key rotation notice of {}: {}", agent, e);
+                return 0;
+            }
+        };
+        let envelope = serde_json::json!({
+            "success": true,
+            "data": notice,
+            "signature": signature,
+        });
+        let targets: Vec<HubInfo> = self
+            .compatible_targets(&[Capability::Entities])
+            .into_iter()
+            .map(|(hub, _)| hub)
+            .filter(|hub| hub.hub_id != self.config.hub_id)
+            .collect();
+
+        let requests = targets.iter().map(|hub| {
+            let url = format!("{}/api/v1/discovery/key-rotations", hub.public_url.trim_end_matches('/'));
+            self.http_client.post(url).json(&envelope).send()
+        });
+        let results = join_all(requests).await;
+
+        let mut accepted = 0;
+        for (hub, result) in targets.iter().zip(results) {
+            match result {
+                Ok(response) if response.status().is_success() => accepted += 1,
+                Ok(response) => warn!(
+                    "Hub {} rejected key rotation of {}: {}",
+                    hub.hub_id, agent, response.status()
+                ),
+                Err(e) => warn!("Failed to notify hub {} of key rotation: {}", hub.hub_id, e),
+            }
+        }
+        debug!("Announced key rotation of {} to {}/{} hubs", agent, accepted, targets.len());
+        accepted
+    }
+
+    /// Check a key rotation notice received from another hub.
+    ///
+    /// The notice must be signed by a registered, verified hub sharing
+    /// `entities`, recently, and name one of that hub's own agents.
+    pub fn verify_key_rotation_notice(&self, body: &[u8]) -> HubResult<KeyRotationNotice> {
+        let envelope = SignedResponse::from_slice(body)?;
+        let sender = envelope.signature.as_ref()
+            .map(|s| s.hub_id.clone())
+            .ok_or_else(|| HubError::invalid_signature("key rotation notice"))?;
+        let hub = self
+            .compatible_targets(&[Capability::Entities])
+            .into_iter()
+            .map(|(hub, _)| hub)
+            .find(|hub| hub.hub_id == sender)
+            .ok_or_else(|| HubError::Unauthorized(format!("{} is not a registered and verified hub", sender)))?;
+        let public_key = hub.public_key.as_deref()
+            .ok_or_else(|| HubError::Unauthorized(format!("Hub {} has no registered key", sender)))?;
+
+        let (notice, _): (KeyRotationNotice, _) =
+            envelope.verified_data(Some(&hub.hub_id), Some(public_key))?;
+        if notice.agent.server_port != server_port_from_url(&hub.public_url) {
+            return Err(HubError::Unauthorized(format!(
+                "Hub {} announced an agent of another hub",
+                hub.hub_id
+            )));
+        }
+        Ok(notice)
+    }
+
     // ========================================================================
     // Getters
     // ========================================================================
@@ -297,15 +1095,108 @@ impl DiscoveryService {
         matches!(self.config.role, HubRole::Primary)
     }
 
+    /// Check if hubs are discovered by gossip
+    pub fn is_gossip(&self) -> bool {
+        self.peers.is_some()
+    }
+
     /// Get this hub's ID
     pub fn hub_id(&self) -> &str {
         &self.config.hub_id
     }
 
+    /// Get the discovery configuration
+    pub fn config(&self) -> &DiscoveryConfig {
+        &self.config
+    }
+
     /// Get this hub's info
     pub fn self_info(&self) -> HubInfo {
         self.self_info.read().clone()
     }
+
+    /// Get the identity this hub signs its responses with
+    pub fn identity(&self) -> &HubIdentity {
+        &self.identity
+    }
+
+    /// Describe this hub for `/.well-known/wisdom-hub`
+    pub fn descriptor(&self) -> HubDescriptor {
+        let info = self.self_info.read();
+        HubDescriptor {
+            hub_id: info.hub_id.clone(),
+            public_key: self.identity.public_key(),
+            public_url: info.public_url.clone(),
+            role: info.role.clone(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: info.capabilities.clone(),
+            nonce: None,
+        }
+    }
+}
+
+/// Check if an address is reachable from the public internet, as opposed
+/// to loopback, private, link-local, shared or otherwise special addresses
+fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                // Shared address space (carrier-grade NAT)
+                || (a == 100 && (b & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(v4) => is_public_address(IpAddr::V4(v4)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_unspecified()
+                    || ip.is_loopback()
+                    || ip.is_multicast()
+                    // Unique local
+                    || (first & 0xfe00) == 0xfc00
+                    // Link-local
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+/// Check a hub's answer to a URL ownership challenge
+fn check_challenge_response(body: &[u8], hub: &HubInfo, nonce: &str) -> HubResult<()> {
+    let public_key = hub.public_key.as_deref()
+        .ok_or_else(|| HubError::FederationError("Hub has no registered key".to_string()))?;
+
+    let (descriptor, _): (HubDescriptor, _) = SignedResponse::from_slice(body)?
+        .verified_data(Some(&hub.hub_id), Some(public_key))?;
+
+    if descriptor.nonce.as_deref() != Some(nonce) {
+        return Err(HubError::FederationError("Challenge nonce not echoed".to_string()));
+    }
+    if descriptor.public_url.trim_end_matches('/') != hub.public_url.trim_end_matches('/') {
+        return Err(HubError::FederationError(format!(
+            "Hub describes itself as {}",
+            descriptor.public_url
+        )));
+    }
+    Ok(())
+}
+
+impl HubDirectory for DiscoveryService {
+    /// Federation targets serving trust stores, at their registered URL
+    fn hub_url(&self, server_port: &str) -> Option<String> {
+        self.compatible_targets(&[Capability::Trust])
+            .into_iter()
+            .map(|(hub, _)| hub.public_url)
+            .find(|url| server_port_from_url(url) == server_port)
+    }
 }
 
 impl Clone for DiscoveryService {
@@ -314,8 +1205,13 @@ impl Clone for DiscoveryService {
             config: self.config.clone(),
             registry: self.registry.clone(),
             client: self.client.clone(),
+            peers: self.peers.clone(),
             store: Arc::clone(&self.store),
             self_info: Arc::clone(&self.self_info),
+            identity: Arc::clone(&self.identity),
+            health: Arc::clone(&self.health),
+            challenges: Arc::clone(&self.challenges),
+            http_client: self.http_client.clone(),
         }
     }
 }
@@ -323,6 +1219,7 @@ impl Clone for DiscoveryService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::discovery::MAX_REQUEST_AGE_SECS;
     use crate::store::RocksStore;
     use tempfile::tempdir;
 
@@ -338,19 +1235,49 @@ mod tests {
             ..Default::default()
         };
 
-        DiscoveryService::new(config, store)
+        DiscoveryService::new(config, store).unwrap()
+    }
+
+    /// Requests of one hub must be at least a millisecond apart
+    fn request_time() -> DateTime<Utc> {
+        std::thread::sleep(Duration::from_millis(2));
+        Utc::now()
+    }
+
+    fn registration(identity: &HubIdentity, public_url: &str) -> RegisterHubRequest {
+        let timestamp = request_time();
+        RegisterHubRequest {
+            hub_id: identity.hub_id().to_string(),
+            public_url: public_url.to_string(),
+            capabilities: CapabilitySet::from_names(["entities"]),
+            version: None,
+            protocol_version: PROTOCOL_VERSION,
+            public_key: identity.public_key(),
+            timestamp,
+            signature: identity.sign_message(&registration_message(identity.hub_id(), public_url, timestamp)),
+            source: None,
+        }
+    }
+
+    fn heartbeat(identity: &HubIdentity, stats: HubStats) -> HeartbeatRequest {
+        let timestamp = request_time();
+        HeartbeatRequest {
+            hub_id: identity.hub_id().to_string(),
+            status: "healthy".to_string(),
+            timestamp,
+            signature: identity.sign_message(&heartbeat_message(identity.hub_id(), "healthy", &stats, timestamp)),
+            stats,
+        }
     }
 
     #[test]
     fn test_register_hub() {
         let service = setup_primary_service();
 
+        let identity = HubIdentity::generate("secondary-1");
         let req = RegisterHubRequest {
-            hub_id: "secondary-1".to_string(),
-            public_url: "https://secondary1.example.com".to_string(),
-            capabilities: vec!["entities".to_string()],
             version: Some("0.1.0".to_string()),
-            public_key: None,
+            ..registration(&identity, "https://secondary1.example.com")
         };
 
         let response = service.register_hub(req).unwrap();
@@ -362,33 +1289,157 @@ mod tests {
     }
 
     #[test]
-    fn test_heartbeat() {
+    fn test_register_hub_limits_challenges() {
         let service = setup_primary_service();
+        let source: IpAddr = "203.0.113.7".parse().unwrap();
 
-        // First register
+        let identity = HubIdentity::generate("secondary-1");
+        for _ in 0..MAX_CHALLENGES_PER_HUB {
+            service.register_hub(registration(&identity, "https://secondary1.example.com")).unwrap();
+        }
+        let result = service.register_hub(registration(&identity, "https://secondary1.example.com"));
+        assert!(matches!(result, Err(HubError::RateLimitExceeded)));
+
+        // One address registering many hubs
+        for i in 0..MAX_CHALLENGES_PER_SOURCE {
+            let identity = HubIdentity::generate(format!("hub-{}", i));
+            let req = RegisterHubRequest {
+                source: Some(source),
+                ..registration(&identity, "https://hub.example.com")
+            };
+            service.register_hub(req).unwrap();
+        }
         let req = RegisterHubRequest {
-            hub_id: "secondary-1".to_string(),
-            public_url: "https://secondary1.example.com".to_string(),
-            capabilities: vec!["entities".to_string()],
-            version: None,
-            public_key: None,
+            source: Some(source),
+            ..registration(&HubIdentity::generate("hub-x"), "https://hub.example.com")
         };
-        service.register_hub(req).unwrap();
+        assert!(matches!(service.register_hub(req), Err(HubError::RateLimitExceeded)));
+    }
+
+    #[tokio::test]
+    async fn test_verify_hub_url_rejects_private_addresses() {
+        let service = setup_primary_service();
+        let identity = HubIdentity::generate("secondary-1");
+        service.register_hub(registration(&identity, "http://127.0.0.1:9")).unwrap();
+
+        let result = service.verify_hub_url("secondary-1").await;
+        assert!(!result.unwrap());
+        assert!(matches!(
+            service.challenge_address("http://10.0.0.1:8080").await,
+            Err(HubError::ValidationError(_))
+        ));
+        assert!(matches!(
+            service.challenge_address("http://[::1]:8080").await,
+            Err(HubError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_is_public_address() {
+        for ip in ["127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254",
+                   "100.64.0.1", "0.0.0.0", "::1", "fe80::1", "fd00::1", "::ffff:127.0.0.1"] {
+            assert!(!is_public_address(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["93.184.216.34", "2606:4700::1111"] {
+            assert!(is_public_address(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn test_heartbeat() {
+        let service = setup_primary_service();
+
+        // First register
+        let identity = HubIdentity::generate("secondary-1");
+        service.register_hub(registration(&identity, "https://secondary1.example.com")).unwrap();
 
         // Then heartbeat
-        let heartbeat_req = HeartbeatRequest {
-            hub_id: "secondary-1".to_string(),
-            status: "healthy".to_string(),
-            stats: HubStats {
-                entities_count: 100,
-                agents_count: 10,
-                fragments_count: 50,
-                uptime_seconds: 3600.0,
-            },
-        };
+        let heartbeat_req = heartbeat(&identity, HubStats {
+            entities_count: 100,
+            agents_count: 10,
+            fragments_count: 50,
+            uptime_seconds: 3600.0,
+        });
 
-        let response = service.process_heartbeat(heartbeat_req).unwrap();
+        // Stats altered in transit
+        let mut tampered = heartbeat_req.clone();
+        tampered.stats.fragments_count = 5000;
+        assert!(service.process_heartbeat(tampered).is_err());
+
+        let response = service.process_heartbeat(heartbeat_req.clone()).unwrap();
         assert!(response.acknowledged);
+
+        // Replayed heartbeat
+        assert!(service.process_heartbeat(heartbeat_req).is_err());
+
+        // Heartbeat signed by another key
+        let spoofed = heartbeat(&HubIdentity::generate("secondary-1"), HubStats::default());
+        assert!(service.process_heartbeat(spoofed).is_err());
+    }
+
+    #[test]
+    fn test_inactive_hub_needs_verified_url() {
+        let dir = tempdir().unwrap();
+        let store = Arc::new(EntityStore::new(RocksStore::open(dir.path().to_str().unwrap()).unwrap()));
+        let service = DiscoveryService::new(DiscoveryConfig {
+            hub_id: "primary-hub".to_string(),
+            heartbeat_timeout_sec: 0,
+            ..Default::default()
+        }, store).unwrap();
+        let registry = service.registry.as_ref().unwrap();
+        let is_target = |hub_id: &str| service.get_federation_targets().iter().any(|h| h.hub_id == hub_id);
+
+        // Silence, a heartbeat and a re-registration do not stand in for the challenge
+        let squatter = HubIdentity::generate("squatter");
+        service.register_hub(registration(&squatter, "https://squatter.example.com")).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+        registry.check_inactive();
+        assert_eq!(registry.get("squatter").unwrap().status, HubStatus::Unknown);
+        service.process_heartbeat(heartbeat(&squatter, HubStats::default())).unwrap();
+        assert!(!is_target("squatter"));
+        service.register_hub(registration(&squatter, "https://squatter.example.com")).unwrap();
+        assert!(!is_target("squatter"));
+
+        // A verified hub comes back with its next heartbeat
+        let identity = HubIdentity::generate("secondary-1");
+        service.register_hub(registration(&identity, "https://secondary1.example.com")).unwrap();
+        registry.mark_verified("secondary-1", "https://secondary1.example.com", true);
+        std::thread::sleep(Duration::from_millis(10));
+        registry.check_inactive();
+        assert!(!is_target("secondary-1"));
+        service.process_heartbeat(heartbeat(&identity, HubStats::default())).unwrap();
+        assert!(is_target("secondary-1"));
+    }
+
+    #[test]
+    fn test_register_hub_rejects_takeover() {
+        let service = setup_primary_service();
+        let identity = HubIdentity::generate("secondary-1");
+        service.register_hub(registration(&identity, "https://secondary1.example.com")).unwrap();
+
+        // Another key claiming the same hub_id cannot move it
+        let impostor = HubIdentity::generate("secondary-1");
+        let result = service.register_hub(registration(&impostor, "https://evil.example.com"));
+        assert!(matches!(result, Err(HubError::Unauthorized(_))));
+
+        // Signature that does not cover the submitted URL
+        let mut req = registration(&identity, "https://secondary1.example.com");
+        req.public_url = "https://evil.example.com".to_string();
+        assert!(service.register_hub(req).is_err());
+
+        // Stale timestamp
+        let mut req = registration(&identity, "https://secondary1.example.com");
+        req.timestamp -= chrono::Duration::seconds(MAX_REQUEST_AGE_SECS + 60);
+        req.signature = identity.sign_message(&registration_message(
+            "secondary-1", &req.public_url, req.timestamp,
+        ));
+        assert!(service.register_hub(req).is_err());
+
+        let hub = service.get_known_hubs().unwrap().hubs
+            .into_iter()
+            .find(|h| h.hub_id == "secondary-1")
+            .unwrap();
+        assert_eq!(hub.public_url, "https://secondary1.example.com");
     }
 
     #[test]
@@ -397,13 +1448,8 @@ mod tests {
 
         // Register some hubs
         for i in 1..=3 {
-            let req = RegisterHubRequest {
-                hub_id: format!("secondary-{}", i),
-                public_url: format!("https://secondary{}.example.com", i),
-                capabilities: vec!["entities".to_string()],
-                version: None,
-                public_key: None,
-            };
+            let identity = HubIdentity::generate(format!("secondary-{}", i));
+            let req = registration(&identity, &format!("https://secondary{}.example.com", i));
             service.register_hub(req).unwrap();
         }
 
@@ -411,4 +1457,257 @@ mod tests {
         // 3 secondary + 1 primary (self)
         assert_eq!(hub_list.hubs.len(), 4);
     }
+
+    #[test]
+    fn test_descriptor_uses_identity() {
+        let identity = HubIdentity::generate("primary-hub");
+        let public_key = identity.public_key();
+        let service = setup_primary_service().with_identity(identity);
+
+        let descriptor = service.descriptor();
+        assert_eq!(descriptor.hub_id, "primary-hub");
+        assert_eq!(descriptor.public_key, public_key);
+        assert_eq!(service.self_info().public_key, Some(public_key));
+    }
+
+    #[test]
+    fn test_unverified_hub_not_federated() {
+        let service = setup_primary_service();
+        let identity = HubIdentity::generate("secondary-1");
+        service.register_hub(registration(&identity, "https://secondary1.example.com")).unwrap();
+
+        let hub = service.registry.as_ref().unwrap().get("secondary-1").unwrap();
+        assert_eq!(hub.status, HubStatus::Unknown);
+        assert!(service.get_federation_targets().is_empty());
+
+        service.registry.as_ref().unwrap()
+            .mark_verified("secondary-1", "https://secondary1.example.com", true);
+        assert_eq!(service.get_federation_targets().len(), 1);
+
+        // Re-registering the verified URL with the same key keeps the hub federated
+        service.register_hub(registration(&identity, "https://secondary1.example.com")).unwrap();
+        assert_eq!(service.get_federation_targets().len(), 1);
+
+        // Moving to another URL needs a new check
+        service.register_hub(registration(&identity, "https://secondary1.example.org")).unwrap();
+        assert!(service.get_federation_targets().is_empty());
+    }
+
+    #[test]
+    fn test_key_rotation_notice() {
+        let service = setup_primary_service();
+        let identity = HubIdentity::generate("secondary-1");
+        service.register_hub(registration(&identity, "https://secondary1.example.com")).unwrap();
+
+        let envelope = |identity: &HubIdentity, server_port: &str| {
+            let notice = KeyRotationNotice { agent: Address::agent(server_port, "agent-1") };
+            serde_json::to_vec(&serde_json::json!({
+                "success": true,
+                "data": notice,
+                "signature": identity.sign(&notice).unwrap(),
+            }))
+            .unwrap()
+        };
+        let own_agent = envelope(&identity, "secondary1.example.com");
+
+        // Only verified hubs may announce rotations
+        assert!(service.verify_key_rotation_notice(&own_agent).is_err());
+        service.registry.as_ref().unwrap()
+            .mark_verified("secondary-1", "https://secondary1.example.com", true);
+        let notice = service.verify_key_rotation_notice(&own_agent).unwrap();
+        assert_eq!(notice.agent.entity, "agent-1");
+
+        // ... and only for their own agents, under their registered key
+        assert!(service.verify_key_rotation_notice(&envelope(&identity, "victim.example.com")).is_err());
+        let impostor = HubIdentity::generate("secondary-1");
+        assert!(service.verify_key_rotation_notice(&envelope(&impostor, "secondary1.example.com")).is_err());
+        assert!(service.verify_key_rotation_notice(br#"{"success":true,"data":{}}"#).is_err());
+    }
+
+    #[test]
+    fn test_federation_targets_prefer_fast_hubs() {
+        let service = setup_primary_service();
+        let registry = service.registry.as_ref().unwrap();
+        for (hub_id, url) in [("fast", "https://fast.example.com"), ("slow", "https://slow.example.com"),
+            ("down", "https://down.example.com"), ("new", "https://new.example.com")]
+        {
+            let identity = HubIdentity::generate(hub_id);
+            service.register_hub(registration(&identity, url)).unwrap();
+            registry.mark_verified(hub_id, url, true);
+        }
+
+        let health = service.hub_health();
+        for _ in 0..5 {
+            health.record("fast", Some(Duration::from_millis(10)));
+            health.record("slow", Some(Duration::from_millis(2000)));
+            health.record("down", None);
+        }
+
+        let targets: Vec<(String, HubStatus)> = service
+            .get_federation_targets()
+            .into_iter()
+            .map(|h| (h.hub_id, h.status))
+            .collect();
+        assert_eq!(targets, [
+            ("fast".to_string(), HubStatus::Healthy),
+            ("new".to_string(), HubStatus::Healthy),
+            ("slow".to_string(), HubStatus::Degraded),
+        ]);
+
+        // The served hub list keeps the registry status: other hubs probe
+        // for themselves
+        let list = service.get_known_hubs().unwrap();
+        let down = list.hubs.iter().find(|h| h.hub_id == "down").unwrap();
+        assert_eq!(down.status, HubStatus::Healthy);
+
+        // A hub comes back once its probes succeed again
+        health.record("down", Some(Duration::from_millis(10)));
+        assert!(service.get_federation_targets().iter().any(|h| h.hub_id == "down"));
+    }
+
+    #[test]
+    fn test_capability_negotiation() {
+        let service = setup_primary_service();
+        let registry = service.registry.as_ref().unwrap();
+        // Primaries serve replication, gossip needs gossip mode
+        let own = service.self_info().capabilities;
+        assert!(own.contains(&Capability::Replication));
+        assert!(!own.contains(&Capability::Gossip));
+
+        let identity = HubIdentity::generate("search-hub");
+        let mut request = registration(&identity, "https://search.example.com");
+        request.capabilities = CapabilitySet::from_names(["entities", "search", "search.signed"]);
+        service.register_hub(request).unwrap();
+        registry.mark_verified("search-hub", "https://search.example.com", true);
+
+        let identity = HubIdentity::generate("storage-hub");
+        service.register_hub(registration(&identity, "https://storage.example.com")).unwrap();
+        registry.mark_verified("storage-hub", "https://storage.example.com", true);
+
+        // Protocol 1 hubs with the old defaults sign search responses too
+        let identity = HubIdentity::generate("legacy-hub");
+        let mut request = registration(&identity, "https://legacy.example.com");
+        request.capabilities = CapabilitySet::from_names(["entities", "trust", "search"]);
+        request.protocol_version = 1;
+        service.register_hub(request).unwrap();
+        registry.mark_verified("legacy-hub", "https://legacy.example.com", true);
+
+        let mut search: Vec<String> = service
+            .compatible_targets(&[Capability::Search, Capability::SearchSigned])
+            .into_iter()
+            .filter(|(_, common)| !common.contains(&Capability::SearchLimit))
+            .map(|(hub, _)| hub.hub_id)
+            .collect();
+        search.sort();
+        assert_eq!(search, ["legacy-hub", "search-hub"]);
+        assert_eq!(service.compatible_targets(&[Capability::Entities]).len(), 3);
+
+        // Replication and gossip are only asked of hubs that share them
+        let storage = registry.list().hubs.into_iter().find(|h| h.hub_id == "storage-hub").unwrap();
+        assert!(service.require_capability(&storage, Capability::Replication).is_err());
+        assert!(service.require_capability(&service.self_info(), Capability::Replication).is_ok());
+        assert!(service.require_capability(&service.self_info(), Capability::Gossip).is_err());
+
+        let identity = HubIdentity::generate("ancient-hub");
+        let mut request = registration(&identity, "https://ancient.example.com");
+        request.protocol_version = 0;
+        assert!(service.register_hub(request).is_err());
+    }
+
+    #[test]
+    fn test_check_challenge_response() {
+        let identity = HubIdentity::generate("secondary-1");
+        let hub = HubInfo {
+            hub_id: "secondary-1".to_string(),
+            public_url: "https://secondary1.example.com".to_string(),
+            role: "secondary".to_string(),
+            status: HubStatus::Unknown,
+            last_seen: Utc::now(),
+            capabilities: CapabilitySet::default(),
+            stats: HubStats::default(),
+            public_key: Some(identity.public_key()),
+            protocol_version: PROTOCOL_VERSION,
+            version: None,
+            verified: false,
+        };
+        let answer = |identity: &HubIdentity, public_url: &str, nonce: &str| {
+            let descriptor = HubDescriptor {
+                hub_id: "secondary-1".to_string(),
+                public_key: identity.public_key(),
+                public_url: public_url.to_string(),
+                role: "secondary".to_string(),
+                version: "0.1.0".to_string(),
+                protocol_version: PROTOCOL_VERSION,
+                capabilities: CapabilitySet::default(),
+                nonce: Some(nonce.to_string()),
+            };
+            serde_json::to_vec(&serde_json::json!({
+                "success": true,
+                "signature": identity.sign(&descriptor).unwrap(),
+                "data": descriptor,
+            }))
+            .unwrap()
+        };
+
+        let body = answer(&identity, "https://secondary1.example.com/", "n-1");
+        assert!(check_challenge_response(&body, &hub, "n-1").is_ok());
+        // Stale nonce
+        assert!(check_challenge_response(&body, &hub, "n-2").is_err());
+        // URL served by a hub with another key
+        let body = answer(&HubIdentity::generate("secondary-1"), "https://secondary1.example.com", "n-1");
+        assert!(check_challenge_response(&body, &hub, "n-1").is_err());
+        // Hub describing a different URL
+        let body = answer(&identity, "https://elsewhere.example.com", "n-1");
+        assert!(check_challenge_response(&body, &hub, "n-1").is_err());
+    }
+
+    #[test]
+    fn test_registry_survives_restart() {
+        let dir = tempdir().unwrap();
+        let config = DiscoveryConfig {
+            role: HubRole::Primary,
+            hub_id: "primary-hub".to_string(),
+            public_url: "https://primary.example.com".to_string(),
+            hub_retention_sec: 3600,
+            ..Default::default()
+        };
+        let open = || {
+            let rocks = RocksStore::open(dir.path().to_str().unwrap()).unwrap();
+            DiscoveryService::new(config.clone(), Arc::new(EntityStore::new(rocks))).unwrap()
+        };
+
+        let identity = HubIdentity::generate("secondary-1");
+        let stale = HubIdentity::generate("secondary-2");
+        let version = {
+            let service = open();
+            service.register_hub(registration(&identity, "https://secondary1.example.com")).unwrap();
+            service.register_hub(registration(&stale, "https://secondary2.example.com")).unwrap();
+            let registry = service.registry.as_ref().unwrap();
+            registry.mark_verified("secondary-1", "https://secondary1.example.com", true);
+            registry.mark_verified("secondary-2", "https://secondary2.example.com", true);
+
+            // Last seen long before the retention period
+            let mut hub = registry.get("secondary-2").unwrap();
+            hub.last_seen = Utc::now() - chrono::Duration::hours(2);
+            hub.status = HubStatus::Inactive;
+            registry.register(hub);
+            registry.list().version
+        };
+
+        let service = open();
+        let registry = service.registry.as_ref().unwrap();
+        let hub = registry.get("secondary-1").unwrap();
+        assert_eq!(hub.public_url, "https://secondary1.example.com");
+        assert_eq!(hub.status, HubStatus::Healthy);
+        assert_eq!(service.get_federation_targets().len(), 1);
+
+        // The stale hub was pruned at startup, but its verified key stays pinned
+        assert!(registry.get("secondary-2").is_none());
+        assert_eq!(registry.pinned_key("secondary-2"), Some(stale.public_key()));
+        assert!(registry.list().version > version);
+
+        // Key pins and replay protection survive the restart
+        let impostor = HubIdentity::generate("secondary-1");
+        assert!(service.register_hub(registration(&impostor, "https://evil.example.com")).is_err());
+    }
 }
@@ -0,0 +1,501 @@
+//! Background tasks driving hub discovery
+//!
+//! Secondary hubs register with the primary, send heartbeats and refresh
+//! their hub list; primaries mark silent hubs inactive and replicate each
+//! other's registries. In gossip mode
+//! every hub exchanges entries with random peers instead. Each task runs on
+//! a jittered interval, backs off exponentially while failing and stops on
+//! shutdown.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use rand::Rng;
+use serde::Serialize;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::discovery::HubHealth;
+use crate::models::HubResult;
+use super::DiscoveryService;
+
+/// First retry after a failure; doubles per consecutive failure
+const INITIAL_BACKOFF_SECS: u64 = 5;
+/// Runs start up to this fraction of their interval early
+const JITTER_FRACTION: f64 = 0.1;
+
+/// Status of one discovery task
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TaskStatus {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_success_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    pub consecutive_failures: u32,
+    /// Unset once the task stopped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_run_at: Option<DateTime<Utc>>,
+}
+
+/// Discovery status reported on `/health`
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscoveryStatus {
+    pub role: String,
+    /// "registry" or "gossip"
+    pub mode: String,
+    /// Primary the hub currently talks to (secondary hubs)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary_hub_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_registration_at: Option<DateTime<Utc>>,
+    /// Hubs currently used for federation
+    pub federation_targets: usize,
+    /// Probe latency and error statistics per hub
+    pub hub_health: Vec<HubHealth>,
+    pub tasks: Vec<TaskStatus>,
+}
+
+type TaskStatusMap = Arc<RwLock<BTreeMap<&'static str, TaskStatus>>>;
+
+/// Read access to the discovery tasks' state
+#[derive(Clone)]
+pub struct DiscoveryMonitor {
+    service: Arc<DiscoveryService>,
+    tasks: TaskStatusMap,
+}
+
+impl DiscoveryMonitor {
+    /// Current discovery status
+    pub fn status(&self) -> DiscoveryStatus {
+        DiscoveryStatus {
+            role: self.service.self_info().role,
+            mode: if self.service.is_gossip() { "gossip" } else { "registry" }.to_string(),
+            primary_hub_url: self.service.active_primary_url(),
+            last_registration_at: self.service.last_registration(),
+            federation_targets: self.service.get_federation_targets().len(),
+            hub_health: self.service.hub_health().all(),
+            tasks: self.tasks.read().values().cloned().collect(),
+        }
+    }
+}
+
+/// Running discovery tasks
+pub struct DiscoveryTasks {
+    shutdown: watch::Sender<bool>,
+    handles: Vec<JoinHandle<()>>,
+    monitor: DiscoveryMonitor,
+}
+
+impl DiscoveryTasks {
+    /// Start the tasks for the hub's role and discovery mode
+    pub fn start(service: Arc<DiscoveryService>) -> Self {
+        let (shutdown, shutdown_rx) = watch::channel(false);
+        let tasks: TaskStatusMap = Arc::new(RwLock::new(BTreeMap::new()));
+        let config = service.config().clone();
+        let mut handles = Vec::new();
+
+        if service.is_gossip() {
+            handles.push(spawn_task(
+                "gossip",
+                Duration::from_secs(config.gossip_interval_sec),
+                Arc::clone(&service),
+                Arc::clone(&tasks),
+                shutdown_rx.clone(),
+                |service| async move {
+                    service.check_inactive_hubs();
+                    service.gossip_round().await.map(|_| ())
+                },
+            ));
+        } else if service.is_primary() {
+            handles.push(spawn_task(
+                "inactive_check",
+                Duration::from_secs(config.registration_interval_sec),
+                Arc::clone(&service),
+                Arc::clone(&tasks),
+                shutdown_rx.clone(),
+                |service| async move {
+                    service.check_inactive_hubs();
+                    Ok(())
+                },
+            ));
+            if !config.primary_urls().is_empty() {
+                handles.push(spawn_task(
+                    "replication",
+                    Duration::from_secs(config.hub_list_refresh_sec),
+                    Arc::clone(&service),
+                    Arc::clone(&tasks),
+                    shutdown_rx.clone(),
+                    |service| async move { service.replicate_registry().await.map(|_| ()) },
+                ));
+            }
+        } else {
+            handles.push(spawn_task(
+                "membership",
+                Duration::from_secs(config.registration_interval_sec),
+                Arc::clone(&service),
+                Arc::clone(&tasks),
+                shutdown_rx.clone(),
+                |service| async move { service.maintain_membership().await },
+            ));
+            handles.push(spawn_task(
+                "hub_list",
+                Duration::from_secs(config.hub_list_refresh_sec),
+                Arc::clone(&service),
+                Arc::clone(&tasks),
+                shutdown_rx.clone(),
+                |service| async move { service.refresh_hub_list().await.map(|_| ()) },
+            ));
+        }
+        if config.probe_interval_sec > 0 {
+            handles.push(spawn_task(
+                "probe",
+                Duration::from_secs(config.probe_interval_sec),
+                Arc::clone(&service),
+                Arc::clone(&tasks),
+                shutdown_rx,
+                |service| async move { service.probe_hubs().await.map(|_| ()) },
+            ));
+        }
+        info!("Started {} discovery tasks", handles.len());
+
+        Self {
+            shutdown,
+            handles,
+            monitor: DiscoveryMonitor { service, tasks },
+        }
+    }
+
+    /// Handle for reporting task status
+    pub fn monitor(&self) -> DiscoveryMonitor {
+        self.monitor.clone()
+    }
+
+    /// Stop all tasks, abandoning in-flight requests, and wait for them to exit
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(true);
+        for handle in self.handles {
+            let _ = handle.await;
+        }
+        info!("Discovery tasks stopped");
+    }
+}
+
+/// Delay before the next run.
+///
+/// The regular interval, or while failing an exponential backoff from
+/// `INITIAL_BACKOFF_SECS` capped at the interval; `jitter` in `[0, 1)` moves
+/// the run up to `JITTER_FRACTION` earlier so hubs do not synchronize.
+pub fn next_delay(interval: Duration, consecutive_failures: u32, jitter: f64) -> Duration {
+    let base = if consecutive_failures == 0 {
+        interval
+    } else {
+        let exponent = (consecutive_failures - 1).min(16);
+        Duration::from_secs(INITIAL_BACKOFF_SECS.saturating_mul(1 << exponent)).min(interval)
+    };
+    base.mul_f64(1.0 - JITTER_FRACTION * jitter.clamp(0.0, 1.0))
+}
+
+fn run_at(delay: Duration) -> Option<DateTime<Utc>> {
+    chrono::Duration::from_std(delay).ok().map(|d| Utc::now() + d)
+}
+
+fn spawn_task<F, Fut>(
+    name: &'static str,
+    interval: Duration,
+    service: Arc<DiscoveryService>,
+    tasks: TaskStatusMap,
+    mut shutdown: watch::Receiver<bool>,
+    run: F,
+) -> JoinHandle<()>
+where
+    F: Fn(Arc<DiscoveryService>) -> Fut + Send + 'static,
+    Fut: Future<Output = HubResult<()>> + Send + 'static,
+{
+    // The first run waits a moment so the HTTP server is up when the
+    // primary calls back to verify this hub's URL
+    let mut delay = next_delay(
+        Duration::from_secs(INITIAL_BACKOFF_SECS).min(interval),
+        0,
+        rand::thread_rng().gen(),
+    );
+    tasks.write().insert(name, TaskStatus {
+        name: name.to_string(),
+        next_run_at: run_at(delay),
+        ..Default::default()
+    });
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = shutdown.changed() => break,
+            }
+
+            let result = tokio::select! {
+                result = run(Arc::clone(&service)) => result,
+                _ = shutdown.changed() => break,
+            };
+
+            let failures = {
+                let mut tasks = tasks.write();
+                let status = match tasks.get_mut(name) {
+                    Some(status) => status,
+                    None => break,
+                };
+                match result {
+                    Ok(()) => {
+                        status.last_success_at = Some(Utc::now());
+                        status.last_error = None;
+                        status.consecutive_failures = 0;
+                    }
+                    Err(e) => {
+                        status.last_error = Some(e.to_string());
+                        status.consecutive_failures += 1;
+                    }
+                }
+                delay = next_delay(interval, status.consecutive_failures, rand::thread_rng().gen());
+                status.next_run_at = run_at(delay);
+                status.consecutive_failures
+            };
+
+            if failures > 0 {
+                warn!("Discovery task {} failed {} times in a row, retrying in {:?}", name, failures, delay);
+            } else {
+                debug!("Discovery task {} succeeded", name);
+            }
+        }
+
+        if let Some(status) = tasks.write().get_mut(name) {
+            status.next_run_at = None;
+        }
+        debug!("Discovery task {} stopped", name);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{web, App, HttpServer};
+    use tempfile::{tempdir, TempDir};
+
+    use crate::api::{configure_routes, ApiState, AppState};
+    use crate::config::{DiscoveryMode, HubRole, ResourceSettings};
+    use crate::crypto::HubIdentity;
+    use crate::discovery::HubStatus;
+    use crate::resources::ResourceMonitor;
+    use crate::services::{DiscoveryConfig, TrustConfig};
+    use crate::store::{EntityStore, RocksStore};
+
+    /// Serve a registry-mode hub with the full REST API on a local port
+    fn spawn_hub(role: HubRole, hub_id: &str, primary_hub_url: Option<String>) -> (ApiState, String, TempDir) {
+        spawn_hub_with(hub_id, DiscoveryConfig {
+            role,
+            primary_hub_url,
+            ..Default::default()
+        })
+    }
+
+    /// Serve a hub with the full REST API on a local port, filling in the
+    /// hub_id and public_url of `config`.
+    ///
+    /// The returned directory holds the hub's database; keep it alive while
+    /// the hub runs.
+    fn spawn_hub_with(hub_id: &str, config: DiscoveryConfig) -> (ApiState, String, TempDir) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let public_url = format!("http://{}", listener.local_addr().unwrap());
+
+        let dir = tempdir().unwrap();
+        let rocks = RocksStore::open(dir.path().to_str().unwrap()).unwrap();
+        let config = DiscoveryConfig {
+            hub_id: hub_id.to_string(),
+            public_url: public_url.clone(),
+            allow_private_hub_urls: true,
+            ..config
+        };
+        let state = ApiState::new(
+            Arc::new(EntityStore::new(rocks)),
+            TrustConfig::default(),
+            config,
+            HubIdentity::generate(hub_id),
+            Arc::new(ResourceMonitor::new(ResourceSettings::default())),
+        )
+        .unwrap();
+
+        let app_state = AppState::new(hub_id);
+        let api_state = state.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(web::Data::new(app_state.clone()))
+                .app_data(web::Data::new(api_state.clone()))
+                .configure(configure_routes)
+        })
+        .workers(1)
+        .listen(listener)
+        .unwrap()
+        .run();
+        actix_rt::spawn(server);
+
+        (state, public_url, dir)
+    }
+
+    #[test]
+    fn test_next_delay() {
+        let interval = Duration::from_secs(300);
+
+        assert_eq!(next_delay(interval, 0, 0.0), interval);
+        assert_eq!(next_delay(interval, 1, 0.0), Duration::from_secs(5));
+        assert_eq!(next_delay(interval, 3, 0.0), Duration::from_secs(20));
+        // Never slower than the regular schedule
+        assert_eq!(next_delay(interval, 10, 0.0), interval);
+        assert_eq!(next_delay(interval, u32::MAX, 0.0), interval);
+        // Jitter only moves runs earlier
+        assert_eq!(next_delay(interval, 0, 1.0), Duration::from_secs(270));
+    }
+
+    #[actix_rt::test]
+    async fn test_secondary_joins_stand_in_primary() {
+        let (primary, primary_url, _primary_dir) = spawn_hub(HubRole::Primary, "primary-hub", None);
+        let (secondary, _, _secondary_dir) = spawn_hub(HubRole::Secondary, "secondary-hub", Some(primary_url));
+        let discovery = &secondary.discovery_service;
+
+        // Registers, then the primary verifies the secondary's URL
+        discovery.maintain_membership().await.unwrap();
+        assert!(discovery.last_registration().is_some());
+        let mut verified = false;
+        for _ in 0..100 {
+            if primary.discovery_service.get_federation_targets().iter().any(|h| h.hub_id == "secondary-hub") {
+                verified = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert!(verified);
+
+        // The primary probes the secondary's health endpoint
+        assert_eq!(primary.discovery_service.probe_hubs().await.unwrap(), 1);
+        let health = primary.discovery_service.hub_health().health("secondary-hub").unwrap();
+        assert_eq!(health.status, HubStatus::Healthy);
+        assert!(health.p50_ms.is_some());
+
+        // Heartbeat, signed by the secondary and acknowledged in a signed response
+        assert!(!discovery.needs_registration());
+        discovery.maintain_membership().await.unwrap();
+
+        let list = discovery.refresh_hub_list().await.unwrap();
+        assert_eq!(list.hubs.len(), 2);
+        let targets = discovery.get_federation_targets();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].hub_id, "primary-hub");
+    }
+
+    #[actix_rt::test]
+    async fn test_tasks_report_status_and_shut_down() {
+        // Nothing listens at the primary URL
+        let (secondary, _, _secondary_dir) = spawn_hub(
+            HubRole::Secondary,
+            "secondary-hub",
+            Some("http://127.0.0.1:9".to_string()),
+        );
+        let tasks = DiscoveryTasks::start(Arc::clone(&secondary.discovery_service));
+        let monitor = tasks.monitor();
+
+        let status = monitor.status();
+        assert_eq!(status.role, "secondary");
+        assert_eq!(status.tasks.len(), 3);
+        assert!(status.tasks.iter().all(|t| t.next_run_at.is_some()));
+
+        tokio::time::timeout(Duration::from_secs(1), tasks.shutdown())
+            .await
+            .expect("discovery tasks did not stop");
+        assert!(monitor.status().tasks.iter().all(|t| t.next_run_at.is_none()));
+    }
+
+    #[actix_rt::test]
+    async fn test_gossip_converges_from_seeds() {
+        let gossip = |seed_hubs: Vec<String>| DiscoveryConfig {
+            mode: DiscoveryMode::Gossip,
+            seed_hubs,
+            ..Default::default()
+        };
+        let (a, a_url, _a_dir) = spawn_hub_with("hub-a", gossip(vec![]));
+        let (b, b_url, _b_dir) = spawn_hub_with("hub-b", gossip(vec![a_url]));
+        let (c, _, _c_dir) = spawn_hub_with("hub-c", gossip(vec![b_url]));
+        let hubs = [&a, &b, &c];
+
+        // Nobody to talk to yet
+        assert_eq!(a.discovery_service.gossip_round().await.unwrap(), 0);
+
+        // B and C only know their seeds; A learns about C through B
+        for _ in 0..3 {
+            for hub in hubs {
+                hub.discovery_service.gossip_round().await.unwrap();
+            }
+        }
+
+        // Every hub verified every other hub's URL by talking to it
+        for hub in hubs {
+            let discovery = &hub.discovery_service;
+            let mut targets: Vec<String> = discovery
+                .get_federation_targets()
+                .into_iter()
+                .map(|h| h.hub_id)
+                .collect();
+            targets.sort();
+            let expected: Vec<&str> = ["hub-a", "hub-b", "hub-c"]
+                .into_iter()
+                .filter(|id| *id != discovery.hub_id())
+                .collect();
+            assert_eq!(targets, expected);
+            assert_eq!(discovery.get_known_hubs().unwrap().hubs.len(), 3);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_failover_and_registry_replication() {
+        let (primary_1, primary_1_url, _primary_1_dir) = spawn_hub(HubRole::Primary, "primary-1", None);
+        let (primary_2, _, _primary_2_dir) = spawn_hub_with("primary-2", DiscoveryConfig {
+            role: HubRole::Primary,
+            primary_hub_urls: vec![primary_1_url.clone()],
+            ..Default::default()
+        });
+        // The preferred primary is down
+        let (secondary, _, _secondary_dir) = spawn_hub_with("secondary-hub", DiscoveryConfig {
+            role: HubRole::Secondary,
+            primary_hub_url: Some("http://127.0.0.1:9".to_string()),
+            primary_hub_urls: vec![primary_1_url.clone()],
+            ..Default::default()
+        });
+
+        let discovery = &secondary.discovery_service;
+        discovery.maintain_membership().await.unwrap();
+        assert_eq!(discovery.active_primary_url(), Some(primary_1_url));
+        let mut verified = false;
+        for _ in 0..100 {
+            if primary_1.discovery_service.get_federation_targets().iter().any(|h| h.hub_id == "secondary-hub") {
+                verified = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert!(verified);
+
+        // The second primary learns the secondary and the first primary
+        assert_eq!(primary_2.discovery_service.replicate_registry().await.unwrap(), 1);
+        let mut targets: Vec<String> = primary_2.discovery_service
+            .get_federation_targets()
+            .into_iter()
+            .map(|h| h.hub_id)
+            .collect();
+        targets.sort();
+        assert_eq!(targets, ["primary-1", "secondary-hub"]);
+
+        // Heartbeats keep going to the primary that answered
+        discovery.maintain_membership().await.unwrap();
+        assert_eq!(discovery.refresh_hub_list().await.unwrap().hubs.len(), 2);
+    }
+}
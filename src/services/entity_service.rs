@@ -1,23 +1,32 @@
 //! Entity service with signature verification
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use serde_json::json;
 
 use crate::crypto::{canonical_json, verify_with_key};
 use crate::models::{
-    Agent, CreateAgentRequest, Fragment, CreateFragmentRequest,
-    Relation, CreateRelationRequest, Tag, CreateTagRequest,
-    Transform, CreateTransformRequest,
-    HubError, HubResult, Domain,
+    Agent, CreateAgentRequest, UpdateTrustRequest, RotateKeyRequest, RotationSigner, TrustScope, Fragment, CreateFragmentRequest,
+    Relation, CreateRelationRequest, RelationType, Tag, CreateTagRequest,
+    Transform, CreateTransformRequest, Vote, CreateVoteRequest, VoteKind, VoteSummary, CountedEvidence,
+    KeyRevocation, RevokeKeyRequest, RevocationSigner, ConfirmContentRequest, Quarantine, QuarantineChange,
+    Address, HubError, HubResult, Domain,
 };
 use crate::store::{EntityStore, Cursor, ListResult};
+use crate::trust::verify_key_rotation;
+use super::TrustService;
+
+/// Tolerated clock difference for client-supplied timestamps
+const MAX_CLOCK_SKEW_SECS: i64 = 300;
 
 /// Entity service handling business logic and validation
 #[derive(Clone)]
 pub struct EntityService {
     store: Arc<EntityStore>,
     verify_signatures: bool,
+    /// Trust service whose cache is kept in step with agent changes
+    trust_service: Option<Arc<TrustService>>,
 }
 
 impl EntityService {
@@ -26,6 +35,7 @@ impl EntityService {
         Self {
             store,
             verify_signatures: true,
+            trust_service: None,
         }
     }
 
@@ -34,9 +44,16 @@ impl EntityService {
         Self {
             store,
             verify_signatures: false,
+            trust_service: None,
         }
     }
 
+    /// Invalidate cached trust stores of `trust_service` when agents change
+    pub fn with_trust_service(mut self, trust_service: Arc<TrustService>) -> Self {
+        self.trust_service = Some(trust_service);
+        self
+    }
+
     /// Get the underlying store
     pub fn store(&self) -> &EntityStore {
         &self.store
@@ -52,6 +69,20 @@ impl EntityService {
         if req.public_key.is_empty() {
             return Err(HubError::InvalidPublicKey("Public key cannot be empty".to_string()));
         }
+        if req.recovery_key.as_deref() == Some(req.public_key.as_str()) {
+            return Err(HubError::ValidationError(
+                "recovery_key must differ from public_key".to_string(),
+            ));
+        }
+        // Future timestamps would keep full trust weight forever under decay
+        let latest = chrono::Utc::now() + chrono::Duration::seconds(MAX_CLOCK_SKEW_SECS);
+        if let Some(ref trust) = req.trust {
+            if let Some(i) = trust.trusts.iter().position(|t| t.asserted_at.is_some_and(|at| at > latest)) {
+                return Err(HubError::ValidationError(format!(
+                    "trust.trusts[{}].asserted_at lies in the future", i
+                )));
+            }
+        }
 
         // Verify signature if enabled
         if self.verify_signatures {
@@ -65,19 +96,7 @@ impl EntityService {
 
     /// Verify agent signature using canonical JSON over all fields
     fn verify_agent_signature(&self, req: &CreateAgentRequest) -> HubResult<()> {
-        let uuid = req.uuid.clone().unwrap_or_default();
-        let trust_val = req.trust.as_ref()
-            .map(|t| serde_json::to_value(t).unwrap())
-            .unwrap_or_else(|| json!({"num_trusts": 0, "trusts": []}));
-
-        let payload = json!({
-            "description": req.description.as_deref().unwrap_or(""),
-            "primary_hub": req.primary_hub.as_deref().unwrap_or(""),
-            "public_key": req.public_key,
-            "trust": trust_val,
-            "uuid": uuid,
-        });
-        let data = canonical_json(&payload);
+        let data = canonical_json(&req.signing_payload());
         let is_valid = verify_with_key(&req.public_key, data.as_bytes(), &req.signature)?;
 
         if !is_valid {
@@ -99,6 +118,14 @@ impl EntityService {
             })
     }
 
+    /// Get an agent by address, if it lives on this hub
+    fn get_local_agent(&self, addr: &Address) -> HubResult<Agent> {
+        if self.trust_service.as_ref().is_some_and(|trust| !trust.is_local(addr)) {
+            return Err(HubError::ValidationError(format!("{} is not an agent of this hub", addr)));
+        }
+        self.get_agent(&addr.entity)
+    }
+
     /// List agents with pagination
     pub fn list_agents(&self, cursor: Option<&str>, limit: usize) -> HubResult<ListResult<Agent>> {
         let cursor = cursor
@@ -108,11 +135,403 @@ impl EntityService {
         self.store.list_agents(&cursor, limit.min(100))
     }
 
+    /// Add, change or remove trust entries of an existing agent
+    pub fn update_agent_trust(&self, uuid: &str, req: UpdateTrustRequest) -> HubResult<Agent> {
+        if req.updates.is_empty() {
+            return Err(HubError::ValidationError("updates must not be empty".to_string()));
+        }
+        let now = chrono::Utc::now();
+        for (i, update) in req.updates.iter().enumerate() {
+            if update.agent.domain != Domain::Agent {
+                return Err(HubError::ValidationError(format!(
+                    "updates[{}].agent must be an agent address", i
+                )));
+            }
+            if update.agent.entity == uuid {
+                return Err(HubError::ValidationError(format!(
+                    "updates[{}]: an agent cannot trust itself", i
+                )));
+            }
+            if let Some(TrustScope::Tag(tag)) = &update.scope {
+                if tag.domain != Domain::Tag {
+                    return Err(HubError::ValidationError(format!(
+                        "updates[{}].scope must reference a tag address", i
+                    )));
+                }
+            }
+            match update.asserted_at {
+                Some(asserted_at) if asserted_at > now + chrono::Duration::seconds(MAX_CLOCK_SKEW_SECS) => {
+                    return Err(HubError::ValidationError(format!(
+                        "updates[{}].asserted_at lies in the future", i
+                    )));
+                }
+                None if update.trust.is_some() => {
+                    return Err(HubError::ValidationError(format!(
+                        "updates[{}].asserted_at is required when setting trust", i
+                    )));
+                }
+                _ => {}
+            }
+            if let Some(level) = update.trust {
+                if !(-1.0..=1.0).contains(&level) {
+                    return Err(HubError::ValidationError(format!(
+                        "updates[{}].trust must be between -1.0 and 1.0", i
+                    )));
+                }
+            }
+        }
+
+        let agent = self.store
+            .try_update_agent(uuid, |agent| {
+                if req.version != agent.version + 1 {
+                    return Err(HubError::ValidationError(format!(
+                        "version must be {} (current version + 1)",
+                        agent.version + 1
+                    )));
+                }
+                for update in &req.updates {
+                    agent.apply_trust_update(update);
+                }
+                agent.increment_version();
+
+                // The signature covers the resulting trust store, not the diff
+                if self.verify_signatures {
+                    let data = canonical_json(&agent.trust_state_payload());
+                    if !verify_with_key(&agent.public_key, data.as_bytes(), &req.signature)? {
+                        return Err(HubError::InvalidSignature {
+                            entity_type: "trust_update".to_string(),
+                        });
+                    }
+                }
+
+                agent.updated_at = now;
+                agent.trust_signature = Some(req.signature);
+                Ok(())
+            })?
+            .ok_or_else(|| HubError::not_found("agent", uuid))?;
+        self.agent_changed(uuid);
+        Ok(agent)
+    }
+
+    /// Rotate an agent's key.
+    ///
+    /// The rotation is signed by the current key, or by the recovery key when
+    /// the current key is lost, and the rotated agent by the new key. Retired
+    /// keys stay in the key history so earlier signatures keep verifying.
+    pub fn rotate_agent_key(&self, uuid: &str, req: RotateKeyRequest) -> HubResult<Agent> {
+        let RotateKeyRequest { rotation, signature } = req;
+
+        if rotation.new_public_key.is_empty() {
+            return Err(HubError::InvalidPublicKey("new_public_key cannot be empty".to_string()));
+        }
+        let now = chrono::Utc::now();
+        if rotation.rotated_at > now + chrono::Duration::seconds(MAX_CLOCK_SKEW_SECS) {
+            return Err(HubError::ValidationError("rotated_at lies in the future".to_string()));
+        }
+
+        // Checked against the agent as stored, so concurrent rotations cannot both pass
+        let agent = self.store
+            .try_update_agent(uuid, |agent| {
+                if agent.has_used_key(&rotation.new_public_key) {
+                    return Err(HubError::ValidationError(
+                        "new_public_key has already been used by this agent".to_string(),
+                    ));
+                }
+                let recovery_key = rotation.new_recovery_key.as_ref().or(agent.recovery_key.as_ref());
+                if recovery_key == Some(&rotation.new_public_key) {
+                    return Err(HubError::ValidationError(
+                        "the recovery key must differ from new_public_key".to_string(),
+                    ));
+                }
+                // Otherwise a stolen current key could lock the owner out of recovery
+                let replaces_recovery = agent.recovery_key.is_some()
+                    && rotation.new_recovery_key.is_some()
+                    && rotation.new_recovery_key != agent.recovery_key;
+                if replaces_recovery && rotation.signed_by != RotationSigner::Recovery {
+                    return Err(HubError::ValidationError(
+                        "replacing the recovery key requires a rotation signed by the recovery key".to_string(),
+                    ));
+                }
+                if rotation.version != agent.version + 1 {
+                    return Err(HubError::ValidationError(format!(
+                        "version must be {} (current version + 1)",
+                        agent.version + 1
+                    )));
+                }
+                if rotation.rotated_at <= agent.key_valid_from() {
+                    return Err(HubError::ValidationError(
+                        "rotated_at must be after the current key became valid".to_string(),
+                    ));
+                }
+                if rotation.signed_by == RotationSigner::Recovery && agent.recovery_key.is_none() {
+                    return Err(HubError::ValidationError("agent has no recovery key".to_string()));
+                }
+                if rotation.signed_by == RotationSigner::Previous && self.is_revoked(&agent.uuid, &agent.public_key)? {
+                    return Err(HubError::ValidationError(
+                        "the current key is revoked; the rotation must be signed by the recovery key".to_string(),
+                    ));
+                }
+
+                if self.verify_signatures
+                    && !verify_key_rotation(&agent.uuid, &agent.public_key, agent.recovery_key.as_deref(), &rotation)?
+                {
+                    return Err(HubError::InvalidSignature {
+                        entity_type: "key_rotation".to_string(),
+                    });
+                }
+
+                agent.apply_rotation(rotation);
+
+                // The new key proves possession by signing the rotated agent
+                if self.verify_signatures {
+                    let data = canonical_json(&agent.trust_state_payload());
+                    if !verify_with_key(&agent.public_key, data.as_bytes(), &signature)? {
+                        return Err(HubError::InvalidSignature {
+                            entity_type: "agent".to_string(),
+                        });
+                    }
+                }
+
+                agent.updated_at = now;
+                agent.trust_signature = Some(signature);
+                Ok(())
+            })?
+            .ok_or_else(|| HubError::not_found("agent", uuid))?;
+        self.agent_changed(uuid);
+        Ok(agent)
+    }
+
+    /// Declare one of an agent's keys compromised as of a point in time.
+    ///
+    /// The revocation is signed by the agent's current or recovery key.
+    /// Fragments, relations and tags signed with the key after that time are
+    /// quarantined until the agent confirms them.
+    pub fn revoke_key(&self, uuid: &str, req: RevokeKeyRequest) -> HubResult<(KeyRevocation, QuarantineChange)> {
+        let agent = self.get_agent(uuid)?;
+
+        if !agent.has_used_key(&req.public_key) {
+            return Err(HubError::ValidationError("public_key is not a key of this agent".to_string()));
+        }
+        let now = chrono::Utc::now();
+        if req.compromised_at > now + chrono::Duration::seconds(MAX_CLOCK_SKEW_SECS) {
+            return Err(HubError::ValidationError("compromised_at lies in the future".to_string()));
+        }
+
+        let revocation = KeyRevocation {
+            agent: agent.uuid.clone(),
+            public_key: req.public_key,
+            compromised_at: req.compromised_at,
+            reason: req.reason.unwrap_or_default(),
+            signed_by: req.signed_by,
+            signature: req.signature,
+            revoked_at: now,
+        };
+
+        let signer = match revocation.signed_by {
+            RevocationSigner::Current => agent.public_key.as_str(),
+            RevocationSigner::Recovery => agent.recovery_key.as_deref().ok_or_else(|| {
+                HubError::ValidationError("agent has no recovery key".to_string())
+            })?,
+        };
+        if self.verify_signatures {
+            let data = canonical_json(&revocation.signing_payload());
+            if !verify_with_key(signer, data.as_bytes(), &revocation.signature)? {
+                return Err(HubError::InvalidSignature {
+                    entity_type: "key_revocation".to_string(),
+                });
+            }
+        }
+
+        // A revocation can only be moved to an earlier point in time
+        let existing = self.store
+            .get_revocations_for_agent(uuid)?
+            .into_iter()
+            .find(|r| r.public_key == revocation.public_key);
+        if let Some(existing) = existing {
+            if existing.compromised_at <= revocation.compromised_at {
+                return Err(HubError::ValidationError(format!(
+                    "key is already revoked as of {}",
+                    existing.compromised_at.to_rfc3339()
+                )));
+            }
+        }
+
+        self.store.put_revocation(&revocation)?;
+        let quarantined = self.quarantine_content(&agent, &revocation)?;
+        if quarantined.is_empty() {
+            self.agent_changed(uuid);
+        } else {
+            self.content_changed();
+        }
+        Ok((revocation, quarantined))
+    }
+
+    /// Revocations recorded after a point in time, oldest first
+    pub fn list_revocations(&self, since: Option<chrono::DateTime<chrono::Utc>>) -> HubResult<Vec<KeyRevocation>> {
+        let mut revocations: Vec<KeyRevocation> = self.store
+            .list_revocations()?
+            .into_iter()
+            .filter(|r| since.is_none_or(|since| r.revoked_at > since))
+            .collect();
+        revocations.sort_by_key(|r| r.revoked_at);
+        Ok(revocations)
+    }
+
+    /// Check if a key of an agent has been revoked
+    fn is_revoked(&self, agent_uuid: &str, public_key: &str) -> HubResult<bool> {
+        Ok(self.store
+            .get_revocations_for_agent(agent_uuid)?
+            .iter()
+            .any(|r| r.public_key == public_key))
+    }
+
+    /// Quarantine marker for content signed with a key, if a revocation covers it
+    fn quarantine_for(
+        &self,
+        agent: &Agent,
+        key: &str,
+        signed_at: chrono::DateTime<chrono::Utc>,
+        received_at: chrono::DateTime<chrono::Utc>,
+    ) -> HubResult<Option<Quarantine>> {
+        let covered = self.store
+            .get_revocations_for_agent(&agent.uuid)?
+            .iter()
+            .any(|r| r.covers(key, signed_at, received_at));
+        Ok(covered.then(|| Quarantine {
+            key: key.to_string(),
+            since: chrono::Utc::now(),
+        }))
+    }
+
+    /// Quarantine an agent's stored content covered by a revocation
+    fn quarantine_content(&self, agent: &Agent, revocation: &KeyRevocation) -> HubResult<QuarantineChange> {
+        let quarantine = Quarantine {
+            key: revocation.public_key.clone(),
+            since: revocation.revoked_at,
+        };
+        let mut change = QuarantineChange::default();
+
+        for fragment in self.store.get_fragments_by_creator(&agent.uuid)? {
+            if !fragment.is_quarantined()
+                && revocation.covers(agent.key_at(fragment.when), fragment.when, fragment.created_at)
+            {
+                self.store.update_fragment(&fragment.uuid, |f| f.quarantine = Some(quarantine.clone()))?;
+                change.fragments.push(fragment.uuid);
+            }
+        }
+        for relation in self.store.get_relations_by_creator(&agent.uuid)? {
+            if !relation.is_quarantined()
+                && revocation.covers(agent.key_at(relation.when), relation.when, relation.created_at)
+            {
+                self.store.update_relation(&relation.uuid, |r| r.quarantine = Some(quarantine.clone()))?;
+                self.contradiction_changed(&relation)?;
+                change.relations.push(relation.uuid);
+            }
+        }
+        for tag in self.store.get_tags_by_creator(&agent.uuid)? {
+            if !tag.is_quarantined()
+                && revocation.covers(agent.key_at(tag.created_at), tag.created_at, tag.created_at)
+            {
+                self.store.update_tag(&tag.uuid, |t| t.quarantine = Some(quarantine.clone()))?;
+                change.tags.push(tag.uuid);
+            }
+        }
+
+        Ok(change)
+    }
+
+    /// Release quarantined content the agent confirms with its current key.
+    ///
+    /// Only content quarantined before the confirmation was signed is released.
+    pub fn confirm_content(&self, uuid: &str, req: ConfirmContentRequest) -> HubResult<QuarantineChange> {
+        let agent = self.get_agent(uuid)?;
+
+        if req.entities.is_empty() {
+            return Err(HubError::ValidationError("entities must not be empty".to_string()));
+        }
+        if let Some(addr) = req.entities.iter().find(|a| !matches!(a.domain, Domain::Fragment | Domain::Relation | Domain::Tag)) {
+            return Err(HubError::ValidationError(format!(
+                "{} is not a fragment, relation or tag", addr
+            )));
+        }
+        if req.confirmed_at > chrono::Utc::now() + chrono::Duration::seconds(MAX_CLOCK_SKEW_SECS) {
+            return Err(HubError::ValidationError("confirmed_at lies in the future".to_string()));
+        }
+        if self.is_revoked(uuid, &agent.public_key)? {
+            return Err(HubError::ValidationError(
+                "the current key is revoked; rotate the key before confirming content".to_string(),
+            ));
+        }
+        if self.verify_signatures {
+            let data = canonical_json(&req.signing_payload(uuid));
+            if !verify_with_key(&agent.public_key, data.as_bytes(), &req.signature)? {
+                return Err(HubError::InvalidSignature {
+                    entity_type: "content_confirmation".to_string(),
+                });
+            }
+        }
+
+        let releasable = |creator: &Address, quarantine: &Option<Quarantine>| {
+            creator.entity == uuid && quarantine.as_ref().is_some_and(|q| q.since <= req.confirmed_at)
+        };
+        let mut change = QuarantineChange::default();
+        for addr in &req.entities {
+            match addr.domain {
+                Domain::Fragment => {
+                    if let Some(fragment) = self.store.get_fragment(&addr.entity)? {
+                        if releasable(&fragment.creator, &fragment.quarantine) {
+                            self.store.update_fragment(&addr.entity, |f| f.quarantine = None)?;
+                            change.fragments.push(addr.entity.clone());
+                        }
+                    }
+                }
+                Domain::Relation => {
+                    if let Some(relation) = self.store.get_relation(&addr.entity)? {
+                        if releasable(&relation.creator, &relation.quarantine) {
+                            self.store.update_relation(&addr.entity, |r| r.quarantine = None)?;
+                            self.contradiction_changed(&relation)?;
+                            change.relations.push(addr.entity.clone());
+                        }
+                    }
+                }
+                Domain::Tag => {
+                    if let Some(tag) = self.store.get_tag(&addr.entity)? {
+                        if releasable(&tag.creator, &tag.quarantine) {
+                            self.store.update_tag(&addr.entity, |t| t.quarantine = None)?;
+                            change.tags.push(addr.entity.clone());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if !change.is_empty() {
+            self.content_changed();
+        }
+        Ok(change)
+    }
+
     /// Delete an agent
     pub fn delete_agent(&self, uuid: &str) -> HubResult<()> {
         // Check if agent exists
         self.get_agent(uuid)?;
-        self.store.delete_agent(uuid)
+        self.store.delete_agent(uuid)?;
+        self.agent_changed(uuid);
+        Ok(())
+    }
+
+    /// Drop the cached trust store of an agent that changed or was deleted
+    fn agent_changed(&self, uuid: &str) {
+        if let Some(ref trust_service) = self.trust_service {
+            trust_service.invalidate_agent(uuid);
+        }
+    }
+
+    /// Drop all cached trust stores after content was quarantined or released
+    fn content_changed(&self) {
+        if let Some(ref trust_service) = self.trust_service {
+            trust_service.clear_cache();
+        }
     }
 
     // ========================================================================
@@ -124,16 +543,40 @@ impl EntityService {
         // Verify the creating agent exists
         let agent = self.get_agent(&req.creator.entity)?;
 
-        // Verify signature if enabled
+        // New submissions are signed with the current key; older keys only
+        // verify content stored before a rotation
+        let key = agent.public_key.clone();
         if self.verify_signatures {
-            self.verify_fragment_signature(&req, &agent.public_key)?;
+            self.verify_fragment_signature(&req, &key)?;
         }
 
-        let fragment = Fragment::from(req);
+        let mut fragment = Fragment::from(req);
+        fragment.quarantine = self.quarantine_for(&agent, &key, fragment.when, fragment.created_at)?;
+        fragment.counted_evidence = Some(CountedEvidence::default());
         self.store.put_fragment(&fragment)?;
+
+        let tags = self.tag_keys(&fragment)?;
+        self.store.update_agent(&fragment.creator.entity, |creator| {
+            creator.profile.record_fragment(fragment.confidence, &tags);
+        })?;
         Ok(fragment)
     }
 
+    /// Keys under which a fragment's tags count as specializations: the tag
+    /// name for tags known here, the address otherwise
+    fn tag_keys(&self, fragment: &Fragment) -> HubResult<Vec<String>> {
+        fragment
+            .tags
+            .iter()
+            .map(|addr| {
+                Ok(match self.store.get_tag(&addr.entity)? {
+                    Some(tag) => tag.name,
+                    None => addr.to_string(),
+                })
+            })
+            .collect()
+    }
+
     /// Verify fragment signature using canonical JSON over all fields
     fn verify_fragment_signature(&self, req: &CreateFragmentRequest, public_key: &str) -> HubResult<()> {
         let uuid = req.uuid.clone().unwrap_or_default();
@@ -195,8 +638,17 @@ impl EntityService {
 
     /// Delete a fragment
     pub fn delete_fragment(&self, uuid: &str) -> HubResult<()> {
-        self.get_fragment(uuid)?;
-        self.store.delete_fragment(uuid)
+        let fragment = self.get_fragment(uuid)?;
+        self.store.delete_fragment(uuid)?;
+
+        // Retract the fragment's statistics from its creator's profile
+        let tags = self.tag_keys(&fragment)?;
+        let counted = self.counted_evidence(&fragment)?;
+        self.store.update_agent(&fragment.creator.entity, |creator| {
+            creator.profile.forget_fragment(fragment.confidence, &tags);
+            creator.profile.record_evidence(-counted.verified, -counted.contested);
+        })?;
+        Ok(())
     }
 
     // ========================================================================
@@ -214,16 +666,29 @@ impl EntityService {
             self.verify_entity_exists(&req.to)?;
         }
 
-        // Verify signature if enabled
+        // Verify signature against the current key (see `create_fragment`)
+        let key = agent.public_key.clone();
         if self.verify_signatures {
-            self.verify_relation_signature(&req, &agent.public_key)?;
+            self.verify_relation_signature(&req, &key)?;
         }
 
-        let relation = Relation::from(req);
+        let mut relation = Relation::from(req);
+        relation.quarantine = self.quarantine_for(&agent, &key, relation.when, relation.created_at)?;
         self.store.put_relation(&relation)?;
+
+        // Contradictions count against the contradicted fragment's creator
+        self.contradiction_changed(&relation)?;
         Ok(relation)
     }
 
+    /// Recount the evidence of the fragment a CONTRADICTS relation targets
+    fn contradiction_changed(&self, relation: &Relation) -> HubResult<()> {
+        if relation.relation_type == RelationType::Contradicts && relation.to.domain == Domain::Fragment {
+            self.refresh_evidence(&relation.to.entity)?;
+        }
+        Ok(())
+    }
+
     /// Verify relation signature using canonical JSON over all fields
     fn verify_relation_signature(&self, req: &CreateRelationRequest, public_key: &str) -> HubResult<()> {
         let uuid = req.uuid.clone().unwrap_or_default();
@@ -281,6 +746,11 @@ impl EntityService {
                     return Ok(());
                 }
             }
+            Domain::Vote => {
+                if self.store.get_vote(&addr.entity)?.is_some() {
+                    return Ok(());
+                }
+            }
             Domain::Hub => {
                 // Hub addresses don't need entity validation
                 return Ok(());
@@ -344,7 +814,8 @@ impl EntityService {
             self.verify_tag_signature(&req, &agent.public_key)?;
         }
 
-        let tag = Tag::from(req);
+        let mut tag = Tag::from(req);
+        tag.quarantine = self.quarantine_for(&agent, &agent.public_key, tag.created_at, tag.created_at)?;
         self.store.put_tag(&tag)?;
         Ok(tag)
     }
@@ -463,6 +934,193 @@ impl EntityService {
         self.store.list_transforms(&cursor, limit.min(100))
     }
 
+    // ========================================================================
+    // Vote operations
+    // ========================================================================
+
+    /// Cast or update a vote with signature verification.
+    ///
+    /// An agent has one vote per fragment; a new vote replaces the previous
+    /// one (keeping its UUID) if it was cast later. The fragment's vote
+    /// counters are recomputed afterwards.
+    pub fn cast_vote(&self, req: CreateVoteRequest) -> HubResult<Vote> {
+        // Verify the voting agent and the fragment exist
+        let agent = self.get_local_agent(&req.voter)?;
+        if req.fragment.domain != Domain::Fragment {
+            return Err(HubError::ValidationError("votes can only be cast on fragments".to_string()));
+        }
+        let fragment = self.get_fragment(&req.fragment.entity)?;
+        if fragment.creator.entity == req.voter.entity {
+            return Err(HubError::ValidationError("agents cannot vote on their own fragments".to_string()));
+        }
+        if let Some(weight) = req.weight {
+            if !(0.0..=1.0).contains(&weight) {
+                return Err(HubError::ValidationError("weight must be between 0.0 and 1.0".to_string()));
+            }
+        }
+        // Unsigned times would let a captured vote be replayed as the newest
+        match req.when {
+            Some(when) if when > chrono::Utc::now() + chrono::Duration::seconds(MAX_CLOCK_SKEW_SECS) => {
+                return Err(HubError::ValidationError("when lies in the future".to_string()));
+            }
+            None => return Err(HubError::ValidationError("when is required".to_string())),
+            _ => {}
+        }
+
+        // Verify signature against the current key (see `create_fragment`)
+        if self.verify_signatures {
+            self.verify_vote_signature(&req, &agent.public_key)?;
+        }
+
+        let mut vote = Vote::from(req);
+        self.store.replace_vote(&mut vote, |previous, vote| {
+            if let Some(previous) = previous {
+                // Replaying an older signed vote must not undo a newer one
+                if vote.when <= previous.when {
+                    return Err(HubError::ValidationError(
+                        "vote is not newer than the agent's current vote".to_string(),
+                    ));
+                }
+                vote.uuid = previous.uuid.clone();
+                vote.version = previous.version + 1;
+                vote.created_at = previous.created_at;
+            }
+            Ok(())
+        })?;
+        self.refresh_evidence(&vote.fragment.entity)?;
+        Ok(vote)
+    }
+
+    /// Verify vote signature using canonical JSON over all fields
+    fn verify_vote_signature(&self, req: &CreateVoteRequest, public_key: &str) -> HubResult<()> {
+        let uuid = req.uuid.clone().unwrap_or_default();
+        let when_str = req.when.as_ref()
+            .map(|w| w.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+            .unwrap_or_default();
+
+        let payload = json!({
+            "comment": req.comment.as_deref().unwrap_or(""),
+            "fragment": serde_json::to_value(&req.fragment).unwrap(),
+            "kind": req.kind.to_string(),
+            "uuid": uuid,
+            "voter": serde_json::to_value(&req.voter).unwrap(),
+            "weight": req.weight.unwrap_or(1.0),
+            "when": when_str,
+        });
+        let data = canonical_json(&payload);
+        let is_valid = verify_with_key(public_key, data.as_bytes(), &req.signature)?;
+
+        if !is_valid {
+            return Err(HubError::InvalidSignature {
+                entity_type: "vote".to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Recount the votes and contradictions a fragment received and move the
+    /// change in counted evidence to its creator's profile.
+    ///
+    /// The fragment is recounted under the store's update lock, which vote
+    /// writes also take, so concurrent votes are neither lost nor counted twice.
+    fn refresh_evidence(&self, fragment_uuid: &str) -> HubResult<()> {
+        let mut change = None;
+        self.store.try_update_fragment(fragment_uuid, |fragment| {
+            let votes = self.store.get_votes_for_fragment(fragment_uuid)?;
+            let contradictions: Vec<Relation> = self.store
+                .get_relations_by_to(fragment_uuid)?
+                .into_iter()
+                .filter(|r| r.relation_type == RelationType::Contradicts && !r.is_quarantined())
+                .collect();
+            let previous = self.counted_evidence(fragment)?;
+            let counted = self.count_evidence(&fragment.creator, &votes, &contradictions);
+
+            fragment.votes = VoteSummary::from_votes(&votes);
+            fragment.counted_evidence = Some(counted);
+            change = Some((
+                fragment.creator.entity.clone(),
+                counted.verified - previous.verified,
+                counted.contested - previous.contested,
+            ));
+            Ok(())
+        })?;
+
+        if let Some((creator, verified, contested)) = change {
+            self.store.update_agent(&creator, |creator| {
+                creator.profile.record_evidence(verified, contested);
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Evidence counted towards a fragment's creator so far. Fragments stored
+    /// before it was recorded had every vote and contradiction counted in full.
+    fn counted_evidence(&self, fragment: &Fragment) -> HubResult<CountedEvidence> {
+        if let Some(counted) = fragment.counted_evidence {
+            return Ok(counted);
+        }
+        let contradicted: f32 = self.store
+            .get_relations_by_to(&fragment.uuid)?
+            .iter()
+            .filter(|r| r.relation_type == RelationType::Contradicts)
+            .map(|r| r.confidence)
+            .sum();
+        let (verified, contested) = fragment.votes.weights();
+        Ok(CountedEvidence {
+            verified,
+            contested: contested + contradicted,
+        })
+    }
+
+    /// Evidence against or for a fragment: each agent other than the creator
+    /// counts once, with their vote if they cast one and their strongest
+    /// contradiction otherwise, weighted by the hub's trust in them (see
+    /// `TrustService::evidence_weight`).
+    fn count_evidence(&self, creator: &Address, votes: &[Vote], contradictions: &[Relation]) -> CountedEvidence {
+        // Author's (verified, contested) weight, by agent
+        let mut stances: HashMap<&str, (&Address, f32, f32)> = HashMap::new();
+        for relation in contradictions {
+            let author = relation.asserter();
+            if author.entity == creator.entity {
+                continue;
+            }
+            let stance = stances.entry(&author.entity).or_insert((author, 0.0, 0.0));
+            stance.2 = stance.2.max(relation.confidence);
+        }
+        for vote in votes {
+            let stance = match vote.kind {
+                VoteKind::Verify => (&vote.voter, vote.weight, 0.0),
+                VoteKind::Contest => (&vote.voter, 0.0, vote.weight),
+            };
+            stances.insert(&vote.voter.entity, stance);
+        }
+
+        let mut counted = CountedEvidence::default();
+        for (author, verified, contested) in stances.into_values() {
+            let weight = self.trust_service.as_ref().map_or(1.0, |trust| trust.evidence_weight(author));
+            counted.verified += weight * verified;
+            counted.contested += weight * contested;
+        }
+        counted
+    }
+
+    /// Get a vote by UUID
+    pub fn get_vote(&self, uuid: &str) -> HubResult<Vote> {
+        self.store
+            .get_vote(uuid)?
+            .ok_or_else(|| HubError::NotFound {
+                entity_type: "vote".to_string(),
+                id: uuid.to_string(),
+            })
+    }
+
+    /// List the votes on a fragment
+    pub fn list_votes(&self, fragment_uuid: &str) -> HubResult<Vec<Vote>> {
+        self.get_fragment(fragment_uuid)?;
+        self.store.get_votes_for_fragment(fragment_uuid)
+    }
+
     // ========================================================================
     // Statistics
     // ========================================================================
@@ -494,7 +1152,9 @@ impl std::fmt::Debug for EntityService {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::Address;
+    use crate::crypto::{sign, KeyPair};
+    use crate::models::{Address, KeyRotation, Trust, TrustStore, TrustUpdate, VoteKind};
+    use crate::services::TrustConfig;
     use crate::store::RocksStore;
     use tempfile::TempDir;
 
@@ -514,7 +1174,9 @@ mod tests {
             uuid: None,
             public_key: "test-key".to_string(),
             description: Some("Test agent".to_string()),
+            trust: None,
             primary_hub: None,
+            recovery_key: None,
             signature: "sig".to_string(),
         };
 
@@ -526,6 +1188,34 @@ mod tests {
         assert_eq!(retrieved.public_key, agent.public_key);
     }
 
+    #[test]
+    fn test_create_agent_rejects_future_trust() {
+        let (service, _temp) = create_test_service();
+        let trust_at = |asserted_at| CreateAgentRequest {
+            uuid: None,
+            public_key: "test-key".to_string(),
+            description: None,
+            trust: Some(TrustStore {
+                num_trusts: 1,
+                trusts: vec![Trust {
+                    agent: Address::agent("hub:8080", "agent-2"),
+                    trust: 1.0,
+                    scope: None,
+                    asserted_at: Some(asserted_at),
+                }],
+            }),
+            primary_hub: None,
+            recovery_key: None,
+            signature: "sig".to_string(),
+        };
+
+        let err = service
+            .create_agent(trust_at(chrono::Utc::now() + chrono::Duration::days(365)))
+            .unwrap_err();
+        assert!(matches!(err, HubError::ValidationError(_)));
+        assert!(service.create_agent(trust_at(chrono::Utc::now())).is_ok());
+    }
+
     #[test]
     fn test_create_fragment() {
         let (service, _temp) = create_test_service();
@@ -535,7 +1225,9 @@ mod tests {
             uuid: Some("agent-1".to_string()),
             public_key: "test-key".to_string(),
             description: None,
+            trust: None,
             primary_hub: None,
+            recovery_key: None,
             signature: "sig".to_string(),
         }).unwrap();
 
@@ -550,6 +1242,8 @@ mod tests {
             creator: creator.clone(),
             when: None,
             signature: "sig".to_string(),
+            confidence: None,
+            evidence_type: None,
         };
 
         let fragment = service.create_fragment(req).unwrap();
@@ -567,7 +1261,9 @@ mod tests {
                 uuid: Some(format!("agent-{}", i)),
                 public_key: "key".to_string(),
                 description: None,
+                trust: None,
                 primary_hub: None,
+                recovery_key: None,
                 signature: "sig".to_string(),
             }).unwrap();
         }
@@ -596,7 +1292,9 @@ mod tests {
             uuid: Some("agent-1".to_string()),
             public_key: "key".to_string(),
             description: None,
+            trust: None,
             primary_hub: None,
+            recovery_key: None,
             signature: "sig".to_string(),
         }).unwrap();
 
@@ -609,10 +1307,465 @@ mod tests {
             creator,
             when: None,
             signature: "sig".to_string(),
+            confidence: None,
+            evidence_type: None,
         }).unwrap();
 
         let stats = service.get_stats().unwrap();
         assert_eq!(stats.agents_count, 1);
         assert_eq!(stats.fragments_count, 1);
     }
+
+    #[test]
+    fn test_update_agent_trust() {
+        let (service, _temp) = create_test_service();
+
+        service.create_agent(CreateAgentRequest {
+            uuid: Some("agent-1".to_string()),
+            public_key: "key".to_string(),
+            description: None,
+            trust: None,
+            primary_hub: None,
+            recovery_key: None,
+            signature: "sig".to_string(),
+        }).unwrap();
+
+        let bob = Address::agent("hub:8080", "bob");
+        let carol = Address::agent("hub:8080", "carol");
+        let now = Some(chrono::Utc::now());
+
+        // Setting trust without saying when it was asserted is rejected
+        let undated = service.update_agent_trust("agent-1", UpdateTrustRequest {
+            updates: vec![TrustUpdate { agent: bob.clone(), trust: Some(0.8), scope: None, asserted_at: None }],
+            version: 2,
+            signature: "sig".to_string(),
+        });
+        assert!(matches!(undated, Err(HubError::ValidationError(_))));
+
+        let agent = service.update_agent_trust("agent-1", UpdateTrustRequest {
+            updates: vec![
+                TrustUpdate { agent: bob.clone(), trust: Some(0.8), scope: None, asserted_at: now },
+                TrustUpdate { agent: carol.clone(), trust: Some(-0.3), scope: None, asserted_at: now },
+            ],
+            version: 2,
+            signature: "sig".to_string(),
+        }).unwrap();
+        assert_eq!(agent.version, 2);
+        assert_eq!(agent.trust.num_trusts, 2);
+
+        let agent = service.update_agent_trust("agent-1", UpdateTrustRequest {
+            updates: vec![
+                TrustUpdate { agent: bob.clone(), trust: Some(0.5), scope: None, asserted_at: now },
+                TrustUpdate { agent: carol.clone(), trust: None, scope: None, asserted_at: None },
+            ],
+            version: 3,
+            signature: "sig".to_string(),
+        }).unwrap();
+        assert_eq!(agent.trust.num_trusts, 1);
+        assert_eq!(agent.get_trust_for(&bob), 0.5);
+
+        // Replaying an old version is rejected
+        let stale = service.update_agent_trust("agent-1", UpdateTrustRequest {
+            updates: vec![TrustUpdate { agent: bob, trust: None, scope: None, asserted_at: None }],
+            version: 3,
+            signature: "sig".to_string(),
+        });
+        assert!(matches!(stale, Err(HubError::ValidationError(_))));
+    }
+
+    #[test]
+    fn test_update_agent_trust_signature() {
+        let temp_dir = TempDir::new().unwrap();
+        let rocks = RocksStore::open(temp_dir.path()).unwrap();
+        let store = Arc::new(EntityStore::new(rocks));
+        let trust_service = Arc::new(TrustService::new(Arc::clone(&store), TrustConfig::default()));
+        let service = EntityService::new(Arc::clone(&store)).with_trust_service(Arc::clone(&trust_service));
+
+        let keypair = KeyPair::generate();
+        store.put_agent(&Agent::new("agent-1", keypair.public_key_base64()).with_signature("sig")).unwrap();
+        let alice = Address::agent("hub:8080", "agent-1");
+        let bob = Address::agent("hub:8080", "bob");
+        assert_eq!(trust_service.get_direct_trust(&alice, &bob).unwrap(), None);
+
+        let updates = vec![TrustUpdate {
+            agent: Address::agent("hub:8080", "bob"),
+            trust: Some(0.7),
+            scope: None,
+            asserted_at: Some(chrono::Utc::now()),
+        }];
+        // Sign the trust store as it will look after the update
+        let mut expected = store.get_agent("agent-1").unwrap().unwrap();
+        for update in &updates {
+            expected.apply_trust_update(update);
+        }
+        expected.increment_version();
+        let payload = expected.trust_state_payload();
+        let signature = sign(&keypair, canonical_json(&payload).as_bytes());
+
+        let forged = service.update_agent_trust("agent-1", UpdateTrustRequest {
+            updates: updates.clone(),
+            version: 2,
+            signature: sign(&KeyPair::generate(), canonical_json(&payload).as_bytes()),
+        });
+        assert!(matches!(forged, Err(HubError::InvalidSignature { .. })));
+
+        let agent = service.update_agent_trust("agent-1", UpdateTrustRequest {
+            updates,
+            version: 2,
+            signature: signature.clone(),
+        }).unwrap();
+        assert_eq!(agent.trust.num_trusts, 1);
+        // The creation signature is kept next to the trust update's
+        assert_eq!(agent.signature, "sig");
+        assert_eq!(agent.trust_signature.as_deref(), Some(signature.as_str()));
+        assert_eq!(agent.state_signature(), signature);
+        // The trust service sees the update without being told by the caller
+        assert_eq!(trust_service.get_direct_trust(&alice, &bob).unwrap(), Some(0.7));
+    }
+
+    #[test]
+    fn test_rotate_agent_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let rocks = RocksStore::open(temp_dir.path()).unwrap();
+        let store = Arc::new(EntityStore::new(rocks));
+        let service = EntityService::new(Arc::clone(&store));
+
+        let old_key = KeyPair::generate();
+        let recovery_key = KeyPair::generate();
+        let new_key = KeyPair::generate();
+        let now = chrono::Utc::now();
+        let mut agent = Agent::new("agent-1", old_key.public_key_base64())
+            .with_recovery_key(recovery_key.public_key_base64())
+            .with_signature("sig");
+        agent.created_at = now - chrono::Duration::days(1);
+        store.put_agent(&agent).unwrap();
+
+        // The old key is lost; the recovery key delegates to the new one
+        let mut rotation = KeyRotation {
+            new_public_key: new_key.public_key_base64(),
+            new_recovery_key: None,
+            rotated_at: now - chrono::Duration::hours(1),
+            version: 2,
+            signed_by: RotationSigner::Recovery,
+            signature: String::new(),
+        };
+        let statement = canonical_json(&rotation.statement_payload("agent-1", &agent.public_key));
+        let mut expected = agent.clone();
+        expected.apply_rotation(rotation.clone());
+        let signature = sign(&new_key, canonical_json(&expected.trust_state_payload()).as_bytes());
+
+        rotation.signature = sign(&KeyPair::generate(), statement.as_bytes());
+        let forged = service.rotate_agent_key("agent-1", RotateKeyRequest {
+            rotation: rotation.clone(),
+            signature: signature.clone(),
+        });
+        assert!(matches!(forged, Err(HubError::InvalidSignature { .. })));
+
+        rotation.signature = sign(&recovery_key, statement.as_bytes());
+        let rotated = service.rotate_agent_key("agent-1", RotateKeyRequest {
+            rotation: rotation.clone(),
+            signature,
+        }).unwrap();
+        assert_eq!(rotated.public_key, new_key.public_key_base64());
+        assert_eq!(rotated.version, 2);
+        assert_eq!(rotated.key_history.len(), 1);
+
+        // Replaying the rotation fails: the new key is now current
+        assert!(service.rotate_agent_key("agent-1", RotateKeyRequest {
+            rotation: rotation.clone(),
+            signature: String::new(),
+        }).is_err());
+
+        // The current key alone cannot replace the recovery key
+        let takeover = KeyRotation {
+            new_public_key: KeyPair::generate().public_key_base64(),
+            new_recovery_key: Some(KeyPair::generate().public_key_base64()),
+            rotated_at: now,
+            version: 3,
+            signed_by: RotationSigner::Previous,
+            signature: String::new(),
+        };
+        assert!(matches!(
+            service.rotate_agent_key("agent-1", RotateKeyRequest { rotation: takeover, signature: String::new() }),
+            Err(HubError::ValidationError(_))
+        ));
+
+        // New fragments must be signed with the current key, even if backdated
+        let fragment = |when: chrono::DateTime<chrono::Utc>, keypair: &KeyPair| {
+            let creator = Address::agent("hub:8080", "agent-1");
+            let payload = json!({
+                "confidence": 0.5,
+                "content": "signed before the rotation",
+                "creator": serde_json::to_value(&creator).unwrap(),
+                "evidence_type": "unknown",
+                "tags": [],
+                "transform": serde_json::Value::Null,
+                "uuid": "",
+                "when": when.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            });
+            CreateFragmentRequest {
+                uuid: None,
+                tags: None,
+                transform: None,
+                content: "signed before the rotation".to_string(),
+                creator,
+                when: Some(when),
+                signature: sign(keypair, canonical_json(&payload).as_bytes()),
+                confidence: None,
+                evidence_type: None,
+            }
+        };
+        let before = now - chrono::Duration::hours(2);
+        assert!(matches!(
+            service.create_fragment(fragment(before, &old_key)),
+            Err(HubError::InvalidSignature { .. })
+        ));
+        assert!(matches!(
+            service.create_fragment(fragment(now, &old_key)),
+            Err(HubError::InvalidSignature { .. })
+        ));
+        assert!(service.create_fragment(fragment(before, &new_key)).is_ok());
+        assert!(service.create_fragment(fragment(now, &new_key)).is_ok());
+    }
+
+    #[test]
+    fn test_revoke_key_quarantines_content() {
+        let (service, _temp) = create_test_service();
+        let store = service.store();
+        let now = chrono::Utc::now();
+        let creator = Address::agent("hub:8080", "agent-1");
+
+        let mut agent = Agent::new("agent-1", "key-1").with_recovery_key("recovery").with_signature("sig");
+        agent.created_at = now - chrono::Duration::days(1);
+        store.put_agent(&agent).unwrap();
+
+        let stored_fragment = |content: &str, age: chrono::Duration| {
+            let mut fragment = Fragment::new(content, creator.clone());
+            fragment.when = now - age;
+            fragment.created_at = now - age;
+            store.put_fragment(&fragment).unwrap();
+            fragment
+        };
+        let before = stored_fragment("rust before the leak", chrono::Duration::hours(2));
+        let after = stored_fragment("rust after the leak", chrono::Duration::minutes(30));
+
+        let revoke = |compromised_at| RevokeKeyRequest {
+            public_key: "key-1".to_string(),
+            compromised_at,
+            reason: Some("laptop stolen".to_string()),
+            signed_by: RevocationSigner::Recovery,
+            signature: "sig".to_string(),
+        };
+        let (revocation, quarantined) = service
+            .revoke_key("agent-1", revoke(now - chrono::Duration::hours(1)))
+            .unwrap();
+        assert_eq!(revocation.reason, "laptop stolen");
+        assert_eq!(quarantined.fragments, vec![after.uuid.clone()]);
+        assert!(service.revoke_key("agent-1", revoke(now)).is_err());
+        assert_eq!(service.list_revocations(None).unwrap().len(), 1);
+        assert!(service.list_revocations(Some(now + chrono::Duration::hours(1))).unwrap().is_empty());
+
+        let results = service.search_fragments("rust", 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].uuid, before.uuid);
+
+        // New content signed with the revoked key is quarantined on arrival
+        let fresh = service.create_fragment(CreateFragmentRequest {
+            uuid: None,
+            tags: None,
+            transform: None,
+            content: "rust from the thief".to_string(),
+            creator: creator.clone(),
+            when: Some(now - chrono::Duration::hours(3)),
+            signature: "sig".to_string(),
+            confidence: None,
+            evidence_type: None,
+        }).unwrap();
+        assert!(fresh.is_quarantined());
+
+        // Confirming requires a key that is not revoked
+        let confirm = ConfirmContentRequest {
+            entities: vec![Address::fragment("hub:8080", &after.uuid)],
+            confirmed_at: chrono::Utc::now(),
+            signature: "sig".to_string(),
+        };
+        assert!(service.confirm_content("agent-1", confirm.clone()).is_err());
+
+        service.rotate_agent_key("agent-1", RotateKeyRequest {
+            rotation: KeyRotation {
+                new_public_key: "key-2".to_string(),
+                new_recovery_key: None,
+                rotated_at: chrono::Utc::now(),
+                version: 2,
+                signed_by: RotationSigner::Recovery,
+                signature: "sig".to_string(),
+            },
+            signature: "sig".to_string(),
+        }).unwrap();
+        let released = service.confirm_content("agent-1", confirm).unwrap();
+        assert_eq!(released.fragments, vec![after.uuid.clone()]);
+        assert!(!service.get_fragment(&after.uuid).unwrap().is_quarantined());
+        assert!(service.get_fragment(&fresh.uuid).unwrap().is_quarantined());
+    }
+
+    #[test]
+    fn test_cast_vote() {
+        let (service, _temp) = create_test_service();
+        for uuid in ["author", "reviewer"] {
+            service.create_agent(CreateAgentRequest {
+                uuid: Some(uuid.to_string()),
+                public_key: "key".to_string(),
+                description: None,
+                trust: None,
+                primary_hub: None,
+                recovery_key: None,
+                signature: "sig".to_string(),
+            }).unwrap();
+        }
+        let fragment = service.create_fragment(CreateFragmentRequest {
+            uuid: Some("frag-1".to_string()),
+            tags: None,
+            transform: None,
+            content: "Water boils at 100C at sea level".to_string(),
+            creator: Address::agent("hub:8080", "author"),
+            when: None,
+            signature: "sig".to_string(),
+            confidence: None,
+            evidence_type: None,
+        }).unwrap();
+
+        let vote_request = |voter: &str, kind: VoteKind, when: chrono::DateTime<chrono::Utc>| CreateVoteRequest {
+            uuid: None,
+            fragment: Address::fragment("hub:8080", &fragment.uuid),
+            voter: Address::agent("hub:8080", voter),
+            kind,
+            weight: Some(0.8),
+            comment: None,
+            when: Some(when),
+            signature: "sig".to_string(),
+        };
+        let earlier = chrono::Utc::now() - chrono::Duration::minutes(5);
+
+        let first = service.cast_vote(vote_request("reviewer", VoteKind::Verify, earlier)).unwrap();
+        let votes = service.get_fragment("frag-1").unwrap().votes;
+        assert_eq!((votes.votes_count, votes.verifications), (1, 1));
+
+        // Changing the vote replaces it instead of adding a second one
+        let updated = service.cast_vote(vote_request("reviewer", VoteKind::Contest, chrono::Utc::now())).unwrap();
+        assert_eq!(updated.uuid, first.uuid);
+        assert_eq!(updated.version, 2);
+        let votes = service.get_fragment("frag-1").unwrap().votes;
+        assert_eq!((votes.votes_count, votes.verifications, votes.contestations), (1, 0, 1));
+        assert!((votes.contestation_weight - 0.8).abs() < 0.001);
+        assert_eq!(service.list_votes("frag-1").unwrap().len(), 1);
+        assert_eq!(service.get_vote(&first.uuid).unwrap().kind, VoteKind::Contest);
+
+        // The author's profile follows the replaced vote
+        let profile = service.get_agent("author").unwrap().profile;
+        assert_eq!(profile.verified_weight, 0.0);
+        assert!((profile.contested_weight - 0.8).abs() < 0.001);
+        assert!((profile.historical_accuracy - 1.0 / 2.8).abs() < 0.001);
+
+        // Replaying the older vote is rejected
+        assert!(matches!(
+            service.cast_vote(vote_request("reviewer", VoteKind::Verify, earlier)),
+            Err(HubError::ValidationError(_))
+        ));
+        // Votes without a signed time could be replayed at any later time
+        let mut undated = vote_request("reviewer", VoteKind::Verify, chrono::Utc::now());
+        undated.when = None;
+        assert!(matches!(service.cast_vote(undated), Err(HubError::ValidationError(_))));
+        // Authors cannot vote on their own fragments
+        assert!(matches!(
+            service.cast_vote(vote_request("author", VoteKind::Verify, chrono::Utc::now())),
+            Err(HubError::ValidationError(_))
+        ));
+
+        // Voters must be agents of this hub, not namesakes elsewhere
+        let trust = TrustService::new(Arc::clone(&service.store), TrustConfig::default())
+            .with_local_server("hub:8080");
+        let service = service.with_trust_service(Arc::new(trust));
+        let mut remote = vote_request("reviewer", VoteKind::Verify, chrono::Utc::now());
+        remote.voter = Address::agent("elsewhere:8080", "reviewer");
+        assert!(matches!(service.cast_vote(remote), Err(HubError::ValidationError(_))));
+    }
+
+    #[test]
+    fn test_profile_statistics() {
+        let (service, _temp) = create_test_service();
+        for uuid in ["author", "critic"] {
+            service.create_agent(CreateAgentRequest {
+                uuid: Some(uuid.to_string()),
+                public_key: "key".to_string(),
+                description: None,
+                trust: None,
+                primary_hub: None,
+                recovery_key: None,
+                signature: "sig".to_string(),
+            }).unwrap();
+        }
+        let creator = Address::agent("hub:8080", "author");
+        let critic = Address::agent("hub:8080", "critic");
+        let tag = service.create_tag(CreateTagRequest {
+            uuid: None,
+            name: "rust".to_string(),
+            content: String::new(),
+            category: crate::models::TagCategory::Language,
+            creator: creator.clone(),
+            signature: "sig".to_string(),
+        }).unwrap();
+
+        let fragment = |uuid: &str, creator: &Address, tags: Option<Vec<Address>>, confidence: f32| CreateFragmentRequest {
+            uuid: Some(uuid.to_string()),
+            tags,
+            transform: None,
+            content: format!("claim {}", uuid),
+            creator: creator.clone(),
+            when: None,
+            signature: "sig".to_string(),
+            confidence: Some(confidence),
+            evidence_type: None,
+        };
+        let tagged = Some(vec![Address::tag("hub:8080", &tag.uuid)]);
+        service.create_fragment(fragment("tagged", &creator, tagged, 0.9)).unwrap();
+        service.create_fragment(fragment("plain", &creator, None, 0.5)).unwrap();
+        service.create_fragment(fragment("rebuttal", &critic, None, 0.5)).unwrap();
+
+        let profile = service.get_agent("author").unwrap().profile;
+        assert_eq!(profile.fragment_count, 2);
+        assert!((profile.get_specialization("rust") - 0.5).abs() < 0.001);
+
+        let contradict = |by: &Address, confidence: f32| CreateRelationRequest {
+            uuid: None,
+            from: Address::fragment("hub:8080", "rebuttal"),
+            to: Address::fragment("hub:8080", "tagged"),
+            by: by.clone(),
+            r#type: "CONTRADICTS".to_string(),
+            content: None,
+            creator: by.clone(),
+            when: None,
+            signature: "sig".to_string(),
+            confidence: Some(confidence),
+        };
+        service.create_relation(contradict(&critic, 0.5)).unwrap();
+        let profile = service.get_agent("author").unwrap().profile;
+        assert!((profile.contested_weight - 0.5).abs() < 0.001);
+        assert!(profile.historical_accuracy < 0.5);
+
+        // Each agent counts once per fragment, and authors not at all
+        service.create_relation(contradict(&critic, 0.8)).unwrap();
+        service.create_relation(contradict(&creator, 1.0)).unwrap();
+        let profile = service.get_agent("author").unwrap().profile;
+        assert!((profile.contested_weight - 0.8).abs() < 0.001);
+        let counted = service.get_fragment("tagged").unwrap().counted_evidence.unwrap();
+        assert!((counted.contested - 0.8).abs() < 0.001);
+
+        // Deleting the contradicted fragment retracts its tags and contradictions
+        service.delete_fragment("tagged").unwrap();
+        let profile = service.get_agent("author").unwrap().profile;
+        assert_eq!(profile.fragment_count, 1);
+        assert!((profile.avg_confidence - 0.5).abs() < 0.001);
+        assert!(profile.specializations.is_empty());
+        assert!((profile.historical_accuracy - 0.5).abs() < 0.001);
+    }
 }
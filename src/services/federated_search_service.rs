@@ -9,11 +9,11 @@ use serde::{Deserialize, Serialize};
 use tracing::{debug, warn};
 
 use crate::models::{Fragment, HubResult, HubError};
-use crate::discovery::HubInfo;
+use crate::discovery::{Capability, HubInfo, HubStatus, SignedResponse};
 use super::{EntityService, DiscoveryService};
 
 /// Search result with source information
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResultItem {
     pub fragment: Fragment,
     pub source_hub_id: String,
@@ -21,14 +21,14 @@ pub struct SearchResultItem {
 }
 
 /// Source hub contribution
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchSource {
     pub hub_id: String,
     pub count: usize,
 }
 
 /// Federated search response
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FederatedSearchResponse {
     pub results: Vec<SearchResultItem>,
     pub sources: Vec<SearchSource>,
@@ -42,6 +42,10 @@ pub struct FederatedSearchService {
     discovery_service: Arc<DiscoveryService>,
     http_client: reqwest::Client,
     timeout: Duration,
+    /// Most hubs queried per search
+    max_hubs: usize,
+    /// Relevance score of results from degraded hubs
+    degraded_relevance: f64,
 }
 
 impl FederatedSearchService {
@@ -54,12 +58,16 @@ impl FederatedSearchService {
             .timeout(Duration::from_secs(10))
             .build()
             .expect("Failed to create HTTP client");
+        let max_hubs = discovery_service.config().federation_max_hubs;
+        let degraded_relevance = discovery_service.config().degraded_relevance;
 
         Self {
             entity_service,
             discovery_service,
             http_client,
             timeout: Duration::from_secs(5),
+            max_hubs,
+            degraded_relevance,
         }
     }
 
@@ -108,8 +116,15 @@ impl FederatedSearchService {
             });
         }
 
-        // Get other hubs for federation
-        let other_hubs = self.discovery_service.get_federation_targets();
+        // Get other hubs for federation that serve signed search results,
+        // fastest healthy hubs first
+        let mut compatible = self.discovery_service
+            .compatible_targets(&[Capability::Search, Capability::SearchSigned]);
+        compatible.truncate(self.max_hubs);
+        let (other_hubs, limit_supported): (Vec<HubInfo>, Vec<bool>) = compatible
+            .into_iter()
+            .map(|(hub, common)| (hub, common.contains(&Capability::SearchLimit)))
+            .unzip();
 
         if other_hubs.is_empty() {
             debug!("No other hubs available for federation");
@@ -130,7 +145,8 @@ impl FederatedSearchService {
         let remaining_needed = min_results.saturating_sub(all_results.len());
         let futures: Vec<_> = other_hubs
             .iter()
-            .map(|hub| self.query_remote_hub(hub, query, remaining_needed))
+            .zip(&limit_supported)
+            .map(|(hub, &limit_supported)| self.query_remote_hub(hub, query, remaining_needed, limit_supported))
             .collect();
 
         let remote_results = join_all(futures).await;
@@ -153,7 +169,8 @@ impl FederatedSearchService {
                             all_results.push(SearchResultItem {
                                 fragment,
                                 source_hub_id: hub.hub_id.clone(),
-                                relevance_score: 0.9, // Remote results slightly lower
+                                // Remote results slightly lower, more so from degraded hubs
+                                relevance_score: if hub.status == HubStatus::Degraded { self.degraded_relevance } else { 0.9 },
                             });
                         }
                     }
@@ -189,19 +206,22 @@ impl FederatedSearchService {
         })
     }
 
-    /// Query a remote hub for search results
+    /// Query a remote hub for search results.
+    ///
+    /// Uses the hub's signed, non-federating `/search` endpoint; results are
+    /// dropped unless signed by the key the hub registered with. Hubs without
+    /// `search.limit` get the query alone and their results are truncated here.
     async fn query_remote_hub(
         &self,
         hub: &HubInfo,
         query: &str,
         limit: usize,
+        limit_supported: bool,
     ) -> HubResult<Vec<Fragment>> {
-        let url = format!(
-            "{}/api/v1/fragments/search?q={}&limit={}",
-            hub.public_url,
-            urlencoding::encode(query),
-            limit
-        );
+        let mut url = format!("{}/api/v1/search?q={}", hub.public_url, urlencoding::encode(query));
+        if limit_supported {
+            url.push_str(&format!("&limit={}", limit));
+        }
 
         let response = tokio::time::timeout(self.timeout, self.http_client.get(&url).send())
             .await
@@ -216,33 +236,15 @@ impl FederatedSearchService {
             )));
         }
 
-        // Parse the API response
-        #[derive(Deserialize)]
-        struct ApiResponse<T> {
-            success: bool,
-            data: Option<T>,
-            #[allow(dead_code)]
-            error: Option<String>,
-        }
-
-        #[derive(Deserialize)]
-        struct SearchData {
-            items: Vec<Fragment>,
-        }
-
-        let api_response: ApiResponse<SearchData> = response
-            .json()
+        let body = response
+            .bytes()
             .await
-            .map_err(|e| HubError::NetworkError(format!("Failed to parse response: {}", e)))?;
+            .map_err(|e| HubError::NetworkError(format!("Failed to read response: {}", e)))?;
 
-        if !api_response.success {
-            return Err(HubError::FederationError(format!(
-                "Hub {} returned error in response",
-                hub.hub_id
-            )));
-        }
+        let (data, _): (FederatedSearchResponse, _) = SignedResponse::from_slice(&body)?
+            .verified_data(Some(&hub.hub_id), hub.public_key.as_deref())?;
 
-        Ok(api_response.data.map(|d| d.items).unwrap_or_default())
+        Ok(data.results.into_iter().take(limit).map(|item| item.fragment).collect())
     }
 
     /// Set query timeout
@@ -250,6 +252,18 @@ impl FederatedSearchService {
         self.timeout = timeout;
         self
     }
+
+    /// Set the most hubs queried per search
+    pub fn with_max_hubs(mut self, max_hubs: usize) -> Self {
+        self.max_hubs = max_hubs;
+        self
+    }
+
+    /// Set the relevance score of results from degraded hubs
+    pub fn with_degraded_relevance(mut self, relevance: f64) -> Self {
+        self.degraded_relevance = relevance;
+        self
+    }
 }
 
 #[cfg(test)]
@@ -273,7 +287,7 @@ mod tests {
             public_url: "http://localhost:8080".to_string(),
             ..Default::default()
         };
-        let discovery_service = Arc::new(DiscoveryService::new(discovery_config, store));
+        let discovery_service = Arc::new(DiscoveryService::new(discovery_config, store).unwrap());
 
         FederatedSearchService::new(entity_service, discovery_service)
     }
@@ -299,4 +313,23 @@ mod tests {
         assert!(!response.federated);
         assert_eq!(response.sources.len(), 1);
     }
+
+    #[test]
+    fn test_federation_limits_from_config() {
+        let dir = tempdir().unwrap();
+        let rocks = RocksStore::open(dir.path().to_str().unwrap()).unwrap();
+        let store = Arc::new(EntityStore::new(rocks));
+        let entity_service = Arc::new(EntityService::new(Arc::clone(&store)));
+        let discovery_config = DiscoveryConfig {
+            hub_id: "test-hub".to_string(),
+            federation_max_hubs: 3,
+            degraded_relevance: 0.5,
+            ..Default::default()
+        };
+        let discovery_service = Arc::new(DiscoveryService::new(discovery_config, store).unwrap());
+
+        let service = FederatedSearchService::new(entity_service, discovery_service);
+        assert_eq!(service.max_hubs, 3);
+        assert_eq!(service.degraded_relevance, 0.5);
+    }
 }
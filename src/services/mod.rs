@@ -3,11 +3,13 @@
 mod entity_service;
 mod trust_service;
 mod discovery_service;
+mod discovery_tasks;
 mod federated_search_service;
 mod validity_service;
 
 pub use entity_service::*;
 pub use trust_service::*;
 pub use discovery_service::*;
+pub use discovery_tasks::*;
 pub use federated_search_service::*;
 pub use validity_service::*;
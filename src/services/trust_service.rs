@@ -6,11 +6,20 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 
+use chrono::{DateTime, Duration, Utc};
+use futures::future::join_all;
+use parking_lot::RwLock;
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use crate::config::{TrustDecayMode, TrustSettings};
 use crate::models::{
-    Address, TrustPath, TrustPathHop, TrustScore,
+    Agent, Address, ExpertiseDomain, TrustScope, TrustPath, StaleTrustEdge, StaleTrustReport, TrustPathHop, TrustScore,
+    TrustExplanation, ExplainedPath, ExplainedHop, ExplainedEndorsement, CutTrustEdge, Relation, RelationType,
     HubResult, HubError, Domain,
 };
-use crate::store::EntityStore;
+use crate::store::{Cursor, EntityStore};
+use crate::trust::{analyze_sybils, HubDirectory, RemoteTrustConfig, RemoteTrustFetcher, SybilConfig, SybilReport};
 
 /// Configuration for trust calculations
 #[derive(Debug, Clone)]
@@ -21,6 +30,47 @@ pub struct TrustConfig {
     pub damping_factor: f32,
     /// Minimum trust threshold to consider a path valid
     pub min_trust_threshold: f32,
+    /// Lowest damping factor a request may override with
+    pub min_damping_factor: f32,
+    /// Highest damping factor a request may override with
+    pub max_damping_factor: f32,
+    /// Decay applied to trust edges as they age
+    pub decay: TrustDecay,
+    /// Age after which trust edges are ignored (None = never expire)
+    pub max_age: Option<Duration>,
+    /// Age after which trust edges are reported as stale
+    pub stale_after: Duration,
+    /// Sybil analysis and path down-weighting
+    pub sybil: SybilConfig,
+    /// Interval between Sybil analysis runs
+    pub sybil_refresh_interval: std::time::Duration,
+    /// Resolution of agents hosted on other hubs
+    pub remote: RemoteTrustConfig,
+}
+
+/// Decay function applied to the trust level of aging edges
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrustDecay {
+    /// Trust keeps its full weight
+    None,
+    /// Weight halves every `half_life_days`
+    Exponential { half_life_days: f32 },
+    /// Weight is multiplied by `factor` for every full `interval_days`
+    Step { interval_days: u32, factor: f32 },
+}
+
+impl TrustDecay {
+    /// Weight (0.0 to 1.0) of a trust edge of the given age
+    pub fn weight(&self, age: Duration) -> f32 {
+        let age_days = age.num_seconds().max(0) as f32 / 86_400.0;
+        match *self {
+            TrustDecay::None => 1.0,
+            TrustDecay::Exponential { half_life_days } => 0.5f32.powf(age_days / half_life_days),
+            TrustDecay::Step { interval_days, factor } => {
+                factor.powi((age_days / interval_days as f32).floor() as i32)
+            }
+        }
+    }
 }
 
 impl Default for TrustConfig {
@@ -29,20 +79,464 @@ impl Default for TrustConfig {
             max_depth: 5,
             damping_factor: 0.8,
             min_trust_threshold: 0.01,
+            min_damping_factor: 0.1,
+            max_damping_factor: 1.0,
+            decay: TrustDecay::None,
+            max_age: None,
+            stale_after: Duration::days(365),
+            sybil: SybilConfig::default(),
+            sybil_refresh_interval: std::time::Duration::from_secs(600),
+            remote: RemoteTrustConfig::default(),
+        }
+    }
+}
+
+impl TrustConfig {
+    /// Check that the configured values are usable
+    pub fn validate(&self) -> HubResult<()> {
+        if self.max_depth == 0 {
+            return Err(HubError::ConfigError("trust.max_depth must be at least 1".to_string()));
+        }
+        if !(self.min_damping_factor > 0.0 && self.min_damping_factor <= self.max_damping_factor
+            && self.max_damping_factor <= 1.0)
+        {
+            return Err(HubError::ConfigError(format!(
+                "trust damping bounds must satisfy 0 < min ({}) <= max ({}) <= 1",
+                self.min_damping_factor, self.max_damping_factor
+            )));
+        }
+        if self.damping_factor < self.min_damping_factor || self.damping_factor > self.max_damping_factor {
+            return Err(HubError::ConfigError(format!(
+                "trust.damping_factor {} is outside [{}, {}]",
+                self.damping_factor, self.min_damping_factor, self.max_damping_factor
+            )));
+        }
+        if !(0.0..1.0).contains(&self.min_trust_threshold) {
+            return Err(HubError::ConfigError(format!(
+                "trust.min_trust_threshold {} must be between 0.0 and 1.0",
+                self.min_trust_threshold
+            )));
+        }
+        match self.decay {
+            TrustDecay::Exponential { half_life_days } if half_life_days <= 0.0 => {
+                return Err(HubError::ConfigError(
+                    "trust.decay_half_life_days must be positive".to_string(),
+                ));
+            }
+            TrustDecay::Step { interval_days, factor } if interval_days == 0 || !(0.0..=1.0).contains(&factor) => {
+                return Err(HubError::ConfigError(
+                    "trust.decay_step_days must be positive and trust.decay_step_factor between 0.0 and 1.0"
+                        .to_string(),
+                ));
+            }
+            _ => {}
+        }
+        if !(self.sybil.suspicion_threshold > 0.0 && self.sybil.suspicion_threshold <= 1.0) {
+            return Err(HubError::ConfigError(
+                "trust.sybil_threshold must be greater than 0.0 and at most 1.0".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.sybil.penalty) {
+            return Err(HubError::ConfigError(
+                "trust.sybil_penalty must be between 0.0 and 1.0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Decay weight of an edge asserted at `asserted_at`, or None if it has expired
+    pub fn edge_weight(&self, asserted_at: DateTime<Utc>, now: DateTime<Utc>) -> Option<f32> {
+        let age = now - asserted_at;
+        if self.max_age.is_some_and(|max_age| age > max_age) {
+            return None;
+        }
+        Some(self.decay.weight(age))
+    }
+
+    /// Apply per-request overrides, rejecting values outside the operator bounds
+    pub fn with_overrides(&self, options: &TrustQueryOptions) -> HubResult<TrustConfig> {
+        let mut config = self.clone();
+
+        if let Some(depth) = options.max_depth {
+            if depth == 0 || depth > self.max_depth {
+                return Err(HubError::ValidationError(format!(
+                    "max_depth must be between 1 and {}",
+                    self.max_depth
+                )));
+            }
+            config.max_depth = depth;
+        }
+
+        if let Some(damping) = options.damping_factor {
+            if !(self.min_damping_factor..=self.max_damping_factor).contains(&damping) {
+                return Err(HubError::ValidationError(format!(
+                    "damping_factor must be between {} and {}",
+                    self.min_damping_factor, self.max_damping_factor
+                )));
+            }
+            config.damping_factor = damping;
+        }
+
+        Ok(config)
+    }
+}
+
+impl TryFrom<&TrustSettings> for TrustConfig {
+    type Error = HubError;
+
+    fn try_from(settings: &TrustSettings) -> HubResult<Self> {
+        let config = Self {
+            max_depth: settings.max_depth,
+            damping_factor: settings.damping_factor,
+            min_trust_threshold: settings.min_trust_threshold,
+            min_damping_factor: settings.min_damping_factor,
+            max_damping_factor: settings.max_damping_factor,
+            decay: match settings.decay_mode {
+                TrustDecayMode::None => TrustDecay::None,
+                TrustDecayMode::Exponential => TrustDecay::Exponential {
+                    half_life_days: settings.decay_half_life_days,
+                },
+                TrustDecayMode::Step => TrustDecay::Step {
+                    interval_days: settings.decay_step_days,
+                    factor: settings.decay_step_factor,
+                },
+            },
+            max_age: match settings.max_trust_age_days {
+                0 => None,
+                days => Some(Duration::days(days as i64)),
+            },
+            stale_after: Duration::days(settings.stale_trust_after_days as i64),
+            sybil: SybilConfig {
+                seeds: settings.sybil_seed_agents.clone(),
+                min_seed_age: Duration::days(settings.sybil_min_seed_age_days as i64),
+                suspicion_threshold: settings.sybil_threshold,
+                penalty: settings.sybil_penalty,
+            },
+            sybil_refresh_interval: std::time::Duration::from_secs(settings.sybil_refresh_sec.max(1)),
+            remote: RemoteTrustConfig {
+                enabled: settings.cross_hub,
+                max_fetches_per_query: settings.max_remote_fetches,
+                cache_ttl: std::time::Duration::from_secs(settings.remote_cache_ttl_sec),
+                timeout: std::time::Duration::from_millis(settings.remote_timeout_ms.max(1)),
+            },
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// Per-request overrides for trust calculations
+#[derive(Debug, Clone, Default)]
+pub struct TrustQueryOptions {
+    /// Maximum path depth (must not exceed the configured max_depth)
+    pub max_depth: Option<u8>,
+    /// Damping factor (must lie within the configured damping bounds)
+    pub damping_factor: Option<f32>,
+    /// Contexts to evaluate trust in; empty means only general trust edges are used
+    pub scope: Vec<TrustScope>,
+    /// Explain trust scores, returning up to this many contributing paths
+    pub explain_top_k: Option<usize>,
+    /// Fold TRUST relations about the entity into its score as endorsements
+    pub include_endorsements: bool,
+}
+
+/// State collected while searching trust paths
+#[derive(Debug, Default)]
+struct SearchTrace {
+    /// Remote agents that could not be resolved from the cache
+    missing: HashSet<Address>,
+    /// Edges cut by min_trust_threshold (only collected when explaining)
+    cut_edges: Option<Vec<CutTrustEdge>>,
+}
+
+/// Resolved set of contexts a trust edge must match to be followed
+#[derive(Debug, Clone, Default)]
+struct ScopeFilter {
+    tags: HashSet<String>,
+    domains: HashSet<ExpertiseDomain>,
+    /// Lowercased tag and domain names, so domain-scoped edges match tagged content
+    names: HashSet<String>,
+}
+
+impl ScopeFilter {
+    fn is_general(&self) -> bool {
+        self.tags.is_empty() && self.domains.is_empty()
+    }
+
+    fn matches(&self, scope: &TrustScope) -> bool {
+        match scope {
+            TrustScope::Tag(addr) => self.tags.contains(&addr.entity),
+            TrustScope::Domain(domain) => {
+                self.domains.contains(domain) || self.names.contains(&domain.name().to_lowercase())
+            }
         }
     }
 }
 
+/// Maximum number of agents kept in the trust cache before it is reset
+const AGENT_CACHE_CAPACITY: usize = 10_000;
+
+/// Maximum number of TRUST relation endorsements folded into a score
+const MAX_ENDORSEMENTS: usize = 32;
+
+/// Maximum number of paths and cut edges returned in an explanation
+pub const MAX_EXPLAIN_PATHS: usize = 20;
+
+/// Aggregation applied to trust paths, as reported in explanations
+const AGGREGATION_FORMULA: &str = "score = max over paths of product(trust_level * damping * sybil_factor) per hop; \
+    damping = 1 for the first hop";
+/// Added to the formula when endorsements were folded in
+const ENDORSEMENT_FORMULA: &str = "; endorsements add a final hop with trust_level = relation confidence";
+
 /// Service for trust path calculations
 pub struct TrustService {
     store: Arc<EntityStore>,
     config: TrustConfig,
+    /// Agents loaded during path finding, keyed by UUID
+    agent_cache: RwLock<HashMap<String, Agent>>,
+    /// Latest Sybil analysis, if one has run
+    sybil_report: RwLock<Option<Arc<SybilReport>>>,
+    /// `server:port` of this hub, used to tell local from remote agents
+    local_server: Option<String>,
+    /// Fetcher for agents on other hubs (None if cross-hub resolution is disabled)
+    remote: Option<RemoteTrustFetcher>,
 }
 
 impl TrustService {
     /// Create a new trust service
     pub fn new(store: Arc<EntityStore>, config: TrustConfig) -> Self {
-        Self { store, config }
+        let remote = config.remote.enabled.then(|| RemoteTrustFetcher::new(config.remote.clone()));
+
+        Self {
+            store,
+            config,
+            agent_cache: RwLock::new(HashMap::new()),
+            sybil_report: RwLock::new(None),
+            local_server: None,
+            remote,
+        }
+    }
+
+    /// Set the `server:port` under which this hub's agents are addressed
+    pub fn with_local_server(mut self, server_port: impl Into<String>) -> Self {
+        self.local_server = Some(server_port.into());
+        self
+    }
+
+    /// Fetch agents on other hubs only from the verified hubs in `directory`
+    pub fn with_hub_directory(mut self, directory: Arc<dyn HubDirectory>) -> Self {
+        self.remote = self.remote.map(|remote| remote.with_directory(directory));
+        self
+    }
+
+    /// Fetcher for agents on other hubs, if cross-hub resolution is enabled
+    pub fn remote(&self) -> Option<&RemoteTrustFetcher> {
+        self.remote.as_ref()
+    }
+
+    /// Latest Sybil analysis, if one has run
+    pub fn sybil_report(&self) -> Option<Arc<SybilReport>> {
+        self.sybil_report.read().clone()
+    }
+
+    /// Re-run the Sybil analysis over all local agents
+    pub fn refresh_sybil_analysis(&self) -> HubResult<Arc<SybilReport>> {
+        let mut agents = Vec::new();
+        let mut cursor = Cursor::start();
+        loop {
+            let page = self.store.list_agents(&cursor, 1000)?;
+            agents.extend(page.items);
+            match page.next_cursor {
+                Some(next) if page.has_more => cursor = Cursor::from_uuid(next),
+                _ => break,
+            }
+        }
+
+        let report = Arc::new(analyze_sybils(&agents, &self.config.sybil, Utc::now()));
+        *self.sybil_report.write() = Some(Arc::clone(&report));
+        Ok(report)
+    }
+
+    /// Run [`refresh_sybil_analysis`](Self::refresh_sybil_analysis) on a blocking thread
+    pub async fn run_sybil_analysis(self: &Arc<Self>) -> HubResult<Arc<SybilReport>> {
+        let service = Arc::clone(self);
+        tokio::task::spawn_blocking(move || service.refresh_sybil_analysis())
+            .await
+            .map_err(|e| HubError::Internal(format!("Sybil analysis task failed: {}", e)))?
+    }
+
+    /// Start periodic Sybil analysis in the background
+    pub fn start_sybil_analysis(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let interval = self.config.sybil_refresh_interval;
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+
+            loop {
+                ticker.tick().await;
+                match self.run_sybil_analysis().await {
+                    Ok(report) => debug!(
+                        "Sybil analysis: {} agents, {} suspected clusters",
+                        report.agent_count,
+                        report.clusters.len()
+                    ),
+                    Err(e) => warn!("Sybil analysis failed: {}", e),
+                }
+            }
+        })
+    }
+
+    /// Path weight multiplier for passing through an agent
+    fn sybil_factor(&self, report: Option<&SybilReport>, config: &TrustConfig, addr: &Address) -> f32 {
+        let report = match report {
+            Some(report) if config.sybil.penalty > 0.0 => report,
+            _ => return 1.0,
+        };
+        let suspicion = report.suspicion(&addr.entity, self.is_local(addr));
+        if suspicion >= config.sybil.suspicion_threshold {
+            1.0 - config.sybil.penalty * suspicion
+        } else {
+            1.0
+        }
+    }
+
+    /// Weight of an agent's votes and contradictions in other agents' profiles:
+    /// the path weight multiplier of their Sybil suspicion
+    pub fn evidence_weight(&self, addr: &Address) -> f32 {
+        self.sybil_factor(self.sybil_report().as_deref(), &self.config, addr).max(0.0)
+    }
+
+    /// Drop the cached trust store of an agent after it changed or was deleted
+    pub fn invalidate_agent(&self, uuid: &str) {
+        self.agent_cache.write().remove(uuid);
+    }
+
+    /// Refetch a remote agent, e.g. after its hub announced a key rotation.
+    ///
+    /// Returns None for local agents and when remote resolution is disabled.
+    pub async fn refresh_remote_agent(&self, addr: &Address) -> HubResult<Option<Agent>> {
+        match self.remote {
+            Some(ref remote) if !self.is_local(addr) => remote.fetch(addr).await.map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Drop all cached trust stores
+    pub fn clear_cache(&self) {
+        self.agent_cache.write().clear();
+    }
+
+    /// Load an agent through the cache
+    fn load_agent(&self, uuid: &str) -> HubResult<Option<Agent>> {
+        if let Some(agent) = self.agent_cache.read().get(uuid) {
+            return Ok(Some(agent.clone()));
+        }
+
+        let agent = self.store.get_agent(uuid)?;
+        if let Some(ref agent) = agent {
+            let mut cache = self.agent_cache.write();
+            if cache.len() >= AGENT_CACHE_CAPACITY {
+                cache.clear();
+            }
+            cache.insert(uuid.to_string(), agent.clone());
+        }
+        Ok(agent)
+    }
+
+    /// Check if an agent lives on this hub; without a local server set, all do
+    pub fn is_local(&self, addr: &Address) -> bool {
+        addr.server_port.is_empty()
+            || self.local_server.as_deref().is_none_or(|local| local == addr.server_port)
+    }
+
+    /// Resolve an agent and the hub its trust store was read from.
+    ///
+    /// Local agents come from the store; agents on other hubs come from the
+    /// remote cache only, even if a local agent has the same UUID. Remote
+    /// agents that have not been fetched yet are recorded in the trace so the
+    /// caller can fetch them and retry.
+    fn resolve_agent(
+        &self,
+        addr: &Address,
+        trace: &mut SearchTrace,
+    ) -> HubResult<Option<(Agent, Option<String>)>> {
+        if self.is_local(addr) {
+            return Ok(self.load_agent(&addr.entity)?.map(|agent| (agent, self.local_server.clone())));
+        }
+
+        let remote = match self.remote {
+            Some(ref remote) => remote,
+            None => return Ok(None),
+        };
+        match remote.cached(addr) {
+            Some(agent) => Ok(agent.map(|agent| (agent, Some(addr.server_port.clone())))),
+            None => {
+                trace.missing.insert(addr.clone());
+                Ok(None)
+            }
+        }
+    }
+
+    /// Run a query, fetching remote agents it could not resolve and retrying
+    /// until everything reachable is resolved or the fetch budget is spent.
+    async fn with_remote_agents<T>(
+        &self,
+        mut query: impl FnMut(&mut SearchTrace) -> HubResult<T>,
+    ) -> HubResult<T> {
+        let remote = match self.remote {
+            Some(ref remote) => remote,
+            None => return query(&mut SearchTrace::default()),
+        };
+        let mut budget = remote.config().max_fetches_per_query;
+
+        loop {
+            let mut trace = SearchTrace::default();
+            let result = query(&mut trace)?;
+            let missing = trace.missing;
+            if missing.is_empty() || budget == 0 {
+                if !missing.is_empty() {
+                    debug!("Remote fetch budget exhausted, {} agents unresolved", missing.len());
+                }
+                return Ok(result);
+            }
+
+            // Fetch in a stable order so truncation is deterministic
+            let mut batch: Vec<Address> = missing.into_iter().collect();
+            batch.sort_by_key(|addr| addr.to_string());
+            batch.truncate(budget);
+            budget -= batch.len();
+
+            debug!("Fetching {} remote trust stores", batch.len());
+            join_all(batch.iter().map(|addr| remote.fetch(addr))).await;
+        }
+    }
+
+    /// Find the best trust path, fetching trust stores from other hubs as needed
+    pub async fn find_best_path_federated(
+        &self,
+        from: &Address,
+        to: &Address,
+        options: &TrustQueryOptions,
+    ) -> HubResult<Option<TrustPath>> {
+        let config = self.config.with_overrides(options)?;
+        let scope = self.resolve_scope(&options.scope)?;
+        self.with_remote_agents(|trace| self.best_path_with_config(from, to, &config, &scope, trace))
+            .await
+    }
+
+    /// Calculate a trust score, fetching trust stores from other hubs as needed
+    pub async fn calculate_trust_score_federated(
+        &self,
+        entity: &Address,
+        viewer: &Address,
+        options: &TrustQueryOptions,
+    ) -> HubResult<TrustScore> {
+        let config = self.config.with_overrides(options)?;
+        let scope = self.resolve_scope(&options.scope)?;
+        self.with_remote_agents(|trace| {
+            self.trust_score_with_config(entity, viewer, &config, scope.clone(), options, trace)
+        })
+        .await
     }
 
     /// Get the store reference
@@ -50,11 +544,150 @@ impl TrustService {
         &self.store
     }
 
+    /// Get the operator configuration
+    pub fn config(&self) -> &TrustConfig {
+        &self.config
+    }
+
     /// Find the best trust path from one agent to another using BFS
     pub fn find_best_path(
         &self,
         from: &Address,
         to: &Address,
+    ) -> HubResult<Option<TrustPath>> {
+        self.best_path_with_config(from, to, &self.config, &ScopeFilter::default(), &mut SearchTrace::default())
+    }
+
+    /// Find the best trust path using per-request overrides
+    pub fn find_best_path_with(
+        &self,
+        from: &Address,
+        to: &Address,
+        options: &TrustQueryOptions,
+    ) -> HubResult<Option<TrustPath>> {
+        let config = self.config.with_overrides(options)?;
+        let scope = self.resolve_scope(&options.scope)?;
+        self.best_path_with_config(from, to, &config, &scope, &mut SearchTrace::default())
+    }
+
+    /// Find all trust paths up to max_depth using BFS
+    pub fn find_all_paths(
+        &self,
+        from: &Address,
+        to: &Address,
+    ) -> HubResult<Vec<TrustPath>> {
+        self.all_paths_with_config(from, to, &self.config, &ScopeFilter::default(), &mut SearchTrace::default())
+    }
+
+    /// Calculate trust score for an entity from a viewer's perspective
+    pub fn calculate_trust_score(
+        &self,
+        entity: &Address,
+        viewer: &Address,
+    ) -> HubResult<TrustScore> {
+        self.trust_score_with_config(
+            entity,
+            viewer,
+            &self.config,
+            ScopeFilter::default(),
+            &TrustQueryOptions::default(),
+            &mut SearchTrace::default(),
+        )
+    }
+
+    /// Calculate trust score using per-request overrides
+    pub fn calculate_trust_score_with(
+        &self,
+        entity: &Address,
+        viewer: &Address,
+        options: &TrustQueryOptions,
+    ) -> HubResult<TrustScore> {
+        let config = self.config.with_overrides(options)?;
+        let scope = self.resolve_scope(&options.scope)?;
+        self.trust_score_with_config(entity, viewer, &config, scope, options, &mut SearchTrace::default())
+    }
+
+    /// Resolve requested scopes, looking up tag names so domain-scoped edges match tags
+    fn resolve_scope(&self, scopes: &[TrustScope]) -> HubResult<ScopeFilter> {
+        let mut filter = ScopeFilter::default();
+        for scope in scopes {
+            match scope {
+                TrustScope::Tag(addr) => self.add_tag_to_scope(&mut filter, addr)?,
+                TrustScope::Domain(domain) => {
+                    filter.domains.insert(domain.clone());
+                }
+            }
+        }
+        Ok(filter)
+    }
+
+    fn add_tag_to_scope(&self, filter: &mut ScopeFilter, tag: &Address) -> HubResult<()> {
+        match self.store.get_tag(&tag.entity)? {
+            // Quarantined tags do not narrow trust
+            Some(stored) if stored.is_quarantined() => {}
+            Some(stored) => {
+                filter.tags.insert(tag.entity.clone());
+                filter.names.insert(stored.name.to_lowercase());
+            }
+            None => {
+                filter.tags.insert(tag.entity.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Decayed trust levels an agent assigns to others within a scope.
+    ///
+    /// Expired edges are skipped. Matching scoped edges take precedence over
+    /// general trust; if several scopes match, the most cautious (lowest)
+    /// level is used.
+    fn effective_trusts(
+        agent: &Agent,
+        scope: &ScopeFilter,
+        config: &TrustConfig,
+        now: DateTime<Utc>,
+    ) -> Vec<(Address, f32)> {
+        let mut general: Vec<(Address, f32)> = Vec::new();
+        let mut scoped: HashMap<&Address, f32> = HashMap::new();
+
+        for trust in &agent.trust.trusts {
+            let weight = match config.edge_weight(trust.asserted_or(agent.created_at), now) {
+                Some(weight) => weight,
+                None => continue,
+            };
+            let level = trust.trust * weight;
+
+            match &trust.scope {
+                None => general.push((trust.agent.clone(), level)),
+                Some(s) if !scope.is_general() && scope.matches(s) => {
+                    scoped.entry(&trust.agent)
+                        .and_modify(|current| *current = current.min(level))
+                        .or_insert(level);
+                }
+                Some(_) => {}
+            }
+        }
+
+        let mut result: Vec<(Address, f32)> = general
+            .into_iter()
+            .filter(|(addr, _)| !scoped.contains_key(addr))
+            .collect();
+        // Keep scoped entries in trust store order for deterministic traversal
+        for trust in &agent.trust.trusts {
+            if let Some(level) = scoped.remove(&trust.agent) {
+                result.push((trust.agent.clone(), level));
+            }
+        }
+        result
+    }
+
+    fn best_path_with_config(
+        &self,
+        from: &Address,
+        to: &Address,
+        config: &TrustConfig,
+        scope: &ScopeFilter,
+        trace: &mut SearchTrace,
     ) -> HubResult<Option<TrustPath>> {
         // Self-trust is always 1.0
         if from == to {
@@ -62,7 +695,7 @@ impl TrustService {
         }
 
         // Find all paths using BFS
-        let paths = self.find_all_paths(from, to)?;
+        let paths = self.all_paths_with_config(from, to, config, scope, trace)?;
 
         // Return the path with highest effective trust
         Ok(paths.into_iter().max_by(|a, b| {
@@ -72,11 +705,13 @@ impl TrustService {
         }))
     }
 
-    /// Find all trust paths up to max_depth using BFS
-    pub fn find_all_paths(
+    fn all_paths_with_config(
         &self,
         from: &Address,
         to: &Address,
+        config: &TrustConfig,
+        scope: &ScopeFilter,
+        trace: &mut SearchTrace,
     ) -> HubResult<Vec<TrustPath>> {
         if from == to {
             return Ok(vec![TrustPath::direct(from.clone(), to.clone(), 1.0)]);
@@ -89,6 +724,8 @@ impl TrustService {
             ));
         }
 
+        let now = Utc::now();
+        let sybil_report = self.sybil_report();
         let mut paths = Vec::new();
         let mut visited = HashSet::new();
 
@@ -99,22 +736,24 @@ impl TrustService {
 
         while let Some((current, path, cumulative_trust)) = queue.pop_front() {
             // Check depth limit
-            if path.len() >= self.config.max_depth as usize {
-                continue;
-            }
-
-            // Check if below minimum threshold
-            if cumulative_trust.abs() < self.config.min_trust_threshold {
+            if path.len() >= config.max_depth as usize {
                 continue;
             }
 
             // Mark as visited for this path
             visited.insert(current.entity.clone());
 
-            // Get the current agent to access their trust store
-            if let Some(agent) = self.store.get_agent(&current.entity)? {
-                for trust in &agent.trust.trusts {
-                    let trustee = &trust.agent;
+            // Paths passing through suspected Sybils are down-weighted
+            let sybil_factor = if path.is_empty() {
+                1.0
+            } else {
+                self.sybil_factor(sybil_report.as_deref(), config, &current)
+            };
+
+            // Get the current agent to access their trust store, possibly from another hub
+            if let Some((agent, hub)) = self.resolve_agent(&current, trace)? {
+                for (trustee, hop_trust) in Self::effective_trusts(&agent, scope, config, now) {
+                    let trustee = &trustee;
 
                     // Skip if already in path (avoid cycles)
                     if path.iter().any(|h| h.agent == *trustee) || trustee.entity == from.entity {
@@ -122,15 +761,15 @@ impl TrustService {
                     }
 
                     // Calculate new trust level with damping
-                    let hop_trust = trust.trust;
-                    let damping = if path.is_empty() { 1.0 } else { self.config.damping_factor };
-                    let new_cumulative = cumulative_trust * hop_trust * damping;
+                    let damping = if path.is_empty() { 1.0 } else { config.damping_factor };
+                    let new_cumulative = cumulative_trust * hop_trust * damping * sybil_factor;
 
                     // Build new path
                     let mut new_path = path.clone();
                     new_path.push(TrustPathHop {
                         agent: trustee.clone(),
                         trust_level: hop_trust,
+                        hub: hub.clone(),
                     });
 
                     // Check if we reached the target
@@ -144,8 +783,17 @@ impl TrustService {
                         };
                         paths.push(trust_path);
                     } else if !visited.contains(&trustee.entity) && trustee.domain == Domain::Agent {
-                        // Continue exploring (only follow agent nodes)
-                        queue.push_back((trustee.clone(), new_path, new_cumulative));
+                        // Continue exploring (only follow agent nodes) unless below the minimum threshold
+                        if new_cumulative.abs() >= config.min_trust_threshold {
+                            queue.push_back((trustee.clone(), new_path, new_cumulative));
+                        } else if let Some(ref mut cut_edges) = trace.cut_edges {
+                            cut_edges.push(CutTrustEdge {
+                                from: current.clone(),
+                                to: trustee.clone(),
+                                trust_level: hop_trust,
+                                cumulative_trust: new_cumulative,
+                            });
+                        }
                     }
                 }
             }
@@ -164,23 +812,203 @@ impl TrustService {
         Ok(paths)
     }
 
-    /// Calculate trust score for an entity from a viewer's perspective
-    pub fn calculate_trust_score(
+    fn trust_score_with_config(
         &self,
         entity: &Address,
         viewer: &Address,
+        config: &TrustConfig,
+        mut scope: ScopeFilter,
+        options: &TrustQueryOptions,
+        trace: &mut SearchTrace,
     ) -> HubResult<TrustScore> {
-        // Calculate from viewer's perspective using best path
-        if let Some(path) = self.find_best_path(viewer, entity)? {
-            Ok(TrustScore::new(
+        if options.explain_top_k.is_some() {
+            trace.cut_edges.get_or_insert_with(Vec::new);
+        }
+
+        // Fragments are scored through their creator, in the context of their tags
+        let mut target = entity.clone();
+        if entity.domain == Domain::Fragment {
+            if let Some(fragment) = self.store.get_fragment(&entity.entity)? {
+                // Content signed with a revoked key earns no trust until confirmed
+                if fragment.is_quarantined() {
+                    return Ok(TrustScore::neutral(entity.clone(), viewer.clone()));
+                }
+                for tag in &fragment.tags {
+                    self.add_tag_to_scope(&mut scope, tag)?;
+                }
+                target = fragment.creator;
+            }
+        }
+
+        // Paths from the viewer to the target, plus paths ending in endorsements if asked for
+        let mut paths: Vec<(TrustPath, Option<String>)> = self
+            .all_paths_with_config(viewer, &target, config, &scope, trace)?
+            .into_iter()
+            .map(|path| (path, None))
+            .collect();
+        let mut endorsements = Vec::new();
+        let relations = if options.include_endorsements { self.endorsements(entity)? } else { Vec::new() };
+        for relation in relations {
+            let (endorsement, path) = self.endorsement_path(&relation, entity, viewer, config, &scope, trace)?;
+            if let Some(path) = path {
+                paths.push((path, Some(relation.uuid.clone())));
+            }
+            endorsements.push(endorsement);
+        }
+        paths.sort_by(|a, b| {
+            b.0.effective_trust
+                .partial_cmp(&a.0.effective_trust)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let mut score = match paths.first() {
+            Some((best, _)) => TrustScore::new(
                 entity.clone(),
                 viewer.clone(),
-                path.effective_trust,
-                1,
-            ).with_best_path(path))
-        } else {
+                best.effective_trust,
+                paths.len(),
+            ).with_best_path(best.clone()),
             // No path found - neutral score
-            Ok(TrustScore::neutral(entity.clone(), viewer.clone()))
+            None => TrustScore::neutral(entity.clone(), viewer.clone()),
+        };
+
+        if let Some(top_k) = options.explain_top_k {
+            let report = self.sybil_report();
+            let mut cut_edges = trace.cut_edges.take().unwrap_or_default();
+            let mut seen = HashSet::new();
+            cut_edges.retain(|e| seen.insert((e.from.entity.clone(), e.to.entity.clone())));
+            cut_edges.truncate(MAX_EXPLAIN_PATHS);
+
+            let mut formula = AGGREGATION_FORMULA.to_string();
+            if options.include_endorsements {
+                formula.push_str(ENDORSEMENT_FORMULA);
+            }
+            score = score.with_explanation(TrustExplanation {
+                formula,
+                target,
+                damping_factor: config.damping_factor,
+                min_trust_threshold: config.min_trust_threshold,
+                max_depth: config.max_depth,
+                paths: paths
+                    .iter()
+                    .take(top_k.min(MAX_EXPLAIN_PATHS))
+                    .map(|(path, relation)| self.explain_path(path, relation.clone(), config, report.as_deref()))
+                    .collect(),
+                endorsements,
+                cut_edges,
+            });
+        }
+
+        Ok(score)
+    }
+
+    /// TRUST relations asserted about an entity, highest confidence first
+    fn endorsements(&self, entity: &Address) -> HubResult<Vec<Relation>> {
+        let mut relations: Vec<Relation> = self.store
+            .get_relations_by_from(&entity.entity)?
+            .into_iter()
+            .filter(|r| r.relation_type == RelationType::Trust && r.from.domain == entity.domain)
+            .filter(|r| !r.is_quarantined())
+            .filter(|r| r.asserter().domain == Domain::Agent)
+            .collect();
+        relations.sort_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap_or(std::cmp::Ordering::Equal));
+        relations.truncate(MAX_ENDORSEMENTS);
+        Ok(relations)
+    }
+
+    /// Path through an endorsing agent: the viewer's best path to the endorser,
+    /// extended by a final hop weighted by the relation's confidence.
+    ///
+    /// Only endorsers the viewer trusts contribute.
+    fn endorsement_path(
+        &self,
+        relation: &Relation,
+        entity: &Address,
+        viewer: &Address,
+        config: &TrustConfig,
+        scope: &ScopeFilter,
+        trace: &mut SearchTrace,
+    ) -> HubResult<(ExplainedEndorsement, Option<TrustPath>)> {
+        let endorser = relation.asserter().clone();
+        let mut endorsement = ExplainedEndorsement {
+            relation: relation.uuid.clone(),
+            endorser: endorser.clone(),
+            confidence: relation.confidence,
+            endorser_trust: None,
+            contribution: 0.0,
+        };
+
+        let (mut hops, contribution) = if endorser.entity == viewer.entity {
+            endorsement.endorser_trust = Some(1.0);
+            (Vec::new(), relation.confidence)
+        } else {
+            let path = match self.best_path_with_config(viewer, &endorser, config, scope, trace)? {
+                Some(path) => path,
+                None => return Ok((endorsement, None)),
+            };
+            endorsement.endorser_trust = Some(path.effective_trust);
+            if path.depth >= config.max_depth as usize {
+                return Ok((endorsement, None));
+            }
+            let sybil_factor = self.sybil_factor(self.sybil_report().as_deref(), config, &endorser);
+            let contribution = path.effective_trust * relation.confidence * config.damping_factor * sybil_factor;
+            (path.hops, contribution)
+        };
+
+        if contribution <= 0.0 || contribution < config.min_trust_threshold {
+            return Ok((endorsement, None));
+        }
+        endorsement.contribution = contribution;
+
+        hops.push(TrustPathHop {
+            agent: entity.clone(),
+            trust_level: relation.confidence,
+            hub: self.local_server.clone(),
+        });
+        let path = TrustPath {
+            from: viewer.clone(),
+            to: entity.clone(),
+            depth: hops.len(),
+            hops,
+            effective_trust: contribution,
+        };
+        Ok((endorsement, Some(path)))
+    }
+
+    /// Break a path down into the factors applied at each hop
+    fn explain_path(
+        &self,
+        path: &TrustPath,
+        endorsement: Option<String>,
+        config: &TrustConfig,
+        report: Option<&SybilReport>,
+    ) -> ExplainedPath {
+        let mut cumulative = 1.0;
+        let hops = path.hops
+            .iter()
+            .enumerate()
+            .map(|(i, hop)| {
+                let (damping, sybil_factor) = if i == 0 {
+                    (1.0, 1.0)
+                } else {
+                    (config.damping_factor, self.sybil_factor(report, config, &path.hops[i - 1].agent))
+                };
+                cumulative *= hop.trust_level * damping * sybil_factor;
+                ExplainedHop {
+                    agent: hop.agent.clone(),
+                    hub: hop.hub.clone(),
+                    trust_level: hop.trust_level,
+                    damping,
+                    sybil_factor,
+                    cumulative_trust: cumulative,
+                }
+            })
+            .collect();
+
+        ExplainedPath {
+            hops,
+            effective_trust: path.effective_trust,
+            endorsement,
         }
     }
 
@@ -194,10 +1022,11 @@ impl TrustService {
             return Ok(None);
         }
 
-        if let Some(agent) = self.store.get_agent(&from.entity)? {
-            for trust in &agent.trust.trusts {
-                if trust.agent.entity == to.entity {
-                    return Ok(Some(trust.trust));
+        if let Some(agent) = self.load_agent(&from.entity)? {
+            let trusts = Self::effective_trusts(&agent, &ScopeFilter::default(), &self.config, Utc::now());
+            for (trustee, level) in trusts {
+                if trustee.entity == to.entity {
+                    return Ok(Some(level));
                 }
             }
         }
@@ -215,6 +1044,7 @@ impl TrustService {
         let mut edges = Vec::new();
         let mut visited = HashSet::new();
         let mut queue = VecDeque::new();
+        let now = Utc::now();
 
         queue.push_back((center.clone(), 0u8));
 
@@ -225,19 +1055,25 @@ impl TrustService {
             visited.insert(current.entity.clone());
 
             // Add node
-            if let Some(agent) = self.store.get_agent(&current.entity)? {
+            if let Some(agent) = self.load_agent(&current.entity)? {
                 nodes.insert(current.entity.clone(), TrustGraphNode {
                     address: current.clone(),
                     description: agent.description,
                     depth,
+                    reputation: None,
                 });
 
-                // Get outgoing trust relations from embedded TrustStore
+                // Get outgoing trust relations from embedded TrustStore, skipping expired ones
                 for trust in &agent.trust.trusts {
+                    let weight = match self.config.edge_weight(trust.asserted_or(agent.created_at), now) {
+                        Some(weight) => weight,
+                        None => continue,
+                    };
                     edges.push(TrustGraphEdge {
                         from: current.clone(),
                         to: trust.agent.clone(),
-                        trust_level: trust.trust,
+                        trust_level: trust.trust * weight,
+                        scope: trust.scope.clone(),
                     });
 
                     if !visited.contains(&trust.agent.entity) && trust.agent.domain == Domain::Agent {
@@ -247,29 +1083,91 @@ impl TrustService {
             }
         }
 
-        Ok(TrustGraph { nodes, edges })
+        // Only keep edges between nodes of the graph, so exports stay well-formed
+        edges.retain(|e| nodes.contains_key(&e.from.entity) && nodes.contains_key(&e.to.entity));
+
+        let mut incoming: HashMap<&str, (f32, usize)> = HashMap::new();
+        for edge in &edges {
+            let entry = incoming.entry(edge.to.entity.as_str()).or_insert((0.0, 0));
+            entry.0 += edge.trust_level;
+            entry.1 += 1;
+        }
+        for (entity, node) in nodes.iter_mut() {
+            node.reputation = incoming.get(entity.as_str()).map(|(sum, count)| sum / *count as f32);
+        }
+
+        Ok(TrustGraph { center: center.clone(), nodes, edges })
+    }
+
+    /// List an agent's trust edges that are older than the staleness threshold
+    pub fn stale_trust_report(&self, uuid: &str) -> HubResult<StaleTrustReport> {
+        let agent = self.store
+            .get_agent(uuid)?
+            .ok_or_else(|| HubError::not_found("agent", uuid))?;
+        let now = Utc::now();
+
+        let mut stale: Vec<StaleTrustEdge> = agent.trust.trusts
+            .iter()
+            .filter_map(|trust| {
+                let asserted_at = trust.asserted_or(agent.created_at);
+                let age = now - asserted_at;
+                if age <= self.config.stale_after {
+                    return None;
+                }
+                let weight = self.config.edge_weight(asserted_at, now);
+                Some(StaleTrustEdge {
+                    agent: trust.agent.clone(),
+                    scope: trust.scope.clone(),
+                    trust: trust.trust,
+                    asserted_at,
+                    age_days: age.num_days() as u32,
+                    weight: weight.unwrap_or(0.0),
+                    expired: weight.is_none(),
+                })
+            })
+            .collect();
+        stale.sort_by_key(|edge| edge.asserted_at);
+
+        Ok(StaleTrustReport {
+            agent: agent.uuid,
+            stale,
+            total: agent.trust.trusts.len(),
+            generated_at: now,
+        })
     }
 }
 
 /// Node in a trust graph
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TrustGraphNode {
     pub address: Address,
     pub description: String,
     pub depth: u8,
+    /// Mean trust level of incoming edges within the graph (None without incoming edges)
+    pub reputation: Option<f32>,
 }
 
 /// Edge in a trust graph
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TrustGraphEdge {
     pub from: Address,
     pub to: Address,
     pub trust_level: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<TrustScope>,
+}
+
+impl TrustGraphEdge {
+    /// Whether this edge expresses distrust
+    pub fn is_distrust(&self) -> bool {
+        self.trust_level < 0.0
+    }
 }
 
 /// Trust graph structure
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TrustGraph {
+    pub center: Address,
     pub nodes: HashMap<String, TrustGraphNode>,
     pub edges: Vec<TrustGraphEdge>,
 }
@@ -277,7 +1175,7 @@ pub struct TrustGraph {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::{Agent, CreateAgentRequest};
+    use crate::models::{Agent, CreateAgentRequest, Fragment, Relation, RelationType, Tag, TagCategory};
     use crate::store::RocksStore;
     use tempfile::tempdir;
 
@@ -293,7 +1191,9 @@ mod tests {
             uuid: Some(uuid.to_string()),
             public_key: "dGVzdC1rZXk=".to_string(),
             description: Some(format!("Agent {}", uuid)),
+            trust: None,
             primary_hub: None,
+            recovery_key: None,
             signature: "sig".to_string(),
         };
         let agent = Agent::from(req);
@@ -400,4 +1300,382 @@ mod tests {
         assert!((score.score - 0.9).abs() < 0.001);
         assert_eq!(score.path_count, 1);
     }
+
+    #[test]
+    fn test_depth_override_limits_search() {
+        let (service, _dir) = setup_test_service();
+
+        let mut alice = create_test_agent(&service.store, "alice");
+        let mut bob = create_test_agent(&service.store, "bob");
+        let _charlie = create_test_agent(&service.store, "charlie");
+
+        let alice_addr = Address::agent("hub:8080", "alice");
+        let bob_addr = Address::agent("hub:8080", "bob");
+        let charlie_addr = Address::agent("hub:8080", "charlie");
+
+        alice.add_trust(bob_addr.clone(), 0.9);
+        service.store.put_agent(&alice).unwrap();
+        bob.add_trust(charlie_addr.clone(), 0.8);
+        service.store.put_agent(&bob).unwrap();
+
+        let shallow = TrustQueryOptions { max_depth: Some(1), ..Default::default() };
+        let path = service.find_best_path_with(&alice_addr, &charlie_addr, &shallow).unwrap();
+        assert!(path.is_none());
+
+        let gentle = TrustQueryOptions { damping_factor: Some(1.0), ..Default::default() };
+        let score = service.calculate_trust_score_with(&charlie_addr, &alice_addr, &gentle).unwrap();
+        assert!((score.score - 0.72).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_overrides_outside_bounds_rejected() {
+        let (service, _dir) = setup_test_service();
+        let alice_addr = Address::agent("hub:8080", "alice");
+        let bob_addr = Address::agent("hub:8080", "bob");
+
+        let too_deep = TrustQueryOptions { max_depth: Some(6), ..Default::default() };
+        assert!(matches!(
+            service.find_best_path_with(&alice_addr, &bob_addr, &too_deep),
+            Err(HubError::ValidationError(_))
+        ));
+
+        let too_low = TrustQueryOptions { damping_factor: Some(0.05), ..Default::default() };
+        assert!(matches!(
+            service.calculate_trust_score_with(&bob_addr, &alice_addr, &too_low),
+            Err(HubError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_config_from_settings() {
+        let mut settings = TrustSettings {
+            max_depth: 3,
+            damping_factor: 0.7,
+            min_trust_threshold: 0.05,
+            min_damping_factor: 0.5,
+            max_damping_factor: 0.9,
+            decay_mode: TrustDecayMode::Step,
+            decay_half_life_days: 365.0,
+            decay_step_days: 30,
+            decay_step_factor: 0.5,
+            max_trust_age_days: 90,
+            stale_trust_after_days: 60,
+            sybil_seed_agents: vec!["alice".to_string()],
+            sybil_min_seed_age_days: 30,
+            sybil_threshold: 0.7,
+            sybil_penalty: 0.5,
+            sybil_refresh_sec: 600,
+            cross_hub: true,
+            max_remote_fetches: 8,
+            remote_cache_ttl_sec: 300,
+            remote_timeout_ms: 3000,
+        };
+
+        let config = TrustConfig::try_from(&settings).unwrap();
+        assert_eq!(config.max_depth, 3);
+        assert_eq!(config.decay, TrustDecay::Step { interval_days: 30, factor: 0.5 });
+        assert_eq!(config.max_age, Some(Duration::days(90)));
+        assert!((config.damping_factor - 0.7).abs() < f32::EPSILON);
+
+        settings.damping_factor = 0.95;
+        assert!(TrustConfig::try_from(&settings).is_err());
+    }
+
+    #[test]
+    fn test_cache_invalidation() {
+        let (service, _dir) = setup_test_service();
+
+        let mut alice = create_test_agent(&service.store, "alice");
+        let _bob = create_test_agent(&service.store, "bob");
+        let alice_addr = Address::agent("hub:8080", "alice");
+        let bob_addr = Address::agent("hub:8080", "bob");
+
+        // Prime the cache with Alice's empty trust store
+        assert!(service.find_best_path(&alice_addr, &bob_addr).unwrap().is_none());
+
+        alice.add_trust(bob_addr.clone(), 0.9);
+        service.store.put_agent(&alice).unwrap();
+        assert!(service.find_best_path(&alice_addr, &bob_addr).unwrap().is_none());
+
+        service.invalidate_agent("alice");
+        assert!(service.find_best_path(&alice_addr, &bob_addr).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_scoped_trust_paths() {
+        let (service, _dir) = setup_test_service();
+
+        let mut alice = create_test_agent(&service.store, "alice");
+        let _bob = create_test_agent(&service.store, "bob");
+        let alice_addr = Address::agent("hub:8080", "alice");
+        let bob_addr = Address::agent("hub:8080", "bob");
+        let rust = TrustScope::Domain(ExpertiseDomain::programming("rust"));
+        let finance = TrustScope::Domain(ExpertiseDomain::business("finance"));
+
+        alice.add_scoped_trust(bob_addr.clone(), 0.9, Some(rust.clone()));
+        alice.add_scoped_trust(bob_addr.clone(), 0.0, Some(finance.clone()));
+        service.store.put_agent(&alice).unwrap();
+
+        // Scoped edges are ignored for general queries
+        assert!(service.find_best_path(&alice_addr, &bob_addr).unwrap().is_none());
+
+        let on_rust = TrustQueryOptions { scope: vec![rust], ..Default::default() };
+        let path = service.find_best_path_with(&alice_addr, &bob_addr, &on_rust).unwrap().unwrap();
+        assert!((path.effective_trust - 0.9).abs() < 0.001);
+
+        let on_finance = TrustQueryOptions { scope: vec![finance], ..Default::default() };
+        let path = service.find_best_path_with(&alice_addr, &bob_addr, &on_finance).unwrap().unwrap();
+        assert_eq!(path.effective_trust, 0.0);
+    }
+
+    #[test]
+    fn test_fragment_score_uses_tags_as_scope() {
+        let (service, _dir) = setup_test_service();
+
+        let mut alice = create_test_agent(&service.store, "alice");
+        let _bob = create_test_agent(&service.store, "bob");
+        let alice_addr = Address::agent("hub:8080", "alice");
+        let bob_addr = Address::agent("hub:8080", "bob");
+
+        let mut tag = Tag::new("rust", TagCategory::Language, bob_addr.clone());
+        tag.uuid = "rust-tag".to_string();
+        service.store.put_tag(&tag).unwrap();
+        let tag_addr = Address::tag("hub:8080", "rust-tag");
+
+        alice.add_trust(bob_addr.clone(), 0.2);
+        alice.add_scoped_trust(
+            bob_addr.clone(),
+            0.9,
+            Some(TrustScope::Domain(ExpertiseDomain::programming("rust"))),
+        );
+        service.store.put_agent(&alice).unwrap();
+
+        let tagged = Fragment::with_uuid("frag-1", "ownership rules", bob_addr.clone()).with_tag(tag_addr);
+        service.store.put_fragment(&tagged).unwrap();
+        let untagged = Fragment::with_uuid("frag-2", "stock tips", bob_addr);
+        service.store.put_fragment(&untagged).unwrap();
+
+        let score = service
+            .calculate_trust_score(&Address::fragment("hub:8080", "frag-1"), &alice_addr)
+            .unwrap();
+        assert!((score.score - 0.9).abs() < 0.001);
+
+        let score = service
+            .calculate_trust_score(&Address::fragment("hub:8080", "frag-2"), &alice_addr)
+            .unwrap();
+        assert!((score.score - 0.2).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_trust_score_explanation() {
+        let (service, _dir) = setup_test_service();
+
+        let mut alice = create_test_agent(&service.store, "alice");
+        let mut bob = create_test_agent(&service.store, "bob");
+        let _carol = create_test_agent(&service.store, "carol");
+        let mut dave = create_test_agent(&service.store, "dave");
+        let _erin = create_test_agent(&service.store, "erin");
+        let addr = |uuid: &str| Address::agent("hub:8080", uuid);
+
+        alice.add_trust(addr("bob"), 0.9);
+        alice.add_trust(addr("dave"), 0.02);
+        service.store.put_agent(&alice).unwrap();
+        bob.add_trust(addr("carol"), 0.8);
+        service.store.put_agent(&bob).unwrap();
+        // 0.02 * 0.5 * 0.8 falls below min_trust_threshold
+        dave.add_trust(addr("erin"), 0.5);
+        service.store.put_agent(&dave).unwrap();
+
+        let fragment = Fragment::with_uuid("frag-1", "claim", addr("carol"));
+        service.store.put_fragment(&fragment).unwrap();
+        let fragment_addr = Address::fragment("hub:8080", "frag-1");
+        let endorsement = Relation::self_reference(fragment_addr.clone(), addr("bob"), RelationType::Trust)
+            .with_confidence(0.5)
+            .with_signature("sig");
+        service.store.put_relation(&endorsement).unwrap();
+
+        let options = TrustQueryOptions {
+            explain_top_k: Some(5),
+            include_endorsements: true,
+            ..Default::default()
+        };
+        let score = service.calculate_trust_score_with(&fragment_addr, &addr("alice"), &options).unwrap();
+        assert!((score.score - 0.576).abs() < 0.001);
+        assert_eq!(score.path_count, 2);
+
+        let explanation = score.explanation.unwrap();
+        assert_eq!(explanation.target, addr("carol"));
+        assert_eq!(explanation.paths.len(), 2);
+
+        let best = &explanation.paths[0];
+        assert_eq!(best.hops[0].damping, 1.0);
+        assert_eq!(best.hops[1].damping, 0.8);
+        assert!((best.hops[1].cumulative_trust - 0.576).abs() < 0.001);
+
+        // Bob's endorsement: 0.9 * 0.5 * 0.8
+        let endorsed = &explanation.paths[1];
+        assert_eq!(endorsed.endorsement.as_deref(), Some(endorsement.uuid.as_str()));
+        assert_eq!(endorsed.hops.last().unwrap().agent, fragment_addr);
+        assert!((explanation.endorsements[0].contribution - 0.36).abs() < 0.001);
+
+        assert!(explanation.cut_edges.iter().any(|e| e.from == addr("dave") && e.to == addr("erin")));
+
+        // Without explain mode no explanation is attached, and plain scores
+        // leave endorsements out
+        let plain = service.calculate_trust_score(&fragment_addr, &addr("alice")).unwrap();
+        assert!(plain.explanation.is_none());
+        assert!((plain.score - score.score).abs() < f32::EPSILON);
+        assert_eq!(plain.path_count, 1);
+    }
+
+    #[test]
+    fn test_decay_weights() {
+        let exponential = TrustDecay::Exponential { half_life_days: 30.0 };
+        assert!((exponential.weight(Duration::days(0)) - 1.0).abs() < 0.001);
+        assert!((exponential.weight(Duration::days(30)) - 0.5).abs() < 0.001);
+        assert!((exponential.weight(Duration::days(60)) - 0.25).abs() < 0.001);
+
+        let step = TrustDecay::Step { interval_days: 30, factor: 0.5 };
+        assert_eq!(step.weight(Duration::days(29)), 1.0);
+        assert_eq!(step.weight(Duration::days(30)), 0.5);
+        assert_eq!(step.weight(Duration::days(65)), 0.25);
+    }
+
+    #[test]
+    fn test_decayed_and_expired_edges() {
+        let dir = tempdir().unwrap();
+        let rocks = RocksStore::open(dir.path()).unwrap();
+        let store = Arc::new(EntityStore::new(rocks));
+        let config = TrustConfig {
+            decay: TrustDecay::Exponential { half_life_days: 30.0 },
+            max_age: Some(Duration::days(90)),
+            stale_after: Duration::days(45),
+            ..Default::default()
+        };
+        let service = TrustService::new(store, config);
+
+        let mut alice = create_test_agent(&service.store, "alice");
+        let _bob = create_test_agent(&service.store, "bob");
+        let _carol = create_test_agent(&service.store, "carol");
+        let alice_addr = Address::agent("hub:8080", "alice");
+        let bob_addr = Address::agent("hub:8080", "bob");
+        let carol_addr = Address::agent("hub:8080", "carol");
+
+        let now = Utc::now();
+        alice.set_trust_at(bob_addr.clone(), 0.8, None, now - Duration::days(60));
+        alice.set_trust_at(carol_addr.clone(), 0.9, None, now - Duration::days(120));
+        service.store.put_agent(&alice).unwrap();
+
+        // Two half-lives: 0.8 * 0.25
+        let path = service.find_best_path(&alice_addr, &bob_addr).unwrap().unwrap();
+        assert!((path.effective_trust - 0.2).abs() < 0.01);
+
+        // Older than max_age
+        assert!(service.find_best_path(&alice_addr, &carol_addr).unwrap().is_none());
+
+        let report = service.stale_trust_report("alice").unwrap();
+        assert_eq!(report.total, 2);
+        assert_eq!(report.stale.len(), 2);
+        assert_eq!(report.stale[0].agent, carol_addr);
+        assert!(report.stale[0].expired);
+        assert!(!report.stale[1].expired);
+    }
+
+    #[test]
+    fn test_trust_graph_reputation() {
+        let (service, _dir) = setup_test_service();
+
+        let mut alice = create_test_agent(&service.store, "alice");
+        let mut bob = create_test_agent(&service.store, "bob");
+        let _carol = create_test_agent(&service.store, "carol");
+        let alice_addr = Address::agent("hub:8080", "alice");
+        let bob_addr = Address::agent("hub:8080", "bob");
+        let carol_addr = Address::agent("hub:8080", "carol");
+
+        alice.add_trust(bob_addr.clone(), 0.8);
+        alice.add_trust(carol_addr.clone(), -0.4);
+        bob.add_trust(carol_addr.clone(), 0.6);
+        bob.add_trust(Address::agent("hub:8080", "dave"), 0.9);
+        service.store.put_agent(&alice).unwrap();
+        service.store.put_agent(&bob).unwrap();
+
+        let graph = service.build_trust_graph(&alice_addr, 2).unwrap();
+        assert_eq!(graph.nodes.len(), 3);
+        // Edge to the unknown agent dave is dropped
+        assert_eq!(graph.edges.len(), 3);
+        assert!(graph.nodes["alice"].reputation.is_none());
+        assert!((graph.nodes["carol"].reputation.unwrap() - 0.1).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_paths_through_suspected_sybils_downweighted() {
+        let (service, _dir) = setup_test_service();
+        let alice_addr = Address::agent("hub:8080", "alice");
+        let target_addr = Address::agent("hub:8080", "target");
+
+        let mut alice = create_test_agent(&service.store, "alice");
+        let mut sybil = create_test_agent(&service.store, "sybil");
+        let _target = create_test_agent(&service.store, "target");
+        alice.created_at = Utc::now() - Duration::days(100);
+        alice.add_trust(Address::agent("hub:8080", "sybil"), 1.0);
+        sybil.add_trust(target_addr.clone(), 1.0);
+        service.store.put_agent(&alice).unwrap();
+        service.store.put_agent(&sybil).unwrap();
+
+        let before = service.find_best_path(&alice_addr, &target_addr).unwrap().unwrap();
+
+        // Only alice is established; the mass she passes on moves past the
+        // intermediate agent, which ends up suspected
+        let report = service.refresh_sybil_analysis().unwrap();
+        assert_eq!(report.suspicion("sybil", true), 1.0);
+
+        let after = service.find_best_path(&alice_addr, &target_addr).unwrap().unwrap();
+        assert!((after.effective_trust - before.effective_trust * 0.5).abs() < 0.001);
+    }
+
+    #[tokio::test]
+    async fn test_cross_hub_path() {
+        let dir = tempdir().unwrap();
+        let store = Arc::new(EntityStore::new(RocksStore::open(dir.path()).unwrap()));
+        let config = TrustConfig {
+            remote: RemoteTrustConfig {
+                max_fetches_per_query: 1,
+                timeout: std::time::Duration::from_millis(200),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let service = TrustService::new(store, config).with_local_server("hub:8080");
+
+        let mut alice = create_test_agent(&service.store, "alice");
+        let _carol = create_test_agent(&service.store, "carol");
+        let bob_addr = Address::agent("127.0.0.1:1", "bob");
+        let dave_addr = Address::agent("127.0.0.1:1", "dave");
+        alice.add_trust(bob_addr.clone(), 0.9);
+        alice.add_trust(dave_addr.clone(), 0.9);
+        service.store.put_agent(&alice).unwrap();
+
+        // Bob lives on another hub and has already been fetched
+        let mut bob = Agent::new("bob", "key");
+        bob.add_trust(Address::agent("hub:8080", "carol"), 0.8);
+        service.remote().unwrap().insert(&bob_addr, Some(bob));
+
+        let alice_addr = Address::agent("hub:8080", "alice");
+        let carol_addr = Address::agent("hub:8080", "carol");
+        let path = service
+            .find_best_path_federated(&alice_addr, &carol_addr, &TrustQueryOptions::default())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(path.depth, 2);
+        assert_eq!(path.hops[0].hub.as_deref(), Some("hub:8080"));
+        assert_eq!(path.hops[1].hub.as_deref(), Some("127.0.0.1:1"));
+
+        // Dave's hub is not registered: the single allowed fetch fails and is cached
+        assert!(matches!(service.remote().unwrap().cached(&dave_addr), Some(None)));
+
+        // Without fetching, only local and cached agents are followed
+        let local_only = service.find_best_path(&alice_addr, &carol_addr).unwrap().unwrap();
+        assert_eq!(local_only.depth, 2);
+    }
 }
@@ -1,36 +1,48 @@
 //! Validity service for checking reasoning chain integrity
 
-use std::collections::HashSet;
-use uuid::Uuid;
-
-use crate::models::{Address, Relation, RelationType};
-
-/// The validity status of a reasoning chain
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum ChainValidity {
-    /// All premises exist and are not contested
-    Valid,
-    /// Depends on unverified premises
-    Conditional,
-    /// At least one element is contested
-    Contested,
-    /// A premise is missing or was revoked
-    Broken,
-}
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
 
-impl std::fmt::Display for ChainValidity {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            ChainValidity::Valid => write!(f, "valid"),
-            ChainValidity::Conditional => write!(f, "conditional"),
-            ChainValidity::Contested => write!(f, "contested"),
-            ChainValidity::Broken => write!(f, "broken"),
-        }
-    }
-}
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+use crate::models::{
+    default_confidence, Address, CachedValidity, ChainValidity, Domain, EvidenceType, Fragment, HubError,
+    HubResult, QuarantineChange, Relation, RelationType,
+};
+use crate::store::EntityStore;
+use super::TrustService;
+
+/// Maximum DERIVED_FROM depth followed from the analyzed fragment
+const MAX_CHAIN_DEPTH: usize = 64;
+
+/// Maximum number of fragments analyzed per report
+const MAX_CHAIN_FRAGMENTS: usize = 1000;
+
+/// Maximum number of validity change events kept for polling, per agent
+const MAX_RECENT_EVENTS: usize = 100;
+
+/// Capacity of the validity change broadcast channel
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// Age in days at which a relation counts half towards a dispute's activity
+const DISPUTE_ACTIVITY_HALF_LIFE_DAYS: f32 = 7.0;
+
+/// Maximum number of CONTRADICTS relations examined when looking for disputes
+const MAX_DISPUTE_CONTRADICTIONS: usize = 10_000;
+
+/// Maximum number of dispute clusters examined per search
+const MAX_DISPUTE_CLUSTERS: usize = 500;
+
+/// Maximum number of fragments of a dispute cluster examined
+const MAX_DISPUTE_CLUSTER_FRAGMENTS: usize = 100;
 
 /// Type of validity issue found
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum IssueType {
     /// A DERIVED_FROM reference points to a non-existent fragment
     MissingReference,
@@ -57,7 +69,7 @@ impl std::fmt::Display for IssueType {
 }
 
 /// A specific validity issue
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ValidityIssue {
     /// The fragment with the issue
     pub fragment_id: String,
@@ -87,7 +99,7 @@ impl ValidityIssue {
 }
 
 /// Complete validity report for a reasoning chain
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ValidityReport {
     /// Overall validity status
     pub validity: ChainValidity,
@@ -159,7 +171,7 @@ impl Default for ValidityReport {
 }
 
 /// Evidence balance for a thesis
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct EvidenceBalance {
     /// The thesis fragment ID
     pub thesis_id: String,
@@ -173,6 +185,35 @@ pub struct EvidenceBalance {
     pub contradict_score: f32,
     /// Net score (support - contradict)
     pub net_score: f32,
+    /// Agent whose trust weighted the evidence (None for raw confidences)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub viewer: Option<Address>,
+    /// How each relation was weighted (only for trust-weighted balances)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub breakdown: Vec<WeightedEvidence>,
+}
+
+/// How a single SUPPORTS or CONTRADICTS relation was weighted
+#[derive(Debug, Clone, Serialize)]
+pub struct WeightedEvidence {
+    /// Relation UUID
+    pub relation: String,
+    /// Fragment the relation comes from
+    pub fragment_id: String,
+    /// Whether the fragment supports the thesis (false = contradicts)
+    pub supports: bool,
+    /// Agent asserting the relation (`by`, else its creator)
+    pub creator: Address,
+    /// Viewer's trust in that agent (-1.0 to 1.0)
+    pub creator_trust: f32,
+    /// Confidence of the relation
+    pub relation_confidence: f32,
+    /// Confidence of the source fragment (the default if it is not stored here)
+    pub fragment_confidence: f32,
+    /// Evidence type of the source fragment
+    pub evidence_type: EvidenceType,
+    /// Resulting weight: max(trust, 0) * relation confidence * fragment confidence * evidence type weight
+    pub weight: f32,
 }
 
 impl EvidenceBalance {
@@ -185,6 +226,8 @@ impl EvidenceBalance {
             support_score: 0.0,
             contradict_score: 0.0,
             net_score: 0.0,
+            viewer: None,
+            breakdown: Vec::new(),
         }
     }
 
@@ -203,12 +246,128 @@ impl EvidenceBalance {
     }
 }
 
+/// Fragments connected by CONTRADICTS relations
+#[derive(Debug, Clone, Serialize)]
+pub struct DisputeCluster {
+    /// Fragment IDs in the cluster, sorted
+    pub fragments: Vec<String>,
+    /// CONTRADICTS relations between the fragments
+    pub contradictions: Vec<String>,
+    /// SUPPORTS and CONTRADICTS relations targeting the fragments
+    pub relation_count: usize,
+    /// Distinct agents who created the fragments or relations
+    pub participants: usize,
+    /// Most recent relation targeting the fragments
+    pub last_activity: DateTime<Utc>,
+    /// Relation count weighted by recency (halved every week)
+    pub activity: f32,
+    /// Net evidence (support - contradiction) per stored fragment, highest first
+    pub net_evidence: Vec<(String, f32)>,
+    /// Difference between the highest and lowest net evidence
+    pub trust_spread: f32,
+    /// Ranking score: activity * (1 + trust spread)
+    pub score: f32,
+}
+
+/// A change that may affect the validity of stored reasoning chains
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ValidityTrigger {
+    /// A fragment was stored (it may be a premise others were missing)
+    FragmentCreated { fragment: String },
+    /// A fragment was deleted
+    FragmentDeleted { fragment: String },
+    /// A relation was stored
+    RelationCreated {
+        relation: String,
+        relation_type: RelationType,
+        from: String,
+        to: String,
+    },
+    /// A relation was quarantined or released from quarantine
+    QuarantineChanged {
+        relation: String,
+        relation_type: RelationType,
+        from: String,
+        to: String,
+        quarantined: bool,
+    },
+}
+
+impl ValidityTrigger {
+    /// Trigger for a newly stored relation
+    pub fn relation_created(relation: &Relation) -> Self {
+        ValidityTrigger::RelationCreated {
+            relation: relation.uuid.clone(),
+            relation_type: relation.relation_type,
+            from: relation.from.entity.clone(),
+            to: relation.to.entity.clone(),
+        }
+    }
+
+    /// Trigger for a relation whose quarantine was set or lifted
+    pub fn quarantine_changed(relation: &Relation) -> Self {
+        ValidityTrigger::QuarantineChanged {
+            relation: relation.uuid.clone(),
+            relation_type: relation.relation_type,
+            from: relation.from.entity.clone(),
+            to: relation.to.entity.clone(),
+            quarantined: relation.is_quarantined(),
+        }
+    }
+}
+
+/// Emitted when the cached validity of a fragment changes status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidityChangeEvent {
+    /// The fragment whose validity changed
+    pub fragment_id: String,
+    /// Author of the fragment, who should be notified
+    pub creator: Address,
+    /// Previous status (None if it was never computed)
+    pub previous: Option<ChainValidity>,
+    /// New status
+    pub current: ChainValidity,
+    /// What caused the recomputation
+    pub cause: ValidityTrigger,
+    /// When the change was detected
+    pub occurred_at: DateTime<Utc>,
+}
+
 /// Service for checking validity of reasoning chains
 pub struct ValidityService {
     /// Minimum confidence threshold for "low confidence" warning
     pub min_confidence_threshold: f32,
     /// Minimum trust score for "unverified source" warning
     pub min_trust_threshold: f32,
+    /// Store and trust service used to analyze stored chains
+    backend: Option<(Arc<EntityStore>, Arc<TrustService>)>,
+    /// Live validity change events
+    events: broadcast::Sender<ValidityChangeEvent>,
+    /// Most recent validity change events, oldest first, if there is no store
+    /// to keep them in
+    recent_events: RwLock<VecDeque<ValidityChangeEvent>>,
+}
+
+/// A dispute cluster before its evidence is weighted
+struct DisputeCandidate<'a> {
+    members: Vec<&'a str>,
+    fragments: Vec<Fragment>,
+    /// Unquarantined SUPPORTS and CONTRADICTS relations targeting each member
+    evidence: HashMap<String, Vec<Relation>>,
+    activity: f32,
+}
+
+/// State of a single chain analysis
+struct ChainWalk {
+    report: ValidityReport,
+    viewer: Address,
+    /// Fragments already analyzed
+    visited: HashSet<String>,
+    /// Current DERIVED_FROM path, for cycle detection
+    path: Vec<String>,
+    /// Viewer's trust in each creator seen so far
+    trust_scores: HashMap<String, f32>,
 }
 
 impl ValidityService {
@@ -217,6 +376,9 @@ impl ValidityService {
         Self {
             min_confidence_threshold: 0.3,
             min_trust_threshold: 0.3,
+            backend: None,
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            recent_events: RwLock::new(VecDeque::new()),
         }
     }
 
@@ -225,9 +387,150 @@ impl ValidityService {
         Self {
             min_confidence_threshold: min_confidence,
             min_trust_threshold: min_trust,
+            backend: None,
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            recent_events: RwLock::new(VecDeque::new()),
         }
     }
 
+    /// Analyze chains stored in `store`, scoring sources with `trust_service`
+    pub fn with_store(mut self, store: Arc<EntityStore>, trust_service: Arc<TrustService>) -> Self {
+        self.backend = Some((store, trust_service));
+        self
+    }
+
+    fn backend(&self) -> HubResult<(&EntityStore, &TrustService)> {
+        self.backend
+            .as_ref()
+            .map(|(store, trust)| (store.as_ref(), trust.as_ref()))
+            .ok_or_else(|| HubError::Internal("validity service has no store".to_string()))
+    }
+
+    /// Analyze the reasoning chain of a stored fragment.
+    ///
+    /// Walks DERIVED_FROM relations from the fragment and reports missing
+    /// premises, contradicted premises, low-confidence fragments, sources the
+    /// viewer does not trust and circular derivations. Without a viewer, the
+    /// fragment's creator is used.
+    pub fn analyze_fragment(&self, uuid: &str, viewer: Option<&Address>) -> HubResult<ValidityReport> {
+        let (store, _) = self.backend()?;
+        let root = store
+            .get_fragment(uuid)?
+            .ok_or_else(|| HubError::not_found("fragment", uuid))?;
+
+        let mut walk = ChainWalk {
+            report: ValidityReport::new(),
+            viewer: viewer.cloned().unwrap_or_else(|| root.creator.clone()),
+            visited: HashSet::from([root.uuid.clone()]),
+            path: Vec::new(),
+            trust_scores: HashMap::new(),
+        };
+        self.visit_fragment(&root, 0, &mut walk)?;
+        Ok(walk.report)
+    }
+
+    fn visit_fragment(&self, fragment: &Fragment, depth: usize, walk: &mut ChainWalk) -> HubResult<()> {
+        let (store, _) = self.backend()?;
+        walk.report.fragments_analyzed += 1;
+        self.check_fragment(fragment, depth, walk)?;
+
+        if depth >= MAX_CHAIN_DEPTH {
+            return Ok(());
+        }
+
+        walk.path.push(fragment.uuid.clone());
+        let sources: Vec<Relation> = store
+            .get_relations_by_from(&fragment.uuid)?
+            .into_iter()
+            .filter(|r| r.relation_type == RelationType::DerivedFrom)
+            .collect();
+
+        for relation in sources {
+            walk.report.relations_analyzed += 1;
+            let source_id = &relation.to.entity;
+
+            if let Some(pos) = walk.path.iter().position(|id| id == source_id) {
+                let mut cycle = walk.path[pos..].to_vec();
+                cycle.push(source_id.clone());
+                walk.report.add_issue(ValidityIssue::new(
+                    &fragment.uuid,
+                    IssueType::CircularDependency,
+                    format!("Circular derivation: {}", cycle.join(" -> ")),
+                    1.0,
+                ));
+                continue;
+            }
+            if walk.report.fragments_analyzed >= MAX_CHAIN_FRAGMENTS || !walk.visited.insert(source_id.clone()) {
+                continue;
+            }
+
+            match store.get_fragment(source_id)? {
+                Some(premise) => self.visit_fragment(&premise, depth + 1, walk)?,
+                None => walk.report.add_issue(ValidityIssue::new(
+                    &fragment.uuid,
+                    IssueType::MissingReference,
+                    format!("Derives from missing fragment {}", source_id),
+                    1.0,
+                )),
+            }
+        }
+        walk.path.pop();
+
+        Ok(())
+    }
+
+    /// Check a single fragment of the chain (premises are fragments at depth > 0)
+    fn check_fragment(&self, fragment: &Fragment, depth: usize, walk: &mut ChainWalk) -> HubResult<()> {
+        let (store, trust_service) = self.backend()?;
+
+        if depth > 0 {
+            let contradictions: Vec<Relation> = store
+                .get_relations_by_to(&fragment.uuid)?
+                .into_iter()
+                .filter(|r| r.relation_type == RelationType::Contradicts && !r.is_quarantined())
+                .collect();
+            walk.report.relations_analyzed += contradictions.len();
+            if !contradictions.is_empty() {
+                let strongest = contradictions.iter().map(|r| r.confidence).fold(0.0, f32::max);
+                walk.report.add_issue(ValidityIssue::new(
+                    &fragment.uuid,
+                    IssueType::ContestedPremise,
+                    format!("Premise is contradicted by {} relation(s)", contradictions.len()),
+                    strongest,
+                ));
+            }
+        }
+
+        if fragment.confidence < self.min_confidence_threshold {
+            walk.report.add_issue(ValidityIssue::new(
+                &fragment.uuid,
+                IssueType::LowConfidence,
+                format!("Creator confidence is {:.2}", fragment.confidence),
+                1.0 - fragment.confidence,
+            ));
+        }
+
+        let creator = &fragment.creator;
+        let score = match walk.trust_scores.get(&creator.entity) {
+            Some(score) => *score,
+            None => {
+                let score = trust_service.calculate_trust_score(creator, &walk.viewer)?.score;
+                walk.trust_scores.insert(creator.entity.clone(), score);
+                score
+            }
+        };
+        if score < self.min_trust_threshold {
+            walk.report.add_issue(ValidityIssue::new(
+                &fragment.uuid,
+                IssueType::UnverifiedSource,
+                format!("Viewer's trust in creator {} is {:.2}", creator, score),
+                1.0 - score.max(0.0),
+            ));
+        }
+
+        Ok(())
+    }
+
     /// Analyze DERIVED_FROM relations for a fragment
     /// Returns the IDs of all fragments this one derives from
     pub fn get_derivation_sources(
@@ -295,6 +598,420 @@ impl ValidityService {
         balance
     }
 
+    /// Calculate evidence balance for a stored thesis, weighted by the viewer's
+    /// trust in the agent asserting each relation and by the source fragment's
+    /// confidence and evidence type. Distrusted agents contribute nothing.
+    pub fn weighted_evidence_balance(&self, thesis_id: &str, viewer: &Address) -> HubResult<EvidenceBalance> {
+        let (store, trust_service) = self.backend()?;
+        if store.get_fragment(thesis_id)?.is_none() {
+            return Err(HubError::not_found("fragment", thesis_id));
+        }
+
+        let mut balance = EvidenceBalance::new(thesis_id);
+        balance.viewer = Some(viewer.clone());
+        let mut trust_scores: HashMap<String, f32> = HashMap::new();
+
+        for relation in store.get_relations_by_to(thesis_id)? {
+            if relation.is_quarantined() {
+                continue;
+            }
+            let supports = match relation.relation_type {
+                RelationType::Supports => true,
+                RelationType::Contradicts => false,
+                _ => continue,
+            };
+
+            let asserter = relation.asserter().clone();
+            let creator_trust = match trust_scores.get(&asserter.entity) {
+                Some(score) => *score,
+                None => {
+                    let score = trust_service.calculate_trust_score(&asserter, viewer)?.score;
+                    trust_scores.insert(asserter.entity.clone(), score);
+                    score
+                }
+            };
+
+            let (fragment_confidence, evidence_type) = match store.get_fragment(&relation.from.entity)? {
+                Some(source) if source.is_quarantined() => continue,
+                Some(source) => (source.confidence, source.evidence_type),
+                None => (default_confidence(), EvidenceType::Unknown),
+            };
+
+            let weight = creator_trust.max(0.0)
+                * relation.confidence
+                * fragment_confidence
+                * evidence_type.weight();
+
+            if supports {
+                balance.add_support(&relation.from.entity, weight);
+            } else {
+                balance.add_contradiction(&relation.from.entity, weight);
+            }
+            balance.breakdown.push(WeightedEvidence {
+                relation: relation.uuid,
+                fragment_id: relation.from.entity,
+                supports,
+                creator: asserter,
+                creator_trust,
+                relation_confidence: relation.confidence,
+                fragment_confidence,
+                evidence_type,
+                weight,
+            });
+        }
+
+        Ok(balance)
+    }
+
+    /// Recompute the cached validity of every fragment affected by a change.
+    ///
+    /// Starts at the changed fragment (for new fragments and DERIVED_FROM
+    /// relations) or at its dependents (for deletions and CONTRADICTS
+    /// relations, which only matter to chains using the fragment as a
+    /// premise) and walks DERIVED_FROM relations backwards. All transitive
+    /// dependents are re-analyzed, since a premise can make a chain contested
+    /// without changing the status of the fragments in between. Returns the
+    /// events for fragments whose status changed.
+    pub fn propagate(&self, trigger: &ValidityTrigger) -> HubResult<Vec<ValidityChangeEvent>> {
+        let (store, _) = self.backend()?;
+
+        let mut queue = VecDeque::new();
+        match trigger {
+            ValidityTrigger::FragmentCreated { fragment } => queue.push_back(fragment.clone()),
+            ValidityTrigger::FragmentDeleted { fragment } => queue.extend(self.dependents(fragment)?),
+            ValidityTrigger::RelationCreated { relation_type, from, to, .. }
+            | ValidityTrigger::QuarantineChanged { relation_type, from, to, .. } => match relation_type {
+                RelationType::DerivedFrom => queue.push_back(from.clone()),
+                RelationType::Contradicts => queue.extend(self.dependents(to)?),
+                _ => {}
+            },
+        }
+
+        let mut visited = HashSet::new();
+        let mut events = Vec::new();
+        while let Some(uuid) = queue.pop_front() {
+            if visited.len() >= MAX_CHAIN_FRAGMENTS {
+                warn!("Validity propagation for {:?} stopped after {} fragments", trigger, visited.len());
+                break;
+            }
+            if !visited.insert(uuid.clone()) {
+                continue;
+            }
+            let fragment = match store.get_fragment(&uuid)? {
+                Some(fragment) => fragment,
+                None => continue,
+            };
+
+            if let Some(event) = self.refresh_cached_validity(fragment, trigger)? {
+                events.push(event);
+            }
+            queue.extend(self.dependents(&uuid)?);
+        }
+
+        for event in &events {
+            self.publish(event.clone())?;
+        }
+        Ok(events)
+    }
+
+    /// Run [`propagate`](Self::propagate) on a blocking thread, logging failures
+    pub fn propagate_in_background(self: &Arc<Self>, trigger: ValidityTrigger) {
+        let service = Arc::clone(self);
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = service.propagate(&trigger) {
+                warn!("Validity propagation for {:?} failed: {}", trigger, e);
+            }
+        });
+    }
+
+    /// Propagate the quarantine changes of relations, which count as
+    /// evidence only while not quarantined
+    pub fn propagate_quarantine(&self, change: &QuarantineChange) -> HubResult<Vec<ValidityChangeEvent>> {
+        let (store, _) = self.backend()?;
+        let mut events = Vec::new();
+        for uuid in &change.relations {
+            if let Some(relation) = store.get_relation(uuid)? {
+                events.extend(self.propagate(&ValidityTrigger::quarantine_changed(&relation))?);
+            }
+        }
+        Ok(events)
+    }
+
+    /// Run [`propagate_quarantine`](Self::propagate_quarantine) on a
+    /// blocking thread, logging failures
+    pub fn propagate_quarantine_in_background(self: &Arc<Self>, change: QuarantineChange) {
+        if change.relations.is_empty() {
+            return;
+        }
+        let service = Arc::clone(self);
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = service.propagate_quarantine(&change) {
+                warn!("Validity propagation for quarantine change failed: {}", e);
+            }
+        });
+    }
+
+    /// Subscribe to live validity change events
+    pub fn subscribe(&self) -> broadcast::Receiver<ValidityChangeEvent> {
+        self.events.subscribe()
+    }
+
+    /// Recent validity change events for fragments created by an agent, newest first
+    pub fn recent_events_for(&self, creator_uuid: &str, limit: usize) -> HubResult<Vec<ValidityChangeEvent>> {
+        if let Some((store, _)) = &self.backend {
+            return store.get_validity_events(creator_uuid, limit.min(MAX_RECENT_EVENTS));
+        }
+        Ok(self.recent_events
+            .read()
+            .iter()
+            .rev()
+            .filter(|e| e.creator.entity == creator_uuid)
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    /// Fragments with a DERIVED_FROM relation to `uuid`
+    fn dependents(&self, uuid: &str) -> HubResult<Vec<String>> {
+        let (store, _) = self.backend()?;
+        Ok(store
+            .get_relations_by_to(uuid)?
+            .into_iter()
+            .filter(|r| r.relation_type == RelationType::DerivedFrom)
+            .map(|r| r.from.entity)
+            .collect())
+    }
+
+    /// Re-analyze a fragment and store the result; returns an event if the status changed
+    fn refresh_cached_validity(
+        &self,
+        mut fragment: Fragment,
+        trigger: &ValidityTrigger,
+    ) -> HubResult<Option<ValidityChangeEvent>> {
+        let (store, _) = self.backend()?;
+        let report = self.analyze_fragment(&fragment.uuid, None)?;
+        let cached = CachedValidity {
+            status: report.validity,
+            score: report.confidence_weighted_score,
+            issue_count: report.issues.len(),
+            computed_at: Utc::now(),
+        };
+
+        let previous = fragment.validity.take();
+        let unchanged = previous.as_ref().is_some_and(|p| {
+            p.status == cached.status && p.score == cached.score && p.issue_count == cached.issue_count
+        });
+        let previous_status = previous.map(|p| p.status);
+        let current = cached.status.clone();
+        if unchanged {
+            return Ok(None);
+        }
+
+        store.update_fragment(&fragment.uuid, |stored| stored.validity = Some(cached))?;
+        debug!("Cached validity of fragment {} is now {}", fragment.uuid, current);
+
+        // Fragments without a cached status were treated as valid
+        if previous_status.as_ref().unwrap_or(&ChainValidity::Valid) == &current {
+            return Ok(None);
+        }
+        Ok(Some(ValidityChangeEvent {
+            fragment_id: fragment.uuid,
+            creator: fragment.creator,
+            previous: previous_status,
+            current,
+            cause: trigger.clone(),
+            occurred_at: Utc::now(),
+        }))
+    }
+
+    /// Keep an event for polling (in the store, so it survives restarts)
+    /// and send it to live subscribers
+    fn publish(&self, event: ValidityChangeEvent) -> HubResult<()> {
+        match &self.backend {
+            Some((store, _)) => store.put_validity_event(
+                &event.creator.entity,
+                event.occurred_at,
+                &event.fragment_id,
+                &event,
+                MAX_RECENT_EVENTS,
+            )?,
+            None => {
+                let mut recent = self.recent_events.write();
+                if recent.len() >= MAX_RECENT_EVENTS {
+                    recent.pop_front();
+                }
+                recent.push_back(event.clone());
+            }
+        }
+        // No live subscribers is fine; the event is still kept for polling
+        let _ = self.events.send(event);
+        Ok(())
+    }
+
+    /// Group fragments connected by CONTRADICTS relations into dispute clusters.
+    ///
+    /// Clusters are ranked by recency-weighted activity; the `limit` most
+    /// active are then ordered by their score, which also counts how far apart
+    /// the net evidence of their fragments is. With a viewer, evidence is
+    /// weighted by the viewer's trust (see [`weighted_evidence_balance`](Self::weighted_evidence_balance)),
+    /// otherwise raw relation confidences are used. With a tag (UUID or name),
+    /// only clusters containing a fragment with that tag are returned.
+    ///
+    /// The numbers of contradictions, clusters and fragments per cluster
+    /// examined are capped (see `MAX_DISPUTE_*`).
+    pub fn find_disputes(
+        &self,
+        tag: Option<&str>,
+        viewer: Option<&Address>,
+        limit: usize,
+    ) -> HubResult<Vec<DisputeCluster>> {
+        let (store, _) = self.backend()?;
+        let tag_uuid = match tag {
+            Some(tag) => Some(self.resolve_tag(tag)?),
+            None => None,
+        };
+
+        let contradictions: Vec<Relation> = store
+            .get_relations_by_type_limited(RelationType::Contradicts, MAX_DISPUTE_CONTRADICTIONS)?
+            .into_iter()
+            .filter(Self::is_dispute_contradiction)
+            .collect();
+
+        // Connected components over CONTRADICTS edges, ignoring direction
+        let mut neighbors: HashMap<&str, Vec<&str>> = HashMap::new();
+        for relation in &contradictions {
+            neighbors.entry(&relation.from.entity).or_default().push(&relation.to.entity);
+            neighbors.entry(&relation.to.entity).or_default().push(&relation.from.entity);
+        }
+        let mut starts: Vec<&str> = neighbors.keys().copied().collect();
+        starts.sort_unstable();
+
+        let now = Utc::now();
+        let mut seen: HashSet<&str> = HashSet::new();
+        let mut candidates = Vec::new();
+        for start in starts {
+            if candidates.len() >= MAX_DISPUTE_CLUSTERS {
+                warn!("Dispute search stopped after {} clusters", candidates.len());
+                break;
+            }
+            if !seen.insert(start) {
+                continue;
+            }
+            let mut members = vec![start];
+            let mut queue = VecDeque::from([start]);
+            while let Some(id) = queue.pop_front() {
+                for &next in &neighbors[id] {
+                    if members.len() < MAX_DISPUTE_CLUSTER_FRAGMENTS && seen.insert(next) {
+                        members.push(next);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            members.sort_unstable();
+
+            let fragments: Vec<Fragment> = members
+                .iter()
+                .filter_map(|id| store.get_fragment(id).transpose())
+                .collect::<HubResult<_>>()?;
+            if let Some(ref tag_uuid) = tag_uuid {
+                if !fragments.iter().any(|f| f.has_tag(tag_uuid)) {
+                    continue;
+                }
+            }
+
+            let mut evidence: HashMap<String, Vec<Relation>> = HashMap::new();
+            for id in &members {
+                let targeting: Vec<Relation> = store
+                    .get_relations_by_to(id)?
+                    .into_iter()
+                    .filter(|r| {
+                        Self::is_dispute_contradiction(r)
+                            || (r.relation_type == RelationType::Supports && !r.is_quarantined())
+                    })
+                    .collect();
+                evidence.insert(id.to_string(), targeting);
+            }
+            let activity: f32 = evidence
+                .values()
+                .flatten()
+                .map(|r| {
+                    let age_days = (now - r.created_at).num_seconds().max(0) as f32 / 86_400.0;
+                    0.5f32.powf(age_days / DISPUTE_ACTIVITY_HALF_LIFE_DAYS)
+                })
+                .sum();
+            candidates.push(DisputeCandidate { members, fragments, evidence, activity });
+        }
+
+        candidates.sort_by(|a, b| b.activity.partial_cmp(&a.activity).unwrap_or(std::cmp::Ordering::Equal));
+        candidates.truncate(limit);
+
+        let mut clusters = Vec::with_capacity(candidates.len());
+        for DisputeCandidate { members, fragments, evidence, activity } in candidates {
+            let relations: Vec<&Relation> = evidence.values().flatten().collect();
+            let mut participants: HashSet<&str> = fragments.iter().map(|f| f.creator.entity.as_str()).collect();
+            participants.extend(relations.iter().map(|r| r.asserter().entity.as_str()));
+
+            let mut net_evidence = Vec::with_capacity(fragments.len());
+            for fragment in &fragments {
+                let net = match viewer {
+                    Some(viewer) => self.weighted_evidence_balance(&fragment.uuid, viewer)?.net_score,
+                    None => {
+                        let targeting = evidence.get(&fragment.uuid).map(Vec::as_slice).unwrap_or_default();
+                        self.calculate_evidence_balance(&fragment.uuid, targeting).net_score
+                    }
+                };
+                net_evidence.push((fragment.uuid.clone(), net));
+            }
+            net_evidence.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+            let trust_spread = match (net_evidence.first(), net_evidence.last()) {
+                (Some(high), Some(low)) => high.1 - low.1,
+                _ => 0.0,
+            };
+
+            let member_set: HashSet<&str> = members.iter().copied().collect();
+            let mut contradictions: Vec<String> = relations
+                .iter()
+                .filter(|r| r.relation_type == RelationType::Contradicts && member_set.contains(r.from.entity.as_str()))
+                .map(|r| r.uuid.clone())
+                .collect();
+            contradictions.sort_unstable();
+            clusters.push(DisputeCluster {
+                contradictions,
+                fragments: members.iter().map(|id| id.to_string()).collect(),
+                relation_count: relations.len(),
+                participants: participants.len(),
+                last_activity: relations.iter().map(|r| r.created_at).max().unwrap_or(now),
+                activity,
+                net_evidence,
+                trust_spread,
+                score: activity * (1.0 + trust_spread),
+            });
+        }
+
+        clusters.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(clusters)
+    }
+
+    /// An unquarantined CONTRADICTS relation between fragments
+    fn is_dispute_contradiction(relation: &Relation) -> bool {
+        relation.relation_type == RelationType::Contradicts
+            && relation.from.domain == Domain::Fragment
+            && relation.to.domain == Domain::Fragment
+            && !relation.is_quarantined()
+    }
+
+    /// UUID of a tag given by UUID or name
+    fn resolve_tag(&self, tag: &str) -> HubResult<String> {
+        let (store, _) = self.backend()?;
+        if store.get_tag(tag)?.is_some() {
+            return Ok(tag.to_string());
+        }
+        store
+            .find_tag_by_name(tag)?
+            .map(|t| t.uuid)
+            .ok_or_else(|| HubError::not_found("tag", tag))
+    }
+
     /// Check for circular dependencies in derivation chain
     pub fn check_circular_dependencies(
         &self,
@@ -348,7 +1065,10 @@ impl Default for ValidityService {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::Address;
+    use crate::models::{Agent, Quarantine, Tag, TagCategory};
+    use crate::services::TrustConfig;
+    use crate::store::RocksStore;
+    use tempfile::TempDir;
 
     fn create_test_relation(from: &str, to: &str, rel_type: RelationType) -> Relation {
         Relation::new(
@@ -455,4 +1175,256 @@ mod tests {
         ));
         assert_eq!(report.validity, ChainValidity::Broken);
     }
+
+    fn setup() -> (ValidityService, Arc<EntityStore>, TempDir) {
+        let dir = TempDir::new().unwrap();
+        let store = Arc::new(EntityStore::new(RocksStore::open(dir.path()).unwrap()));
+        let trust = Arc::new(TrustService::new(Arc::clone(&store), TrustConfig::default()));
+        let service = ValidityService::new().with_store(Arc::clone(&store), trust);
+        (service, store, dir)
+    }
+
+    fn agent(uuid: &str) -> Address {
+        Address::agent("hub:8080", uuid)
+    }
+
+    fn put_fragment(store: &EntityStore, uuid: &str, creator: &str) -> Fragment {
+        let fragment = Fragment::with_uuid(uuid, format!("claim {}", uuid), agent(creator));
+        store.put_fragment(&fragment).unwrap();
+        fragment
+    }
+
+    fn put_relation(store: &EntityStore, from: &str, to: &str, relation_type: RelationType) -> Relation {
+        let relation = Relation::new(
+            Address::fragment("hub:8080", from),
+            Address::fragment("hub:8080", to),
+            agent("alice"),
+            relation_type,
+        );
+        store.put_relation(&relation).unwrap();
+        relation
+    }
+
+    #[test]
+    fn test_valid_chain() {
+        let (service, store, _dir) = setup();
+        let mut alice = Agent::new("alice", "key");
+        alice.add_trust(agent("bob"), 0.9);
+        store.put_agent(&alice).unwrap();
+
+        put_fragment(&store, "conclusion", "alice");
+        put_fragment(&store, "premise", "bob");
+        put_relation(&store, "conclusion", "premise", RelationType::DerivedFrom);
+
+        let report = service.analyze_fragment("conclusion", None).unwrap();
+        assert_eq!(report.validity, ChainValidity::Valid);
+        assert_eq!(report.fragments_analyzed, 2);
+        assert!(report.issues.is_empty());
+    }
+
+    #[test]
+    fn test_chain_issues() {
+        let (service, store, _dir) = setup();
+        let mut alice = Agent::new("alice", "key");
+        alice.add_trust(agent("bob"), 0.9);
+        store.put_agent(&alice).unwrap();
+
+        put_fragment(&store, "conclusion", "alice");
+        put_fragment(&store, "contested", "bob");
+        put_fragment(&store, "stranger", "mallory");
+        let mut shaky = Fragment::with_uuid("shaky", "guess", agent("bob"));
+        shaky.confidence = 0.1;
+        store.put_fragment(&shaky).unwrap();
+        put_fragment(&store, "rebuttal", "carol");
+
+        put_relation(&store, "conclusion", "contested", RelationType::DerivedFrom);
+        put_relation(&store, "conclusion", "stranger", RelationType::DerivedFrom);
+        put_relation(&store, "conclusion", "shaky", RelationType::DerivedFrom);
+        put_relation(&store, "conclusion", "ghost", RelationType::DerivedFrom);
+        put_relation(&store, "rebuttal", "contested", RelationType::Contradicts);
+        put_relation(&store, "shaky", "conclusion", RelationType::DerivedFrom);
+
+        let report = service.analyze_fragment("conclusion", None).unwrap();
+        let has = |fragment: &str, issue: IssueType| {
+            report.issues.iter().any(|i| i.fragment_id == fragment && i.issue_type == issue)
+        };
+        assert!(has("conclusion", IssueType::MissingReference));
+        assert!(has("contested", IssueType::ContestedPremise));
+        assert!(has("stranger", IssueType::UnverifiedSource));
+        assert!(has("shaky", IssueType::LowConfidence));
+        assert!(has("shaky", IssueType::CircularDependency));
+        assert_eq!(report.validity, ChainValidity::Broken);
+
+        // From Mallory's perspective Bob is unknown as well
+        let report = service.analyze_fragment("conclusion", Some(&agent("mallory"))).unwrap();
+        assert!(report.issues.iter().any(|i| i.fragment_id == "contested" && i.issue_type == IssueType::UnverifiedSource));
+    }
+
+    #[test]
+    fn test_weighted_evidence_balance() {
+        let (service, store, _dir) = setup();
+        let mut alice = Agent::new("alice", "key");
+        alice.add_trust(agent("bob"), 0.9);
+        alice.add_trust(agent("mallory"), -0.5);
+        store.put_agent(&alice).unwrap();
+
+        put_fragment(&store, "thesis", "carol");
+        let measured = Fragment::with_uuid("measured", "benchmark", agent("bob"))
+            .with_confidence(0.8)
+            .with_evidence_type(EvidenceType::Empirical);
+        store.put_fragment(&measured).unwrap();
+        put_fragment(&store, "rebuttal", "mallory");
+
+        let relate = |from: &str, creator: &str, relation_type| {
+            let relation = Relation::new(
+                Address::fragment("hub:8080", from),
+                Address::fragment("hub:8080", "thesis"),
+                agent(creator),
+                relation_type,
+            );
+            store.put_relation(&relation).unwrap();
+        };
+        relate("measured", "bob", RelationType::Supports);
+        relate("rebuttal", "mallory", RelationType::Contradicts);
+
+        let balance = service.weighted_evidence_balance("thesis", &agent("alice")).unwrap();
+        assert_eq!(balance.breakdown.len(), 2);
+        // 0.9 trust * 1.0 relation * 0.8 confidence * 1.0 empirical
+        assert!((balance.support_score - 0.72).abs() < 0.001);
+        // Mallory is distrusted, so the contradiction carries no weight
+        assert_eq!(balance.contradict_score, 0.0);
+        assert!((balance.net_score - 0.72).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_missing_fragment() {
+        let (service, _store, _dir) = setup();
+        assert!(matches!(
+            service.analyze_fragment("nope", None),
+            Err(HubError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_validity_propagation() {
+        let (service, store, _dir) = setup();
+        let mut alice = Agent::new("alice", "key");
+        alice.add_trust(agent("bob"), 0.9);
+        store.put_agent(&alice).unwrap();
+        let mut events = service.subscribe();
+
+        put_fragment(&store, "premise", "bob");
+        put_fragment(&store, "conclusion", "alice");
+        put_fragment(&store, "corollary", "alice");
+        put_relation(&store, "corollary", "conclusion", RelationType::DerivedFrom);
+        let derivation = put_relation(&store, "conclusion", "premise", RelationType::DerivedFrom);
+
+        // Caching a valid status is not a change worth notifying
+        let changes = service.propagate(&ValidityTrigger::relation_created(&derivation)).unwrap();
+        assert!(changes.is_empty());
+        let cached = store.get_fragment("corollary").unwrap().unwrap().validity.unwrap();
+        assert_eq!(cached.status, ChainValidity::Valid);
+
+        put_fragment(&store, "rebuttal", "carol");
+        let rebuttal = put_relation(&store, "rebuttal", "premise", RelationType::Contradicts);
+        let changes = service.propagate(&ValidityTrigger::relation_created(&rebuttal)).unwrap();
+        assert_eq!(changes.len(), 2);
+        assert!(changes.iter().all(|e| e.current == ChainValidity::Contested && e.creator == agent("alice")));
+        assert_eq!(events.try_recv().unwrap().fragment_id, "conclusion");
+
+        store.delete_fragment("premise").unwrap();
+        let trigger = ValidityTrigger::FragmentDeleted { fragment: "premise".to_string() };
+        let changes = service.propagate(&trigger).unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].previous, Some(ChainValidity::Contested));
+        let cached = store.get_fragment("corollary").unwrap().unwrap().validity.unwrap();
+        assert_eq!(cached.status, ChainValidity::Broken);
+
+        // Events are kept in the store, so a restarted hub still has them
+        let trust = Arc::new(TrustService::new(Arc::clone(&store), TrustConfig::default()));
+        let restarted = ValidityService::new().with_store(Arc::clone(&store), trust);
+        let recent = restarted.recent_events_for("alice", 10).unwrap();
+        assert_eq!(recent.len(), 4);
+        assert_eq!(recent[0].cause, trigger);
+        assert_eq!(restarted.recent_events_for("alice", 1).unwrap().len(), 1);
+        assert!(restarted.recent_events_for("bob", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_quarantine_propagation() {
+        let (service, store, _dir) = setup();
+        let mut alice = Agent::new("alice", "key");
+        alice.add_trust(agent("bob"), 0.9);
+        store.put_agent(&alice).unwrap();
+
+        put_fragment(&store, "premise", "bob");
+        put_fragment(&store, "conclusion", "alice");
+        put_fragment(&store, "rebuttal", "carol");
+        let derivation = put_relation(&store, "conclusion", "premise", RelationType::DerivedFrom);
+        let mut rebuttal = put_relation(&store, "rebuttal", "premise", RelationType::Contradicts);
+        service.propagate(&ValidityTrigger::relation_created(&derivation)).unwrap();
+        service.propagate(&ValidityTrigger::relation_created(&rebuttal)).unwrap();
+        let cached = store.get_fragment("conclusion").unwrap().unwrap().validity.unwrap();
+        assert_eq!(cached.status, ChainValidity::Contested);
+
+        // A quarantined rebuttal no longer counts
+        rebuttal.quarantine = Some(Quarantine { key: "stolen".to_string(), since: Utc::now() });
+        store.put_relation(&rebuttal).unwrap();
+        let change = QuarantineChange { relations: vec![rebuttal.uuid.clone()], ..Default::default() };
+        let changes = service.propagate_quarantine(&change).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].current, ChainValidity::Valid);
+        assert!(matches!(changes[0].cause, ValidityTrigger::QuarantineChanged { quarantined: true, .. }));
+
+        // Released again, it does
+        rebuttal.quarantine = None;
+        store.put_relation(&rebuttal).unwrap();
+        let changes = service.propagate_quarantine(&change).unwrap();
+        assert_eq!(changes[0].current, ChainValidity::Contested);
+    }
+
+    #[test]
+    fn test_find_disputes() {
+        let (service, store, _dir) = setup();
+        let tag = Tag::new("rust", TagCategory::Language, agent("alice"));
+        store.put_tag(&tag).unwrap();
+
+        let tagged = Fragment::with_uuid("a", "claim a", agent("bob"))
+            .with_tag(Address::tag("hub:8080", &tag.uuid));
+        store.put_fragment(&tagged).unwrap();
+        for uuid in ["b", "c", "d", "e", "witness"] {
+            put_fragment(&store, uuid, "carol");
+        }
+        put_relation(&store, "a", "b", RelationType::Contradicts);
+        put_relation(&store, "c", "b", RelationType::Contradicts);
+        put_relation(&store, "witness", "a", RelationType::Supports);
+        put_relation(&store, "d", "e", RelationType::Contradicts);
+        put_relation(&store, "d", "a", RelationType::DerivedFrom);
+
+        let disputes = service.find_disputes(None, None, 10).unwrap();
+        assert_eq!(disputes.len(), 2);
+        assert_eq!(disputes[0].fragments, vec!["a", "b", "c"]);
+        assert_eq!(disputes[0].contradictions.len(), 2);
+        assert_eq!(disputes[0].relation_count, 3);
+        assert_eq!(disputes[0].participants, 3);
+        // a is supported once, b is contradicted twice
+        assert_eq!(disputes[0].net_evidence[0], ("a".to_string(), 1.0));
+        assert!((disputes[0].trust_spread - 3.0).abs() < 0.01);
+        assert!(disputes[0].score > disputes[1].score);
+
+        let disputes = service.find_disputes(Some("rust"), None, 10).unwrap();
+        assert_eq!(disputes.len(), 1);
+        assert_eq!(disputes[0].fragments.len(), 3);
+        assert!(matches!(service.find_disputes(Some("go"), None, 10), Err(HubError::NotFound { .. })));
+
+        // The limit keeps the most active clusters
+        let disputes = service.find_disputes(None, None, 1).unwrap();
+        assert_eq!(disputes.len(), 1);
+        assert_eq!(disputes[0].fragments, vec!["a", "b", "c"]);
+
+        // Nobody is trusted by a viewer without trust entries, so no evidence counts
+        let disputes = service.find_disputes(None, Some(&agent("stranger")), 1).unwrap();
+        assert_eq!(disputes.len(), 1);
+        assert_eq!(disputes[0].trust_spread, 0.0);
+    }
 }
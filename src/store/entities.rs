@@ -1,7 +1,15 @@
 //! Entity storage operations
 
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+
 use super::RocksStore;
-use crate::models::{Agent, Fragment, Relation, Tag, Transform, HubResult, HubError};
+use crate::models::{
+    Agent, Fragment, Relation, RelationType, Tag, Transform, Vote, KeyRevocation, HubResult, HubError,
+};
 
 /// Pagination cursor for list operations
 #[derive(Debug, Clone)]
@@ -42,12 +50,23 @@ pub struct ListResult<T> {
 #[derive(Clone, Debug)]
 pub struct EntityStore {
     rocks: RocksStore,
+    /// Serializes read-modify-write updates of stored entities
+    update_lock: Arc<Mutex<()>>,
 }
 
 impl EntityStore {
     /// Create a new entity store
     pub fn new(rocks: RocksStore) -> Self {
-        Self { rocks }
+        Self {
+            rocks,
+            update_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Build the secondary indexes for entities stored before they existed.
+    /// Returns the number of entities indexed.
+    pub fn ensure_indexes(&self) -> HubResult<usize> {
+        Ok(self.ensure_relation_index()? + self.ensure_vote_index()?)
     }
 
     /// Get a reference to the underlying RocksStore
@@ -86,6 +105,36 @@ impl EntityStore {
         }
     }
 
+    /// Update a stored agent in place.
+    ///
+    /// Updates through this method do not overwrite each other. Returns the
+    /// updated agent, or None if it does not exist.
+    pub fn update_agent(&self, uuid: &str, update: impl FnOnce(&mut Agent)) -> HubResult<Option<Agent>> {
+        self.try_update_agent(uuid, |agent| {
+            update(agent);
+            Ok(())
+        })
+    }
+
+    /// Update a stored agent in place, unless `update` fails.
+    ///
+    /// Checks made in `update` see the agent as stored, so concurrent updates
+    /// cannot both pass them. Nothing is written if `update` returns an error.
+    pub fn try_update_agent(
+        &self,
+        uuid: &str,
+        update: impl FnOnce(&mut Agent) -> HubResult<()>,
+    ) -> HubResult<Option<Agent>> {
+        let _guard = self.update_lock.lock();
+        let mut agent = match self.get_agent(uuid)? {
+            Some(agent) => agent,
+            None => return Ok(None),
+        };
+        update(&mut agent)?;
+        self.put_agent(&agent)?;
+        Ok(Some(agent))
+    }
+
     /// List agents with pagination
     pub fn list_agents(&self, cursor: &Cursor, limit: usize) -> HubResult<ListResult<Agent>> {
         self.list_entities("agents", cursor, limit)
@@ -138,19 +187,62 @@ impl EntityStore {
         }
     }
 
+    /// Update a stored fragment in place.
+    ///
+    /// Updates through this method do not overwrite each other. Returns the
+    /// updated fragment, or None if it does not exist.
+    pub fn update_fragment(
+        &self,
+        uuid: &str,
+        update: impl FnOnce(&mut Fragment),
+    ) -> HubResult<Option<Fragment>> {
+        self.try_update_fragment(uuid, |fragment| {
+            update(fragment);
+            Ok(())
+        })
+    }
+
+    /// Update a stored fragment in place, unless `update` fails (see `try_update_agent`)
+    pub fn try_update_fragment(
+        &self,
+        uuid: &str,
+        update: impl FnOnce(&mut Fragment) -> HubResult<()>,
+    ) -> HubResult<Option<Fragment>> {
+        let _guard = self.update_lock.lock();
+        let mut fragment = match self.get_fragment(uuid)? {
+            Some(fragment) => fragment,
+            None => return Ok(None),
+        };
+        update(&mut fragment)?;
+        self.put_fragment(&fragment)?;
+        Ok(Some(fragment))
+    }
+
     /// List fragments with pagination
     pub fn list_fragments(&self, cursor: &Cursor, limit: usize) -> HubResult<ListResult<Fragment>> {
         self.list_entities("fragments", cursor, limit)
     }
 
     /// Delete a fragment
+    /// Delete a fragment with the votes cast on it, so a fragment recreated
+    /// under the same UUID starts without them
     pub fn delete_fragment(&self, uuid: &str) -> HubResult<()> {
         let cf = self.rocks.cf("fragments")?;
-        let key = uuid.as_bytes();
+        let votes_cf = self.rocks.cf("votes")?;
+        let index_cf = self.rocks.cf("vote_index")?;
+
+        let _guard = self.update_lock.lock();
+        let mut batch = rocksdb::WriteBatch::default();
+        batch.delete_cf(cf, uuid.as_bytes());
+        for (voter, value) in self.scan_prefix("votes", &format!("{}\0", uuid))? {
+            let vote: Vote = serde_json::from_slice(&value)?;
+            batch.delete_cf(index_cf, vote.uuid.as_bytes());
+            batch.delete_cf(votes_cf, Self::vote_key(uuid, &voter));
+        }
 
         self.rocks
             .db()
-            .delete_cf(cf, key)
+            .write(batch)
             .map_err(|e| HubError::DatabaseError(e.to_string()))
     }
 
@@ -173,8 +265,8 @@ impl EntityStore {
             let (_, value) = item.map_err(|e| HubError::DatabaseError(e.to_string()))?;
             let fragment: Fragment = serde_json::from_slice(&value)?;
 
-            // Search in content
-            if fragment.content.to_lowercase().contains(&query_lower) {
+            // Search in content; quarantined fragments are left out
+            if !fragment.is_quarantined() && fragment.content.to_lowercase().contains(&query_lower) {
                 results.push(fragment);
             }
         }
@@ -182,19 +274,49 @@ impl EntityStore {
         Ok(results)
     }
 
+    /// Get all fragments created by an agent
+    pub fn get_fragments_by_creator(&self, creator_uuid: &str) -> HubResult<Vec<Fragment>> {
+        self.scan_entities("fragments", |f: &Fragment| f.creator.entity == creator_uuid)
+    }
+
     // ========================================================================
     // Relation operations
     // ========================================================================
 
-    /// Store a relation
+    /// Relation index keys: `{kind}\0{value}\0{relation uuid}`, so the
+    /// relations with a given target, source or type are adjacent
+    fn relation_index_keys(relation: &Relation) -> [Vec<u8>; 3] {
+        [
+            Self::relation_index_key("to", &relation.to.entity, &relation.uuid),
+            Self::relation_index_key("from", &relation.from.entity, &relation.uuid),
+            Self::relation_index_key("type", &relation.relation_type.to_string(), &relation.uuid),
+        ]
+    }
+
+    fn relation_index_key(kind: &str, value: &str, uuid: &str) -> Vec<u8> {
+        format!("{}\0{}\0{}", kind, value, uuid).into_bytes()
+    }
+
+    /// Store a relation and its index entries
     pub fn put_relation(&self, relation: &Relation) -> HubResult<()> {
         let cf = self.rocks.cf("relations")?;
-        let key = relation.uuid.as_bytes();
+        let index_cf = self.rocks.cf("relation_index")?;
         let value = serde_json::to_vec(relation)?;
 
+        let mut batch = rocksdb::WriteBatch::default();
+        if let Some(previous) = self.get_relation(&relation.uuid)? {
+            for key in Self::relation_index_keys(&previous) {
+                batch.delete_cf(index_cf, key);
+            }
+        }
+        batch.put_cf(cf, relation.uuid.as_bytes(), value);
+        for key in Self::relation_index_keys(relation) {
+            batch.put_cf(index_cf, key, b"");
+        }
+
         self.rocks
             .db()
-            .put_cf(cf, key, value)
+            .write(batch)
             .map_err(|e| HubError::DatabaseError(e.to_string()))
     }
 
@@ -213,32 +335,90 @@ impl EntityStore {
         }
     }
 
+    /// Update a stored relation in place (see `update_fragment`)
+    pub fn update_relation(
+        &self,
+        uuid: &str,
+        update: impl FnOnce(&mut Relation),
+    ) -> HubResult<Option<Relation>> {
+        let _guard = self.update_lock.lock();
+        let mut relation = match self.get_relation(uuid)? {
+            Some(relation) => relation,
+            None => return Ok(None),
+        };
+        update(&mut relation);
+        self.put_relation(&relation)?;
+        Ok(Some(relation))
+    }
+
     /// List relations with pagination
     pub fn list_relations(&self, cursor: &Cursor, limit: usize) -> HubResult<ListResult<Relation>> {
         self.list_entities("relations", cursor, limit)
     }
 
-    /// Delete a relation
+    /// Delete a relation and its index entries
     pub fn delete_relation(&self, uuid: &str) -> HubResult<()> {
         let cf = self.rocks.cf("relations")?;
-        let key = uuid.as_bytes();
+        let index_cf = self.rocks.cf("relation_index")?;
+
+        let mut batch = rocksdb::WriteBatch::default();
+        if let Some(relation) = self.get_relation(uuid)? {
+            for key in Self::relation_index_keys(&relation) {
+                batch.delete_cf(index_cf, key);
+            }
+        }
+        batch.delete_cf(cf, uuid.as_bytes());
 
         self.rocks
             .db()
-            .delete_cf(cf, key)
+            .write(batch)
             .map_err(|e| HubError::DatabaseError(e.to_string()))
     }
 
     /// Get relations by source entity (from address)
     pub fn get_relations_by_from(&self, from_entity: &str) -> HubResult<Vec<Relation>> {
-        let cf = self.rocks.cf("relations")?;
-        let iter = self.rocks.db().iterator_cf(cf, rocksdb::IteratorMode::Start);
+        self.get_indexed_relations("from", from_entity, usize::MAX)
+    }
+
+    /// Get relations by target entity (to address)
+    pub fn get_relations_by_to(&self, to_entity: &str) -> HubResult<Vec<Relation>> {
+        self.get_indexed_relations("to", to_entity, usize::MAX)
+    }
+
+    /// Get all relations of a type
+    pub fn get_relations_by_type(&self, relation_type: RelationType) -> HubResult<Vec<Relation>> {
+        self.get_indexed_relations("type", &relation_type.to_string(), usize::MAX)
+    }
+
+    /// Get up to `limit` relations of a type, in UUID order
+    pub fn get_relations_by_type_limited(
+        &self,
+        relation_type: RelationType,
+        limit: usize,
+    ) -> HubResult<Vec<Relation>> {
+        self.get_indexed_relations("type", &relation_type.to_string(), limit)
+    }
+
+    /// Up to `limit` relations listed in the index under `kind` and `value`
+    fn get_indexed_relations(&self, kind: &str, value: &str, limit: usize) -> HubResult<Vec<Relation>> {
+        let index_cf = self.rocks.cf("relation_index")?;
+        let prefix = Self::relation_index_key(kind, value, "");
+        let iter = self.rocks.db().iterator_cf(
+            index_cf,
+            rocksdb::IteratorMode::From(&prefix, rocksdb::Direction::Forward),
+        );
 
         let mut results = Vec::new();
         for item in iter {
-            let (_, value) = item.map_err(|e| HubError::DatabaseError(e.to_string()))?;
-            let relation: Relation = serde_json::from_slice(&value)?;
-            if relation.from.entity == from_entity {
+            if results.len() >= limit {
+                break;
+            }
+            let (key, _) = item.map_err(|e| HubError::DatabaseError(e.to_string()))?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            let uuid = String::from_utf8_lossy(&key[prefix.len()..]);
+            if let Some(relation) = self.get_relation(&uuid)? {
                 results.push(relation);
             }
         }
@@ -246,21 +426,31 @@ impl EntityStore {
         Ok(results)
     }
 
-    /// Get relations by target entity (to address)
-    pub fn get_relations_by_to(&self, to_entity: &str) -> HubResult<Vec<Relation>> {
-        let cf = self.rocks.cf("relations")?;
-        let iter = self.rocks.db().iterator_cf(cf, rocksdb::IteratorMode::Start);
+    /// Index relations stored before the relation index existed. Does nothing
+    /// if the index already has entries; returns the number of relations indexed.
+    fn ensure_relation_index(&self) -> HubResult<usize> {
+        let index_cf = self.rocks.cf("relation_index")?;
+        if self.rocks.db().iterator_cf(index_cf, rocksdb::IteratorMode::Start).next().is_some() {
+            return Ok(0);
+        }
 
-        let mut results = Vec::new();
-        for item in iter {
-            let (_, value) = item.map_err(|e| HubError::DatabaseError(e.to_string()))?;
-            let relation: Relation = serde_json::from_slice(&value)?;
-            if relation.to.entity == to_entity {
-                results.push(relation);
+        let relations: Vec<Relation> = self.scan_entities("relations", |_| true)?;
+        let mut batch = rocksdb::WriteBatch::default();
+        for relation in &relations {
+            for key in Self::relation_index_keys(relation) {
+                batch.put_cf(index_cf, key, b"");
             }
         }
+        self.rocks
+            .db()
+            .write(batch)
+            .map_err(|e| HubError::DatabaseError(e.to_string()))?;
+        Ok(relations.len())
+    }
 
-        Ok(results)
+    /// Get all relations created by an agent
+    pub fn get_relations_by_creator(&self, creator_uuid: &str) -> HubResult<Vec<Relation>> {
+        self.scan_entities("relations", |r: &Relation| r.creator.entity == creator_uuid)
     }
 
     // ========================================================================
@@ -294,6 +484,18 @@ impl EntityStore {
         }
     }
 
+    /// Update a stored tag in place (see `update_fragment`)
+    pub fn update_tag(&self, uuid: &str, update: impl FnOnce(&mut Tag)) -> HubResult<Option<Tag>> {
+        let _guard = self.update_lock.lock();
+        let mut tag = match self.get_tag(uuid)? {
+            Some(tag) => tag,
+            None => return Ok(None),
+        };
+        update(&mut tag);
+        self.put_tag(&tag)?;
+        Ok(Some(tag))
+    }
+
     /// List tags with pagination
     pub fn list_tags(&self, cursor: &Cursor, limit: usize) -> HubResult<ListResult<Tag>> {
         self.list_entities("tags", cursor, limit)
@@ -326,6 +528,11 @@ impl EntityStore {
         Ok(None)
     }
 
+    /// Get all tags created by an agent
+    pub fn get_tags_by_creator(&self, creator_uuid: &str) -> HubResult<Vec<Tag>> {
+        self.scan_entities("tags", |t: &Tag| t.creator.entity == creator_uuid)
+    }
+
     // ========================================================================
     // Transform operations
     // ========================================================================
@@ -373,10 +580,446 @@ impl EntityStore {
             .map_err(|e| HubError::DatabaseError(e.to_string()))
     }
 
+    // ========================================================================
+    // Vote operations
+    // ========================================================================
+
+    /// Votes are keyed by fragment and voter, so each agent has one vote per
+    /// fragment and a fragment's votes are adjacent
+    fn vote_key(fragment_uuid: &str, voter_uuid: &str) -> Vec<u8> {
+        format!("{}\0{}", fragment_uuid, voter_uuid).into_bytes()
+    }
+
+    /// Store a vote, replacing the voter's previous vote on the fragment
+    pub fn put_vote(&self, vote: &Vote) -> HubResult<()> {
+        let cf = self.rocks.cf("votes")?;
+        let index_cf = self.rocks.cf("vote_index")?;
+        let key = Self::vote_key(&vote.fragment.entity, &vote.voter.entity);
+        let value = serde_json::to_vec(vote)?;
+
+        let mut batch = rocksdb::WriteBatch::default();
+        if let Some(previous) = self.get_vote_by_voter(&vote.fragment.entity, &vote.voter.entity)? {
+            if previous.uuid != vote.uuid {
+                batch.delete_cf(index_cf, previous.uuid.as_bytes());
+            }
+        }
+        batch.put_cf(cf, &key, value);
+        batch.put_cf(index_cf, vote.uuid.as_bytes(), &key);
+
+        self.rocks
+            .db()
+            .write(batch)
+            .map_err(|e| HubError::DatabaseError(e.to_string()))
+    }
+
+    /// Store a vote in place of the voter's previous vote on the fragment.
+    ///
+    /// `replace` sees the previous vote as stored and may adjust the new vote
+    /// or reject it, so concurrent votes of one voter on one fragment do not
+    /// overwrite each other unseen. Returns the previous vote.
+    pub fn replace_vote(
+        &self,
+        vote: &mut Vote,
+        replace: impl FnOnce(Option<&Vote>, &mut Vote) -> HubResult<()>,
+    ) -> HubResult<Option<Vote>> {
+        let _guard = self.update_lock.lock();
+        let previous = self.get_vote_by_voter(&vote.fragment.entity, &vote.voter.entity)?;
+        replace(previous.as_ref(), vote)?;
+
+        // A UUID names one vote; reusing another's would take over its lookup
+        if let Some(existing) = self.get_vote(&vote.uuid)? {
+            if existing.fragment.entity != vote.fragment.entity || existing.voter.entity != vote.voter.entity {
+                return Err(HubError::already_exists("vote", &vote.uuid));
+            }
+        }
+
+        self.put_vote(vote)?;
+        Ok(previous)
+    }
+
+    /// Get an agent's vote on a fragment
+    pub fn get_vote_by_voter(&self, fragment_uuid: &str, voter_uuid: &str) -> HubResult<Option<Vote>> {
+        let cf = self.rocks.cf("votes")?;
+        let key = Self::vote_key(fragment_uuid, voter_uuid);
+
+        match self.rocks.db().get_cf(cf, key) {
+            Ok(Some(value)) => {
+                let vote: Vote = serde_json::from_slice(&value)?;
+                Ok(Some(vote))
+            }
+            Ok(None) => Ok(None),
+            Err(e) => Err(HubError::DatabaseError(e.to_string())),
+        }
+    }
+
+    /// Get a vote by UUID
+    pub fn get_vote(&self, uuid: &str) -> HubResult<Option<Vote>> {
+        let cf = self.rocks.cf("votes")?;
+        let index_cf = self.rocks.cf("vote_index")?;
+
+        let key = match self.rocks.db().get_cf(index_cf, uuid.as_bytes()) {
+            Ok(Some(key)) => key,
+            Ok(None) => return Ok(None),
+            Err(e) => return Err(HubError::DatabaseError(e.to_string())),
+        };
+        match self.rocks.db().get_cf(cf, key) {
+            Ok(Some(value)) => {
+                let vote: Vote = serde_json::from_slice(&value)?;
+                // The index may lag a vote replaced under another UUID
+                Ok((vote.uuid == uuid).then_some(vote))
+            }
+            Ok(None) => Ok(None),
+            Err(e) => Err(HubError::DatabaseError(e.to_string())),
+        }
+    }
+
+    /// Index votes stored before the vote index existed (see `ensure_relation_index`)
+    fn ensure_vote_index(&self) -> HubResult<usize> {
+        let cf = self.rocks.cf("votes")?;
+        let index_cf = self.rocks.cf("vote_index")?;
+        if self.rocks.db().iterator_cf(index_cf, rocksdb::IteratorMode::Start).next().is_some() {
+            return Ok(0);
+        }
+
+        let mut batch = rocksdb::WriteBatch::default();
+        let mut count = 0;
+        for item in self.rocks.db().iterator_cf(cf, rocksdb::IteratorMode::Start) {
+            let (key, value) = item.map_err(|e| HubError::DatabaseError(e.to_string()))?;
+            let vote: Vote = serde_json::from_slice(&value)?;
+            batch.put_cf(index_cf, vote.uuid.as_bytes(), key);
+            count += 1;
+        }
+        self.rocks
+            .db()
+            .write(batch)
+            .map_err(|e| HubError::DatabaseError(e.to_string()))?;
+        Ok(count)
+    }
+
+    /// Get all votes on a fragment
+    pub fn get_votes_for_fragment(&self, fragment_uuid: &str) -> HubResult<Vec<Vote>> {
+        let cf = self.rocks.cf("votes")?;
+        let prefix = Self::vote_key(fragment_uuid, "");
+        let iter = self.rocks.db().iterator_cf(
+            cf,
+            rocksdb::IteratorMode::From(&prefix, rocksdb::Direction::Forward),
+        );
+
+        let mut results = Vec::new();
+        for item in iter {
+            let (key, value) = item.map_err(|e| HubError::DatabaseError(e.to_string()))?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            results.push(serde_json::from_slice(&value)?);
+        }
+
+        Ok(results)
+    }
+
+    /// Delete an agent's vote on a fragment
+    pub fn delete_vote(&self, fragment_uuid: &str, voter_uuid: &str) -> HubResult<()> {
+        let cf = self.rocks.cf("votes")?;
+        let index_cf = self.rocks.cf("vote_index")?;
+        let key = Self::vote_key(fragment_uuid, voter_uuid);
+
+        let mut batch = rocksdb::WriteBatch::default();
+        if let Some(vote) = self.get_vote_by_voter(fragment_uuid, voter_uuid)? {
+            batch.delete_cf(index_cf, vote.uuid.as_bytes());
+        }
+        batch.delete_cf(cf, key);
+
+        self.rocks
+            .db()
+            .write(batch)
+            .map_err(|e| HubError::DatabaseError(e.to_string()))
+    }
+
+    // ========================================================================
+    // Revocation operations
+    // ========================================================================
+
+    /// Revocations are keyed by agent and key, so an agent's are adjacent
+    fn revocation_key(agent_uuid: &str, public_key: &str) -> Vec<u8> {
+        format!("{}\0{}", agent_uuid, public_key).into_bytes()
+    }
+
+    /// Store a revocation, replacing an earlier one of the same key
+    pub fn put_revocation(&self, revocation: &KeyRevocation) -> HubResult<()> {
+        let cf = self.rocks.cf("revocations")?;
+        let key = Self::revocation_key(&revocation.agent, &revocation.public_key);
+        let value = serde_json::to_vec(revocation)?;
+
+        self.rocks
+            .db()
+            .put_cf(cf, key, value)
+            .map_err(|e| HubError::DatabaseError(e.to_string()))
+    }
+
+    /// Get all revocations of an agent's keys
+    pub fn get_revocations_for_agent(&self, agent_uuid: &str) -> HubResult<Vec<KeyRevocation>> {
+        let cf = self.rocks.cf("revocations")?;
+        let prefix = Self::revocation_key(agent_uuid, "");
+        let iter = self.rocks.db().iterator_cf(
+            cf,
+            rocksdb::IteratorMode::From(&prefix, rocksdb::Direction::Forward),
+        );
+
+        let mut results = Vec::new();
+        for item in iter {
+            let (key, value) = item.map_err(|e| HubError::DatabaseError(e.to_string()))?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            results.push(serde_json::from_slice(&value)?);
+        }
+
+        Ok(results)
+    }
+
+    /// Get all revocations
+    pub fn list_revocations(&self) -> HubResult<Vec<KeyRevocation>> {
+        self.scan_entities("revocations", |_: &KeyRevocation| true)
+    }
+
+    // ========================================================================
+    // Validity event operations
+    // ========================================================================
+
+    /// Validity events are keyed by the notified agent and time, so an
+    /// agent's events are adjacent and in order
+    fn validity_event_key(agent_uuid: &str, occurred_at: DateTime<Utc>, fragment_uuid: &str) -> Vec<u8> {
+        format!(
+            "{}\0{:020}\0{}",
+            agent_uuid,
+            occurred_at.timestamp_nanos_opt().unwrap_or(i64::MAX),
+            fragment_uuid
+        )
+        .into_bytes()
+    }
+
+    /// Store a validity change event for an agent, keeping their `keep` most recent
+    pub fn put_validity_event<T: serde::Serialize>(
+        &self,
+        agent_uuid: &str,
+        occurred_at: DateTime<Utc>,
+        fragment_uuid: &str,
+        event: &T,
+        keep: usize,
+    ) -> HubResult<()> {
+        let cf = self.rocks.cf("validity_events")?;
+        let key = Self::validity_event_key(agent_uuid, occurred_at, fragment_uuid);
+        let prefix = format!("{}\0", agent_uuid).into_bytes();
+
+        let mut batch = rocksdb::WriteBatch::default();
+        batch.put_cf(cf, &key, serde_json::to_vec(event)?);
+        let iter = self.rocks.db().iterator_cf(
+            cf,
+            rocksdb::IteratorMode::From(&prefix, rocksdb::Direction::Forward),
+        );
+        let mut older: Vec<Box<[u8]>> = Vec::new();
+        for item in iter {
+            let (stored, _) = item.map_err(|e| HubError::DatabaseError(e.to_string()))?;
+            if !stored.starts_with(&prefix) {
+                break;
+            }
+            if stored.as_ref() != key.as_slice() {
+                older.push(stored);
+            }
+        }
+        let excess = (older.len() + 1).saturating_sub(keep);
+        for stale in older.iter().take(excess) {
+            batch.delete_cf(cf, stale);
+        }
+
+        self.rocks
+            .db()
+            .write(batch)
+            .map_err(|e| HubError::DatabaseError(e.to_string()))
+    }
+
+    /// Up to `limit` validity change events for an agent, newest first
+    pub fn get_validity_events<T: serde::de::DeserializeOwned>(
+        &self,
+        agent_uuid: &str,
+        limit: usize,
+    ) -> HubResult<Vec<T>> {
+        let cf = self.rocks.cf("validity_events")?;
+        let prefix = format!("{}\0", agent_uuid).into_bytes();
+        // Just past the agent's keys, iterating backwards
+        let end = format!("{}\u{1}", agent_uuid).into_bytes();
+        let iter = self.rocks.db().iterator_cf(
+            cf,
+            rocksdb::IteratorMode::From(&end, rocksdb::Direction::Reverse),
+        );
+
+        let mut results = Vec::new();
+        for item in iter {
+            let (key, value) = item.map_err(|e| HubError::DatabaseError(e.to_string()))?;
+            if key.as_ref() >= end.as_slice() {
+                continue;
+            }
+            if !key.starts_with(&prefix) || results.len() >= limit {
+                break;
+            }
+            results.push(serde_json::from_slice(&value)?);
+        }
+
+        Ok(results)
+    }
+
+    // ========================================================================
+    // Hub registry operations (primary hub)
+    // ========================================================================
+
+    const HUB_PREFIX: &'static str = "hub\0";
+    const HUB_PIN_PREFIX: &'static str = "pin\0";
+    const HUB_REGISTRY_VERSION_KEY: &'static str = "version";
+    const HUB_REGISTRY_CLOCK_KEY: &'static str = "clock";
+
+    /// Store the serialized entry of a registered hub
+    pub fn put_registered_hub(&self, hub_id: &str, value: &[u8]) -> HubResult<()> {
+        let cf = self.rocks.cf("hub_registry")?;
+        let key = format!("{}{}", Self::HUB_PREFIX, hub_id);
+
+        self.rocks
+            .db()
+            .put_cf(cf, key, value)
+            .map_err(|e| HubError::DatabaseError(e.to_string()))
+    }
+
+    /// Delete a registered hub (its key pin is kept)
+    pub fn delete_registered_hub(&self, hub_id: &str) -> HubResult<()> {
+        let cf = self.rocks.cf("hub_registry")?;
+        let key = format!("{}{}", Self::HUB_PREFIX, hub_id);
+
+        self.rocks
+            .db()
+            .delete_cf(cf, key)
+            .map_err(|e| HubError::DatabaseError(e.to_string()))
+    }
+
+    /// Get the serialized entries of all registered hubs by hub_id
+    pub fn list_registered_hubs(&self) -> HubResult<Vec<(String, Vec<u8>)>> {
+        self.scan_prefix("hub_registry", Self::HUB_PREFIX)
+    }
+
+    /// Store the serialized key pin of a hub_id
+    pub fn put_hub_pin(&self, hub_id: &str, value: &[u8]) -> HubResult<()> {
+        let cf = self.rocks.cf("hub_registry")?;
+        let key = format!("{}{}", Self::HUB_PIN_PREFIX, hub_id);
+
+        self.rocks
+            .db()
+            .put_cf(cf, key, value)
+            .map_err(|e| HubError::DatabaseError(e.to_string()))
+    }
+
+    /// Delete the key pin of a hub
+    pub fn delete_hub_pin(&self, hub_id: &str) -> HubResult<()> {
+        let cf = self.rocks.cf("hub_registry")?;
+        let key = format!("{}{}", Self::HUB_PIN_PREFIX, hub_id);
+
+        self.rocks
+            .db()
+            .delete_cf(cf, key)
+            .map_err(|e| HubError::DatabaseError(e.to_string()))
+    }
+
+    /// Get the serialized key pins of all hubs by hub_id
+    pub fn list_hub_pins(&self) -> HubResult<Vec<(String, Vec<u8>)>> {
+        self.scan_prefix("hub_registry", Self::HUB_PIN_PREFIX)
+    }
+
+    /// Get the hub list version
+    pub fn get_hub_registry_version(&self) -> HubResult<u64> {
+        let cf = self.rocks.cf("hub_registry")?;
+        match self.rocks.db().get_cf(cf, Self::HUB_REGISTRY_VERSION_KEY) {
+            Ok(Some(value)) => Ok(serde_json::from_slice(&value)?),
+            Ok(None) => Ok(0),
+            Err(e) => Err(HubError::DatabaseError(e.to_string())),
+        }
+    }
+
+    /// Store the hub list version
+    pub fn put_hub_registry_version(&self, version: u64) -> HubResult<()> {
+        let cf = self.rocks.cf("hub_registry")?;
+        let value = serde_json::to_vec(&version)?;
+
+        self.rocks
+            .db()
+            .put_cf(cf, Self::HUB_REGISTRY_VERSION_KEY, value)
+            .map_err(|e| HubError::DatabaseError(e.to_string()))
+    }
+
+    /// Get the versions last merged from other primary hubs, by hub_id
+    pub fn get_hub_registry_clock(&self) -> HubResult<BTreeMap<String, u64>> {
+        let cf = self.rocks.cf("hub_registry")?;
+        match self.rocks.db().get_cf(cf, Self::HUB_REGISTRY_CLOCK_KEY) {
+            Ok(Some(value)) => Ok(serde_json::from_slice(&value)?),
+            Ok(None) => Ok(BTreeMap::new()),
+            Err(e) => Err(HubError::DatabaseError(e.to_string())),
+        }
+    }
+
+    /// Store the versions last merged from other primary hubs
+    pub fn put_hub_registry_clock(&self, clock: &BTreeMap<String, u64>) -> HubResult<()> {
+        let cf = self.rocks.cf("hub_registry")?;
+        let value = serde_json::to_vec(clock)?;
+
+        self.rocks
+            .db()
+            .put_cf(cf, Self::HUB_REGISTRY_CLOCK_KEY, value)
+            .map_err(|e| HubError::DatabaseError(e.to_string()))
+    }
+
     // ========================================================================
     // Generic helper methods
     // ========================================================================
 
+    /// Raw values under a key prefix, with the rest of their key
+    fn scan_prefix(&self, cf_name: &str, prefix: &str) -> HubResult<Vec<(String, Vec<u8>)>> {
+        let cf = self.rocks.cf(cf_name)?;
+        let prefix = prefix.as_bytes();
+        let iter = self.rocks.db().iterator_cf(
+            cf,
+            rocksdb::IteratorMode::From(prefix, rocksdb::Direction::Forward),
+        );
+
+        let mut results = Vec::new();
+        for item in iter {
+            let (key, value) = item.map_err(|e| HubError::DatabaseError(e.to_string()))?;
+            if !key.starts_with(prefix) {
+                break;
+            }
+            let id = String::from_utf8_lossy(&key[prefix.len()..]).into_owned();
+            results.push((id, value.into_vec()));
+        }
+
+        Ok(results)
+    }
+
+    /// All entities of a column family matching a predicate
+    fn scan_entities<T: serde::de::DeserializeOwned>(
+        &self,
+        cf_name: &str,
+        predicate: impl Fn(&T) -> bool,
+    ) -> HubResult<Vec<T>> {
+        let cf = self.rocks.cf(cf_name)?;
+        let iter = self.rocks.db().iterator_cf(cf, rocksdb::IteratorMode::Start);
+
+        let mut results = Vec::new();
+        for item in iter {
+            let (_, value) = item.map_err(|e| HubError::DatabaseError(e.to_string()))?;
+            let entity: T = serde_json::from_slice(&value)?;
+            if predicate(&entity) {
+                results.push(entity);
+            }
+        }
+
+        Ok(results)
+    }
+
     /// Generic list operation for any entity type
     fn list_entities<T: serde::de::DeserializeOwned + HasUuid>(
         &self,
@@ -494,7 +1137,7 @@ impl HasUuid for Transform {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::Address;
+    use crate::models::{Address, VoteKind};
     use tempfile::TempDir;
 
     fn create_test_store() -> (EntityStore, TempDir) {
@@ -585,4 +1228,78 @@ mod tests {
 
         assert_eq!(store.count_agents().unwrap(), 3);
     }
+
+    #[test]
+    fn test_vote_index() {
+        let (store, _temp) = create_test_store();
+        let fragment = Address::fragment("hub:8080", "frag");
+        let voter = Address::agent("hub:8080", "voter");
+        let first = Vote::new(fragment.clone(), voter.clone(), VoteKind::Verify);
+        store.put_vote(&first).unwrap();
+        assert_eq!(store.get_vote(&first.uuid).unwrap().unwrap().kind, VoteKind::Verify);
+
+        // A vote stored under a new UUID replaces the voter's previous one
+        let second = Vote::new(fragment.clone(), voter.clone(), VoteKind::Contest);
+        let previous = store.replace_vote(&mut second.clone(), |_, _| Ok(())).unwrap();
+        assert_eq!(previous.unwrap().uuid, first.uuid);
+        assert!(store.get_vote(&first.uuid).unwrap().is_none());
+        assert_eq!(store.get_vote(&second.uuid).unwrap().unwrap().kind, VoteKind::Contest);
+
+        // Rejected replacements leave the stored vote alone
+        let result = store.replace_vote(&mut first.clone(), |_, _| {
+            Err(HubError::ValidationError("stale".to_string()))
+        });
+        assert!(result.is_err());
+        assert!(store.get_vote(&second.uuid).unwrap().is_some());
+
+        // Another voter cannot take over the vote's UUID
+        let mut takeover = Vote::new(fragment.clone(), Address::agent("hub:8080", "mallory"), VoteKind::Verify);
+        takeover.uuid = second.uuid.clone();
+        let result = store.replace_vote(&mut takeover, |_, _| Ok(()));
+        assert!(matches!(result, Err(HubError::AlreadyExists { .. })));
+        assert_eq!(store.get_vote(&second.uuid).unwrap().unwrap().voter, voter);
+
+        store.delete_vote("frag", "voter").unwrap();
+        assert!(store.get_vote(&second.uuid).unwrap().is_none());
+        assert_eq!(store.ensure_vote_index().unwrap(), 0);
+
+        // Deleting the fragment deletes its votes
+        let third = Vote::new(fragment, voter, VoteKind::Verify);
+        store.put_vote(&third).unwrap();
+        store.put_fragment(&Fragment::with_uuid("frag", "claim", Address::agent("hub:8080", "author"))).unwrap();
+        store.delete_fragment("frag").unwrap();
+        assert!(store.get_vote(&third.uuid).unwrap().is_none());
+        assert!(store.get_votes_for_fragment("frag").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_relation_index() {
+        let (store, _temp) = create_test_store();
+        let creator = Address::agent("hub:8080", "agent-uuid");
+        let fragment = |uuid: &str| Address::fragment("hub:8080", uuid);
+        let support = Relation::new(fragment("a"), fragment("b"), creator.clone(), RelationType::Supports);
+        let rebuttal = Relation::new(fragment("c"), fragment("b"), creator.clone(), RelationType::Contradicts);
+        store.put_relation(&support).unwrap();
+        store.put_relation(&rebuttal).unwrap();
+
+        assert_eq!(store.get_relations_by_to("b").unwrap().len(), 2);
+        assert_eq!(store.get_relations_by_from("c").unwrap()[0].uuid, rebuttal.uuid);
+        assert_eq!(store.get_relations_by_type(RelationType::Supports).unwrap()[0].uuid, support.uuid);
+        // Prefixes of other entities do not match
+        assert!(store.get_relations_by_to("").unwrap().is_empty());
+
+        store.delete_relation(&support.uuid).unwrap();
+        assert_eq!(store.get_relations_by_to("b").unwrap().len(), 1);
+        assert!(store.get_relations_by_type(RelationType::Supports).unwrap().is_empty());
+
+        // Relations stored without index entries are indexed once
+        let index_cf = store.rocks.cf("relation_index").unwrap();
+        for key in EntityStore::relation_index_keys(&rebuttal) {
+            store.rocks.db().delete_cf(index_cf, key).unwrap();
+        }
+        assert!(store.get_relations_by_to("b").unwrap().is_empty());
+        assert_eq!(store.ensure_relation_index().unwrap(), 1);
+        assert_eq!(store.ensure_relation_index().unwrap(), 0);
+        assert_eq!(store.get_relations_by_to("b").unwrap().len(), 1);
+    }
 }
@@ -27,8 +27,14 @@ impl RocksStore {
             "relations",
             "tags",
             "transforms",
+            "votes",
+            "revocations",
+            "hub_registry",
             "trust_relations",
             "sync_log",
+            "relation_index",
+            "vote_index",
+            "validity_events",
         ];
 
         let db = rocksdb::DB::open_cf(&opts, path, cfs)
@@ -64,8 +70,14 @@ impl RocksStore {
             "relations",
             "tags",
             "transforms",
+            "votes",
+            "revocations",
+            "hub_registry",
             "trust_relations",
             "sync_log",
+            "relation_index",
+            "vote_index",
+            "validity_events",
         ];
 
         let db = rocksdb::DB::open_cf(&opts, path, cfs)
@@ -0,0 +1,217 @@
+//! Trust graph export to GraphML and DOT
+
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::services::{TrustGraph, TrustGraphNode};
+
+/// Output format for trust graph exports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GraphFormat {
+    /// JSON (API response)
+    #[default]
+    Json,
+    /// GraphML (Gephi, yEd, ...)
+    GraphMl,
+    /// Graphviz DOT
+    Dot,
+}
+
+impl GraphFormat {
+    /// Content type of the rendered output
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            GraphFormat::Json => "application/json",
+            GraphFormat::GraphMl => "application/graphml+xml",
+            GraphFormat::Dot => "text/vnd.graphviz",
+        }
+    }
+}
+
+impl FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(GraphFormat::Json),
+            "graphml" => Ok(GraphFormat::GraphMl),
+            "dot" => Ok(GraphFormat::Dot),
+            _ => Err(format!("Invalid graph format: {} (expected json, graphml or dot)", s)),
+        }
+    }
+}
+
+/// Nodes ordered by depth, then id, for stable output
+fn sorted_nodes(graph: &TrustGraph) -> Vec<(&String, &TrustGraphNode)> {
+    let mut nodes: Vec<_> = graph.nodes.iter().collect();
+    nodes.sort_by(|a, b| a.1.depth.cmp(&b.1.depth).then_with(|| a.0.cmp(b.0)));
+    nodes
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Render a trust graph as GraphML
+pub fn graph_to_graphml(graph: &TrustGraph) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+    out.push_str("  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n");
+    out.push_str("  <key id=\"address\" for=\"node\" attr.name=\"address\" attr.type=\"string\"/>\n");
+    out.push_str("  <key id=\"depth\" for=\"node\" attr.name=\"depth\" attr.type=\"int\"/>\n");
+    out.push_str("  <key id=\"reputation\" for=\"node\" attr.name=\"reputation\" attr.type=\"float\"/>\n");
+    out.push_str("  <key id=\"center\" for=\"node\" attr.name=\"center\" attr.type=\"boolean\"/>\n");
+    out.push_str("  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"float\"/>\n");
+    out.push_str("  <key id=\"kind\" for=\"edge\" attr.name=\"kind\" attr.type=\"string\"/>\n");
+    out.push_str("  <key id=\"scope\" for=\"edge\" attr.name=\"scope\" attr.type=\"string\"/>\n");
+    out.push_str("  <graph id=\"trust\" edgedefault=\"directed\">\n");
+
+    for (id, node) in sorted_nodes(graph) {
+        let _ = writeln!(out, "    <node id=\"{}\">", xml_escape(id));
+        let _ = writeln!(out, "      <data key=\"label\">{}</data>", xml_escape(&node.description));
+        let _ = writeln!(out, "      <data key=\"address\">{}</data>", xml_escape(&node.address.to_string()));
+        let _ = writeln!(out, "      <data key=\"depth\">{}</data>", node.depth);
+        if let Some(reputation) = node.reputation {
+            let _ = writeln!(out, "      <data key=\"reputation\">{}</data>", reputation);
+        }
+        let _ = writeln!(out, "      <data key=\"center\">{}</data>", node.address == graph.center);
+        out.push_str("    </node>\n");
+    }
+
+    for (i, edge) in graph.edges.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "    <edge id=\"e{}\" source=\"{}\" target=\"{}\">",
+            i,
+            xml_escape(&edge.from.entity),
+            xml_escape(&edge.to.entity)
+        );
+        let _ = writeln!(out, "      <data key=\"weight\">{}</data>", edge.trust_level);
+        let kind = if edge.is_distrust() { "distrust" } else { "trust" };
+        let _ = writeln!(out, "      <data key=\"kind\">{}</data>", kind);
+        if let Some(ref scope) = edge.scope {
+            let _ = writeln!(out, "      <data key=\"scope\">{}</data>", xml_escape(&scope.to_string()));
+        }
+        out.push_str("    </edge>\n");
+    }
+
+    out.push_str("  </graph>\n");
+    out.push_str("</graphml>\n");
+    out
+}
+
+/// Render a trust graph as Graphviz DOT
+///
+/// Trust edges are drawn solid green, distrust edges dashed red; pen width
+/// follows the magnitude of the trust level.
+pub fn graph_to_dot(graph: &TrustGraph) -> String {
+    let mut out = String::new();
+    out.push_str("digraph trust {\n");
+    out.push_str("  rankdir=LR;\n");
+    out.push_str("  node [shape=ellipse];\n");
+
+    for (id, node) in sorted_nodes(graph) {
+        let name = if node.description.is_empty() { id.as_str() } else { node.description.as_str() };
+        let label = match node.reputation {
+            Some(reputation) => format!("{}\nreputation {:.2}", name, reputation),
+            None => name.to_string(),
+        };
+        let shape = if node.address == graph.center { ", shape=doublecircle" } else { "" };
+        let _ = writeln!(out, "  \"{}\" [label=\"{}\"{}];", dot_escape(id), dot_escape(&label), shape);
+    }
+
+    for edge in &graph.edges {
+        let style = if edge.is_distrust() {
+            "color=\"red\", style=\"dashed\""
+        } else {
+            "color=\"forestgreen\""
+        };
+        let label = match edge.scope {
+            Some(ref scope) => format!("{:.2} ({})", edge.trust_level, scope),
+            None => format!("{:.2}", edge.trust_level),
+        };
+        let _ = writeln!(
+            out,
+            "  \"{}\" -> \"{}\" [label=\"{}\", {}, penwidth={:.1}];",
+            dot_escape(&edge.from.entity),
+            dot_escape(&edge.to.entity),
+            dot_escape(&label),
+            style,
+            1.0 + 2.0 * edge.trust_level.abs()
+        );
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::models::Address;
+    use crate::services::TrustGraphEdge;
+
+    fn sample_graph() -> TrustGraph {
+        let alice = Address::agent("hub:8080", "alice");
+        let bob = Address::agent("hub:8080", "bob");
+        let mut nodes = HashMap::new();
+        nodes.insert("alice".to_string(), TrustGraphNode {
+            address: alice.clone(),
+            description: "Alice <ops>".to_string(),
+            depth: 0,
+            reputation: None,
+        });
+        nodes.insert("bob".to_string(), TrustGraphNode {
+            address: bob.clone(),
+            description: "Bob".to_string(),
+            depth: 1,
+            reputation: Some(-0.5),
+        });
+        TrustGraph {
+            center: alice.clone(),
+            nodes,
+            edges: vec![TrustGraphEdge {
+                from: alice,
+                to: bob,
+                trust_level: -0.5,
+                scope: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_graphml_export() {
+        let xml = graph_to_graphml(&sample_graph());
+        assert!(xml.contains("<node id=\"alice\">"));
+        assert!(xml.contains("Alice &lt;ops&gt;"));
+        assert!(xml.contains("source=\"alice\" target=\"bob\""));
+        assert!(xml.contains("<data key=\"kind\">distrust</data>"));
+        assert!(xml.contains("<data key=\"reputation\">-0.5</data>"));
+    }
+
+    #[test]
+    fn test_dot_export() {
+        let dot = graph_to_dot(&sample_graph());
+        assert!(dot.starts_with("digraph trust {"));
+        assert!(dot.contains("\"alice\" [label=\"Alice <ops>\", shape=doublecircle];"));
+        assert!(dot.contains("\"alice\" -> \"bob\""));
+        assert!(dot.contains("style=\"dashed\""));
+    }
+
+    #[test]
+    fn test_format_parse() {
+        assert_eq!("GraphML".parse::<GraphFormat>().unwrap(), GraphFormat::GraphMl);
+        assert_eq!("dot".parse::<GraphFormat>().unwrap(), GraphFormat::Dot);
+        assert!("svg".parse::<GraphFormat>().is_err());
+    }
+}
@@ -4,6 +4,12 @@
 
 mod path_finder;
 mod calculator;
+mod export;
+mod sybil;
+mod remote;
 
 pub use path_finder::*;
 pub use calculator::*;
+pub use export::*;
+pub use sybil::*;
+pub use remote::*;
@@ -118,10 +118,12 @@ mod tests {
             TrustPathHop {
                 agent: Address::agent("hub:8080", "agent-1"),
                 trust_level: 0.9,
+                hub: None,
             },
             TrustPathHop {
                 agent: Address::agent("hub:8080", "agent-2"),
                 trust_level: 0.8,
+                hub: None,
             },
         ];
 
@@ -138,6 +140,7 @@ mod tests {
             TrustPathHop {
                 agent: Address::agent("hub:8080", "agent-1"),
                 trust_level: -0.5,
+                hub: None,
             },
         ];
 
@@ -0,0 +1,514 @@
+//! Fetching trust stores of agents hosted on other hubs
+//!
+//! Remote agents are loaded from the hub named in their address
+//! (`GET /api/v1/agents/{uuid}`), verified against the agent's own signature
+//! and cached for a configurable time. Failed fetches are cached as well, so a
+//! hub that is down is not asked again for every query. Only hubs known to the
+//! [`HubDirectory`] are asked, at the URL registered for them.
+//!
+//! The first key seen for an agent is pinned. A later key is only accepted if
+//! the agent's key history links it to the pinned key through signed rotations.
+//! Trust entries whose timestamps the signature does not cover are dropped.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::RwLock;
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::{debug, warn};
+
+use crate::crypto::{canonical_json, verify_with_key};
+use crate::models::{Address, Agent, Domain, HubError, HubResult, KeyRotation, RotationSigner, Trust};
+
+/// Hubs remote agents may be fetched from
+pub trait HubDirectory: Send + Sync {
+    /// Registered base URL of the verified hub addressed as `server_port`
+    fn hub_url(&self, server_port: &str) -> Option<String>;
+}
+
+/// Maximum number of remote agents kept in the cache before it is reset
+const REMOTE_CACHE_CAPACITY: usize = 10_000;
+
+/// Configuration for cross-hub trust resolution
+#[derive(Debug, Clone)]
+pub struct RemoteTrustConfig {
+    /// Whether trust edges into other hubs are followed
+    pub enabled: bool,
+    /// Maximum number of remote fetches per trust query
+    pub max_fetches_per_query: usize,
+    /// How long fetched agents (and failed fetches) are cached
+    pub cache_ttl: Duration,
+    /// Timeout for a single fetch
+    pub timeout: Duration,
+}
+
+impl Default for RemoteTrustConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_fetches_per_query: 16,
+            cache_ttl: Duration::from_secs(300),
+            timeout: Duration::from_secs(3),
+        }
+    }
+}
+
+/// Keys pinned for a remote agent
+#[derive(Debug, Clone)]
+struct PinnedKey {
+    public_key: String,
+    recovery_key: Option<String>,
+}
+
+struct CachedAgent {
+    /// None if the fetch failed or the agent did not verify
+    agent: Option<Agent>,
+    fetched_at: Instant,
+}
+
+/// Fetches, verifies and caches agents from other hubs
+pub struct RemoteTrustFetcher {
+    config: RemoteTrustConfig,
+    http_client: reqwest::Client,
+    cache: RwLock<HashMap<String, CachedAgent>>,
+    /// Keys first seen per remote agent; later keys must descend from them
+    pinned_keys: RwLock<HashMap<String, PinnedKey>>,
+    /// Hubs that may be asked (None = no hub)
+    directory: Option<Arc<dyn HubDirectory>>,
+}
+
+impl RemoteTrustFetcher {
+    /// Create a new fetcher
+    pub fn new(config: RemoteTrustConfig) -> Self {
+        let http_client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            config,
+            http_client,
+            cache: RwLock::new(HashMap::new()),
+            pinned_keys: RwLock::new(HashMap::new()),
+            directory: None,
+        }
+    }
+
+    /// Fetch only from hubs in `directory`
+    pub fn with_directory(mut self, directory: Arc<dyn HubDirectory>) -> Self {
+        self.directory = Some(directory);
+        self
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &RemoteTrustConfig {
+        &self.config
+    }
+
+    fn cache_key(addr: &Address) -> String {
+        format!("{}/{}", addr.server_port, addr.entity)
+    }
+
+    /// Cached result for an agent address.
+    ///
+    /// Returns `Some(None)` for a cached failure and `None` if the address has
+    /// not been fetched or the entry has expired.
+    pub fn cached(&self, addr: &Address) -> Option<Option<Agent>> {
+        let cache = self.cache.read();
+        cache
+            .get(&Self::cache_key(addr))
+            .filter(|entry| entry.fetched_at.elapsed() < self.config.cache_ttl)
+            .map(|entry| entry.agent.clone())
+    }
+
+    /// Cache an agent (or a failed fetch) for an address
+    pub(crate) fn insert(&self, addr: &Address, agent: Option<Agent>) {
+        let mut cache = self.cache.write();
+        if cache.len() >= REMOTE_CACHE_CAPACITY {
+            cache.clear();
+        }
+        cache.insert(Self::cache_key(addr), CachedAgent {
+            agent,
+            fetched_at: Instant::now(),
+        });
+    }
+
+    /// Drop all cached agents
+    pub fn clear_cache(&self) {
+        self.cache.write().clear();
+    }
+
+    /// Fetch an agent from its hub, caching the outcome either way
+    pub async fn fetch(&self, addr: &Address) -> HubResult<Agent> {
+        let result = self.fetch_uncached(addr).await;
+        match result {
+            Ok(ref agent) => {
+                debug!("Fetched remote trust store of {}", addr);
+                self.insert(addr, Some(agent.clone()));
+            }
+            Err(ref e) => {
+                warn!("Failed to fetch remote agent {}: {}", addr, e);
+                self.insert(addr, None);
+            }
+        }
+        result
+    }
+
+    async fn fetch_uncached(&self, addr: &Address) -> HubResult<Agent> {
+        if addr.domain != Domain::Agent {
+            return Err(HubError::ValidationError(format!("{} is not an agent address", addr)));
+        }
+
+        let base_url = self.directory
+            .as_ref()
+            .and_then(|directory| directory.hub_url(&addr.server_port))
+            .ok_or_else(|| HubError::FederationError(format!(
+                "{} is not a registered and verified hub",
+                addr.server_port
+            )))?;
+        let url = format!(
+            "{}/api/v1/agents/{}",
+            base_url.trim_end_matches('/'),
+            urlencoding::encode(&addr.entity)
+        );
+
+        let response = self
+            .http_client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| HubError::NetworkError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(HubError::FederationError(format!(
+                "Hub {} returned error: {}",
+                addr.server_port,
+                response.status()
+            )));
+        }
+
+        #[derive(Deserialize)]
+        struct ApiResponse<T> {
+            data: Option<T>,
+        }
+
+        let api_response: ApiResponse<Agent> = response
+            .json()
+            .await
+            .map_err(|e| HubError::NetworkError(format!("Failed to parse response: {}", e)))?;
+
+        let mut agent = api_response.data.ok_or_else(|| {
+            HubError::FederationError(format!("Hub {} returned no agent", addr.server_port))
+        })?;
+
+        self.verify(addr, &mut agent)?;
+        Ok(agent)
+    }
+
+    /// Check that a fetched agent is the one addressed and signed by its own
+    /// key, dropping trust entries with timestamps the signature does not cover
+    pub fn verify(&self, addr: &Address, agent: &mut Agent) -> HubResult<()> {
+        if agent.uuid != addr.entity {
+            return Err(HubError::FederationError(format!(
+                "Hub {} returned agent {} for {}",
+                addr.server_port, agent.uuid, addr.entity
+            )));
+        }
+
+        let key = Self::cache_key(addr);
+        let pinned = self.pinned_keys.read().get(&key).cloned();
+        let rotated = match pinned {
+            Some(ref pinned) if pinned.public_key != agent.public_key => {
+                if !verify_key_chain(agent, &pinned.public_key, pinned.recovery_key.as_deref())? {
+                    return Err(HubError::InvalidPublicKey(format!(
+                        "public key of remote agent {} changed without a valid rotation",
+                        addr
+                    )));
+                }
+                true
+            }
+            _ => false,
+        };
+
+        let signed = verify_agent_signature(agent)?.ok_or_else(|| HubError::InvalidSignature {
+            entity_type: "remote_agent".to_string(),
+        })?;
+        let before = agent.trust.trusts.len();
+        agent.trust.trusts.retain(|trust| signed.covers(trust));
+        agent.trust.num_trusts = agent.trust.trusts.len() as u64;
+        if agent.trust.trusts.len() < before {
+            debug!(
+                "Dropped {} trust entries of {} with unsigned timestamps",
+                before - agent.trust.trusts.len(),
+                addr
+            );
+        }
+
+        if pinned.is_none() || rotated {
+            self.pinned_keys.write().insert(key, PinnedKey {
+                public_key: agent.public_key.clone(),
+                recovery_key: agent.recovery_key.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// `server:port` part of a hub URL, as used in entity addresses
+pub fn server_port_from_url(url: &str) -> String {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    without_scheme.split('/').next().unwrap_or_default().to_string()
+}
+
+/// Timestamps of trust entries covered by an agent's signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignedTimestamps {
+    /// `asserted_at` and the creation time entries without one fall back to
+    All,
+    /// `asserted_at` only (creation signatures)
+    AssertedAt,
+    /// None (trust updates signed before timestamps were part of the payload)
+    None,
+}
+
+impl SignedTimestamps {
+    /// Check if the time a trust entry is dated by is signed
+    pub fn covers(&self, trust: &Trust) -> bool {
+        match self {
+            SignedTimestamps::All => true,
+            SignedTimestamps::AssertedAt => trust.asserted_at.is_some(),
+            SignedTimestamps::None => false,
+        }
+    }
+}
+
+/// Verify an agent's signature over its current state.
+///
+/// Returns the timestamps the signature covers, or None if it does not verify.
+pub fn verify_agent_signature(agent: &Agent) -> HubResult<Option<SignedTimestamps>> {
+    let mut payloads = Vec::new();
+    if agent.version <= 1 && agent.trust_signature.is_none() {
+        payloads.push((agent.creation_payload(), SignedTimestamps::AssertedAt));
+        // Agents created without a client-chosen UUID signed an empty one
+        let mut payload = agent.creation_payload();
+        payload["uuid"] = Value::String(String::new());
+        payloads.push((payload, SignedTimestamps::AssertedAt));
+    } else {
+        payloads.push((agent.trust_state_payload(), SignedTimestamps::All));
+        payloads.push((legacy_trust_state_payload(agent), SignedTimestamps::None));
+    }
+
+    for (payload, signed) in payloads {
+        let data = canonical_json(&payload);
+        if verify_with_key(&agent.public_key, data.as_bytes(), agent.state_signature())? {
+            return Ok(Some(signed));
+        }
+    }
+    Ok(None)
+}
+
+/// Trust state payload signed before it covered timestamps
+fn legacy_trust_state_payload(agent: &Agent) -> Value {
+    let mut payload = agent.trust_state_payload();
+    if let Some(map) = payload.as_object_mut() {
+        map.remove("created_at");
+    }
+    if let Some(trusts) = payload["trusts"].as_array_mut() {
+        for trust in trusts.iter_mut().filter_map(Value::as_object_mut) {
+            trust.remove("asserted_at");
+        }
+    }
+    payload
+}
+
+/// Verify a rotation's continuity proof against the keys it replaces
+pub fn verify_key_rotation(
+    agent_uuid: &str,
+    previous_key: &str,
+    recovery_key: Option<&str>,
+    rotation: &KeyRotation,
+) -> HubResult<bool> {
+    let signer = match rotation.signed_by {
+        RotationSigner::Previous => previous_key,
+        RotationSigner::Recovery => match recovery_key {
+            Some(recovery_key) => recovery_key,
+            None => return Ok(false),
+        },
+    };
+    let data = canonical_json(&rotation.statement_payload(agent_uuid, previous_key));
+    verify_with_key(signer, data.as_bytes(), &rotation.signature)
+}
+
+/// Check that an agent's current keys descend from an earlier key (and the
+/// recovery key registered with it) through signed rotations
+pub fn verify_key_chain(agent: &Agent, public_key: &str, recovery_key: Option<&str>) -> HubResult<bool> {
+    let start = match agent.key_history.iter().position(|r| r.public_key == public_key) {
+        Some(start) => start,
+        None => return Ok(false),
+    };
+
+    let mut current = public_key.to_string();
+    let mut recovery = recovery_key.map(str::to_string);
+    for record in &agent.key_history[start..] {
+        if record.public_key != current
+            || !verify_key_rotation(&agent.uuid, &current, recovery.as_deref(), &record.rotation)?
+        {
+            return Ok(false);
+        }
+        current = record.rotation.new_public_key.clone();
+        if let Some(ref new_recovery) = record.rotation.new_recovery_key {
+            recovery = Some(new_recovery.clone());
+        }
+    }
+    Ok(current == agent.public_key && recovery == agent.recovery_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{sign, KeyPair};
+
+    fn signed_agent(keypair: &KeyPair) -> Agent {
+        let mut agent = Agent::new("remote-1", keypair.public_key_base64());
+        agent.add_trust(Address::agent("other:8080", "bob"), 0.6);
+        let signature = sign(keypair, canonical_json(&agent.creation_payload()).as_bytes());
+        agent.with_signature(signature)
+    }
+
+    #[test]
+    fn test_verify_remote_agent() {
+        let fetcher = RemoteTrustFetcher::new(RemoteTrustConfig::default());
+        let keypair = KeyPair::generate();
+        let addr = Address::agent("remote:8080", "remote-1");
+        let agent = signed_agent(&keypair);
+
+        assert!(fetcher.verify(&addr, &mut agent.clone()).is_ok());
+        assert!(fetcher.verify(&Address::agent("remote:8080", "other"), &mut agent.clone()).is_err());
+
+        let mut tampered = agent.clone();
+        tampered.trust.trusts[0].trust = 1.0;
+        assert!(matches!(
+            fetcher.verify(&addr, &mut tampered),
+            Err(HubError::InvalidSignature { .. })
+        ));
+
+        // A different key for a pinned agent is rejected even if self-consistent
+        let mut replaced = signed_agent(&KeyPair::generate());
+        assert!(matches!(
+            fetcher.verify(&addr, &mut replaced),
+            Err(HubError::InvalidPublicKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_unsigned_timestamps_are_dropped() {
+        let fetcher = RemoteTrustFetcher::new(RemoteTrustConfig::default());
+        let keypair = KeyPair::generate();
+        let addr = Address::agent("remote:8080", "remote-1");
+
+        // An entry without asserted_at is dated by the unsigned creation time
+        let mut agent = Agent::new("remote-1", keypair.public_key_base64());
+        agent.add_trust(Address::agent("other:8080", "bob"), 0.6);
+        agent.add_trust(Address::agent("other:8080", "carol"), 0.6);
+        agent.trust.trusts[1].asserted_at = None;
+        let signature = sign(&keypair, canonical_json(&agent.creation_payload()).as_bytes());
+        let mut agent = agent.with_signature(signature);
+        fetcher.verify(&addr, &mut agent).unwrap();
+        assert_eq!(agent.trust.num_trusts, 1);
+        assert_eq!(agent.trust.trusts[0].agent.entity, "bob");
+
+        // Backdating a signed entry breaks the signature
+        let mut updated = agent.clone();
+        updated.version = 2;
+        updated.trust_signature = Some(sign(&keypair, canonical_json(&updated.trust_state_payload()).as_bytes()));
+        fetcher.verify(&addr, &mut updated.clone()).unwrap();
+        updated.trust.trusts[0].asserted_at = Some(chrono::Utc::now() + chrono::Duration::days(1));
+        assert!(fetcher.verify(&addr, &mut updated).is_err());
+
+        // Trust updates signed without timestamps keep verifying, minus their entries
+        let mut legacy = agent.clone();
+        legacy.version = 2;
+        legacy.trust_signature = Some(sign(&keypair, canonical_json(&legacy_trust_state_payload(&legacy)).as_bytes()));
+        fetcher.verify(&addr, &mut legacy).unwrap();
+        assert_eq!(legacy.trust.num_trusts, 0);
+    }
+
+    fn rotated(agent: &Agent, signer: &KeyPair, new_key: &KeyPair) -> Agent {
+        let mut rotation = KeyRotation {
+            new_public_key: new_key.public_key_base64(),
+            new_recovery_key: None,
+            rotated_at: chrono::Utc::now(),
+            version: agent.version + 1,
+            signed_by: RotationSigner::Previous,
+            signature: String::new(),
+        };
+        let statement = canonical_json(&rotation.statement_payload(&agent.uuid, &agent.public_key));
+        rotation.signature = sign(signer, statement.as_bytes());
+
+        let mut agent = agent.clone();
+        agent.apply_rotation(rotation);
+        agent.trust_signature = Some(sign(new_key, canonical_json(&agent.trust_state_payload()).as_bytes()));
+        agent
+    }
+
+    #[test]
+    fn test_verify_rotated_remote_agent() {
+        let fetcher = RemoteTrustFetcher::new(RemoteTrustConfig::default());
+        let old_key = KeyPair::generate();
+        let new_key = KeyPair::generate();
+        let addr = Address::agent("remote:8080", "remote-1");
+        let agent = signed_agent(&old_key);
+        fetcher.verify(&addr, &mut agent.clone()).unwrap();
+
+        // A rotation not signed by the pinned key is rejected
+        let forger = KeyPair::generate();
+        assert!(matches!(
+            fetcher.verify(&addr, &mut rotated(&agent, &forger, &forger)),
+            Err(HubError::InvalidPublicKey(_))
+        ));
+
+        let mut agent = rotated(&agent, &old_key, &new_key);
+        fetcher.verify(&addr, &mut agent).unwrap();
+        assert_eq!(agent.trust.num_trusts, 1);
+
+        // The new key is pinned from now on
+        let newer_key = KeyPair::generate();
+        fetcher.verify(&addr, &mut rotated(&agent, &new_key, &newer_key)).unwrap();
+    }
+
+    #[test]
+    fn test_server_port_from_url() {
+        assert_eq!(server_port_from_url("https://hub.example.com:8443/api"), "hub.example.com:8443");
+        assert_eq!(server_port_from_url("http://localhost:8080"), "localhost:8080");
+        assert_eq!(server_port_from_url("hub:9000"), "hub:9000");
+    }
+
+    struct Directory(Vec<String>);
+
+    impl HubDirectory for Directory {
+        fn hub_url(&self, server_port: &str) -> Option<String> {
+            self.0.iter().find(|url| server_port_from_url(url) == server_port).cloned()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_fetch_is_cached() {
+        let fetcher = RemoteTrustFetcher::new(RemoteTrustConfig {
+            timeout: Duration::from_millis(200),
+            ..Default::default()
+        })
+        .with_directory(Arc::new(Directory(vec!["http://127.0.0.1:1".to_string()])));
+        let addr = Address::agent("127.0.0.1:1", "nobody");
+
+        assert!(fetcher.cached(&addr).is_none());
+        assert!(matches!(fetcher.fetch(&addr).await, Err(HubError::NetworkError(_))));
+        assert!(matches!(fetcher.cached(&addr), Some(None)));
+
+        // Hubs missing from the directory are never contacted
+        let unknown = Address::agent("169.254.169.254:80", "nobody");
+        assert!(matches!(fetcher.fetch(&unknown).await, Err(HubError::FederationError(_))));
+    }
+}
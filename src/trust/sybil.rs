@@ -0,0 +1,341 @@
+//! Sybil-resistance analysis over agent trust edges
+//!
+//! Uses a directed variant of SybilRank: trust mass starts at established
+//! "seed" agents and is propagated along positive trust edges for
+//! `ceil(log2(n))` iterations. Because fake agents receive few edges from the
+//! established region, little mass reaches them before propagation stops.
+//! Degree-normalized mass is turned into a suspicion score, and suspected
+//! agents are grouped into clusters annotated with their conductance.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use crate::models::{Agent, Domain};
+
+/// Configuration for the Sybil analysis
+#[derive(Debug, Clone)]
+pub struct SybilConfig {
+    /// Agents trusted as honest; if empty, agents older than `min_seed_age` are used
+    pub seeds: Vec<String>,
+    /// Minimum age for an agent to count as established
+    pub min_seed_age: Duration,
+    /// Suspicion at or above which an agent counts as suspected
+    pub suspicion_threshold: f32,
+    /// How much a path is down-weighted per suspected intermediate agent (0 = disabled)
+    pub penalty: f32,
+}
+
+impl Default for SybilConfig {
+    fn default() -> Self {
+        Self {
+            seeds: Vec::new(),
+            min_seed_age: Duration::days(30),
+            suspicion_threshold: 0.8,
+            penalty: 0.5,
+        }
+    }
+}
+
+/// A group of suspected agents connected by trust edges
+#[derive(Debug, Clone, Serialize)]
+pub struct SuspectedCluster {
+    /// Agent UUIDs in the cluster
+    pub members: Vec<String>,
+    /// Trust edges between members
+    pub internal_edges: usize,
+    /// Trust edges from non-suspected agents into the cluster
+    pub incoming_edges: usize,
+    /// Share of possible internal edges that exist (0.0 to 1.0)
+    pub density: f32,
+    /// Cut weight divided by the smaller side's volume; low values mean weakly connected
+    pub conductance: f32,
+    /// Mean suspicion of the members
+    pub mean_suspicion: f32,
+}
+
+/// Result of a Sybil analysis run
+#[derive(Debug, Clone, Serialize)]
+pub struct SybilReport {
+    /// Seed agents the propagation started from
+    pub seeds: Vec<String>,
+    /// Suspicion per agent UUID (0.0 = established, 1.0 = unreachable from seeds)
+    pub scores: HashMap<String, f32>,
+    /// Suspected clusters, most weakly connected first
+    pub clusters: Vec<SuspectedCluster>,
+    /// Number of agents analyzed
+    pub agent_count: usize,
+    /// When the analysis ran
+    pub computed_at: DateTime<Utc>,
+}
+
+impl SybilReport {
+    /// Suspicion score for an agent (0.0 if no analysis was possible).
+    ///
+    /// Local agents missing from the report were created after the run and
+    /// count as unreachable from the seeds until the next one. Agents on
+    /// other hubs are not analyzed and score 0.0.
+    pub fn suspicion(&self, uuid: &str, local: bool) -> f32 {
+        match self.scores.get(uuid) {
+            Some(&score) => score,
+            None if local && !self.seeds.is_empty() => 1.0,
+            None => 0.0,
+        }
+    }
+}
+
+/// Run the analysis over all local agents
+pub fn analyze_sybils(agents: &[Agent], config: &SybilConfig, now: DateTime<Utc>) -> SybilReport {
+    let index: HashMap<&str, usize> = agents
+        .iter()
+        .enumerate()
+        .map(|(i, a)| (a.uuid.as_str(), i))
+        .collect();
+    let n = agents.len();
+
+    // Positive trust edges between local agents, strongest edge per pair
+    let mut out_edges: Vec<HashMap<usize, f32>> = vec![HashMap::new(); n];
+    for (i, agent) in agents.iter().enumerate() {
+        for trust in &agent.trust.trusts {
+            if trust.trust <= 0.0 || trust.agent.domain != Domain::Agent {
+                continue;
+            }
+            if let Some(&j) = index.get(trust.agent.entity.as_str()) {
+                if i != j {
+                    let weight = out_edges[i].entry(j).or_insert(0.0);
+                    *weight = weight.max(trust.trust);
+                }
+            }
+        }
+    }
+
+    let seeds: Vec<usize> = if config.seeds.is_empty() {
+        agents
+            .iter()
+            .enumerate()
+            .filter(|(_, a)| now - a.created_at >= config.min_seed_age)
+            .map(|(i, _)| i)
+            .collect()
+    } else {
+        config.seeds.iter().filter_map(|s| index.get(s.as_str()).copied()).collect()
+    };
+
+    let mut report = SybilReport {
+        seeds: seeds.iter().map(|&i| agents[i].uuid.clone()).collect(),
+        scores: HashMap::new(),
+        clusters: Vec::new(),
+        agent_count: n,
+        computed_at: now,
+    };
+
+    // Without established agents there is nothing to measure against
+    if seeds.is_empty() {
+        return report;
+    }
+
+    let mut in_weight = vec![0.0f32; n];
+    for edges in &out_edges {
+        for (&j, &w) in edges {
+            in_weight[j] += w;
+        }
+    }
+
+    // Early-terminated propagation from the seeds
+    let mut mass = vec![0.0f32; n];
+    for &s in &seeds {
+        mass[s] = n as f32 / seeds.len() as f32;
+    }
+    let iterations = (n as f32).log2().ceil().max(1.0) as usize;
+    for _ in 0..iterations {
+        let mut next = vec![0.0f32; n];
+        for (i, edges) in out_edges.iter().enumerate() {
+            if mass[i] == 0.0 {
+                continue;
+            }
+            let total: f32 = edges.values().sum();
+            if total == 0.0 {
+                next[i] += mass[i];
+                continue;
+            }
+            for (&j, &w) in edges {
+                next[j] += mass[i] * w / total;
+            }
+        }
+        mass = next;
+    }
+
+    let normalized: Vec<f32> = (0..n).map(|i| mass[i] / in_weight[i].max(1.0)).collect();
+    let mut reached: Vec<f32> = normalized.iter().copied().filter(|&v| v > 0.0).collect();
+    reached.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let reference = reached.get(reached.len() / 2).copied().unwrap_or(1.0);
+
+    let seed_set: HashSet<usize> = seeds.iter().copied().collect();
+    let suspicion: Vec<f32> = (0..n)
+        .map(|i| {
+            if seed_set.contains(&i) {
+                0.0
+            } else {
+                1.0 - (normalized[i] / reference).min(1.0)
+            }
+        })
+        .collect();
+
+    report.clusters = find_clusters(agents, &out_edges, &suspicion, config.suspicion_threshold);
+    report.scores = agents
+        .iter()
+        .zip(suspicion)
+        .map(|(a, s)| (a.uuid.clone(), s))
+        .collect();
+    report
+}
+
+/// Group suspected agents into connected clusters
+fn find_clusters(
+    agents: &[Agent],
+    out_edges: &[HashMap<usize, f32>],
+    suspicion: &[f32],
+    threshold: f32,
+) -> Vec<SuspectedCluster> {
+    let n = suspicion.len();
+    let suspected: Vec<bool> = suspicion.iter().map(|&s| s >= threshold).collect();
+
+    // Undirected weighted adjacency for component search and volumes
+    let mut neighbors: Vec<HashMap<usize, f32>> = vec![HashMap::new(); n];
+    for (i, edges) in out_edges.iter().enumerate() {
+        for (&j, &w) in edges {
+            *neighbors[i].entry(j).or_insert(0.0) += w;
+            *neighbors[j].entry(i).or_insert(0.0) += w;
+        }
+    }
+    let volume = |i: usize| -> f32 { neighbors[i].values().sum() };
+    let total_volume: f32 = (0..n).map(volume).sum();
+
+    let mut seen = vec![false; n];
+    let mut clusters = Vec::new();
+
+    for start in 0..n {
+        if !suspected[start] || seen[start] {
+            continue;
+        }
+        let mut members = Vec::new();
+        let mut queue = VecDeque::from([start]);
+        seen[start] = true;
+        while let Some(i) = queue.pop_front() {
+            members.push(i);
+            for &j in neighbors[i].keys() {
+                if suspected[j] && !seen[j] {
+                    seen[j] = true;
+                    queue.push_back(j);
+                }
+            }
+        }
+        if members.len() < 2 {
+            continue;
+        }
+
+        let member_set: HashSet<usize> = members.iter().copied().collect();
+        let mut internal_edges = 0;
+        let mut incoming_edges = 0;
+        for (i, edges) in out_edges.iter().enumerate() {
+            for &j in edges.keys() {
+                match (member_set.contains(&i), member_set.contains(&j)) {
+                    (true, true) => internal_edges += 1,
+                    (false, true) if !suspected[i] => incoming_edges += 1,
+                    _ => {}
+                }
+            }
+        }
+
+        let cut: f32 = members
+            .iter()
+            .flat_map(|&i| neighbors[i].iter().filter(|(j, _)| !member_set.contains(j)).map(|(_, w)| *w))
+            .sum();
+        let cluster_volume: f32 = members.iter().map(|&i| volume(i)).sum();
+        let smaller = cluster_volume.min(total_volume - cluster_volume);
+        let conductance = if smaller > 0.0 { (cut / smaller).min(1.0) } else { 0.0 };
+
+        let size = members.len();
+        clusters.push(SuspectedCluster {
+            density: internal_edges as f32 / (size * (size - 1)) as f32,
+            mean_suspicion: members.iter().map(|&i| suspicion[i]).sum::<f32>() / size as f32,
+            members: members.iter().map(|&i| agents[i].uuid.clone()).collect(),
+            internal_edges,
+            incoming_edges,
+            conductance,
+        });
+    }
+
+    clusters.sort_by(|a, b| a.conductance.partial_cmp(&b.conductance).unwrap_or(std::cmp::Ordering::Equal));
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Address;
+
+    fn agent(uuid: &str, age_days: i64) -> Agent {
+        let mut agent = Agent::new(uuid, "key");
+        agent.created_at = Utc::now() - Duration::days(age_days);
+        agent
+    }
+
+    fn trust(from: &mut Agent, to: &str, level: f32) {
+        from.add_trust(Address::agent("hub:8080", to), level);
+    }
+
+    /// Honest ring of established agents plus a fresh, fully meshed Sybil
+    /// cluster reachable through a single attack edge
+    fn sample_agents() -> Vec<Agent> {
+        let mut agents: Vec<Agent> = (0..6).map(|i| agent(&format!("h{}", i), 100)).collect();
+        for (i, honest) in agents.iter_mut().enumerate() {
+            for d in 1..=2 {
+                trust(honest, &format!("h{}", (i + d) % 6), 0.8);
+            }
+        }
+        trust(&mut agents[0], "s0", 0.3);
+
+        let mut sybils: Vec<Agent> = (0..5).map(|i| agent(&format!("s{}", i), 1)).collect();
+        for (i, sybil) in sybils.iter_mut().enumerate() {
+            for j in (0..5).filter(|&j| j != i) {
+                trust(sybil, &format!("s{}", j), 1.0);
+            }
+            trust(sybil, "h3", 1.0);
+        }
+        agents.extend(sybils);
+        agents
+    }
+
+    #[test]
+    fn test_sybil_cluster_is_suspected() {
+        let agents = sample_agents();
+        let report = analyze_sybils(&agents, &SybilConfig::default(), Utc::now());
+
+        assert_eq!(report.seeds.len(), 6);
+        for i in 0..6 {
+            assert_eq!(report.suspicion(&format!("h{}", i), true), 0.0);
+        }
+        for i in 1..5 {
+            assert!(report.suspicion(&format!("s{}", i), true) >= 0.8, "s{} not suspected", i);
+        }
+        // Agents created after the run are suspected until the next one
+        assert_eq!(report.suspicion("newcomer", true), 1.0);
+        assert_eq!(report.suspicion("newcomer", false), 0.0);
+
+        let cluster = &report.clusters[0];
+        assert!(cluster.members.contains(&"s1".to_string()));
+        assert!(cluster.density > 0.9);
+        assert!(cluster.conductance < 0.5);
+    }
+
+    #[test]
+    fn test_no_seeds_no_scores() {
+        let agents = vec![agent("a", 1), agent("b", 1)];
+        let report = analyze_sybils(&agents, &SybilConfig::default(), Utc::now());
+        assert!(report.seeds.is_empty());
+        assert!(report.scores.is_empty());
+        assert_eq!(report.suspicion("a", true), 0.0);
+        assert_eq!(report.suspicion("newcomer", true), 0.0);
+    }
+}
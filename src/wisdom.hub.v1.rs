@@ -22,6 +22,12 @@ pub struct Agent {
     pub created_at: ::core::option::Option<::prost_types::Timestamp>,
     #[prost(message, optional, tag = "9")]
     pub updated_at: ::core::option::Option<::prost_types::Timestamp>,
+    /// Empty if none is registered
+    #[prost(string, tag = "10")]
+    pub recovery_key: ::prost::alloc::string::String,
+    /// Signature over the trust state; empty while the agent is as created
+    #[prost(string, tag = "11")]
+    pub trust_signature: ::prost::alloc::string::String,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TrustStore {
@@ -36,6 +42,12 @@ pub struct Trust {
     /// -1.0 to 1.0
     #[prost(float, tag = "2")]
     pub trust_level: f32,
+    /// Optional context, e.g. "programming:rust" or "tag:<address>"
+    #[prost(string, tag = "3")]
+    pub scope: ::prost::alloc::string::String,
+    /// When the trust was asserted
+    #[prost(message, optional, tag = "4")]
+    pub asserted_at: ::core::option::Option<::prost_types::Timestamp>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Fragment {
@@ -66,6 +78,50 @@ pub struct Fragment {
     pub created_at: ::core::option::Option<::prost_types::Timestamp>,
     #[prost(message, optional, tag = "11")]
     pub updated_at: ::core::option::Option<::prost_types::Timestamp>,
+    /// Aggregate verification votes
+    #[prost(message, optional, tag = "12")]
+    pub votes: ::core::option::Option<VoteSummary>,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct VoteSummary {
+    #[prost(int32, tag = "1")]
+    pub votes_count: i32,
+    #[prost(int32, tag = "2")]
+    pub verifications: i32,
+    #[prost(int32, tag = "3")]
+    pub contestations: i32,
+    #[prost(float, tag = "4")]
+    pub verification_weight: f32,
+    #[prost(float, tag = "5")]
+    pub contestation_weight: f32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Vote {
+    #[prost(string, tag = "1")]
+    pub uuid: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub fragment_address: ::prost::alloc::string::String,
+    /// Agent address
+    #[prost(string, tag = "3")]
+    pub voter_address: ::prost::alloc::string::String,
+    /// VERIFY or CONTEST
+    #[prost(string, tag = "4")]
+    pub kind: ::prost::alloc::string::String,
+    /// 0.0 to 1.0
+    #[prost(float, tag = "5")]
+    pub weight: f32,
+    #[prost(string, tag = "6")]
+    pub comment: ::prost::alloc::string::String,
+    #[prost(int32, tag = "7")]
+    pub version: i32,
+    #[prost(string, tag = "8")]
+    pub signature: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "9")]
+    pub when: ::core::option::Option<::prost_types::Timestamp>,
+    #[prost(message, optional, tag = "10")]
+    pub created_at: ::core::option::Option<::prost_types::Timestamp>,
+    #[prost(message, optional, tag = "11")]
+    pub updated_at: ::core::option::Option<::prost_types::Timestamp>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Relation {
@@ -158,6 +214,9 @@ pub struct CreateAgentRequest {
     /// For validation (not stored)
     #[prost(string, tag = "6")]
     pub created_by: ::prost::alloc::string::String,
+    /// Optional key that may sign a key rotation
+    #[prost(string, tag = "7")]
+    pub recovery_key: ::prost::alloc::string::String,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GetAgentRequest {
@@ -178,6 +237,38 @@ pub struct ListAgentsResponse {
     #[prost(string, tag = "2")]
     pub next_cursor: ::prost::alloc::string::String,
 }
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TrustUpdate {
+    /// Trusted agent address
+    #[prost(string, tag = "1")]
+    pub agent_address: ::prost::alloc::string::String,
+    /// -1.0 to 1.0, ignored when remove is set
+    #[prost(float, tag = "2")]
+    pub trust_level: f32,
+    /// Remove the trust entry instead of setting it
+    #[prost(bool, tag = "3")]
+    pub remove: bool,
+    /// Optional context, empty = general trust
+    #[prost(string, tag = "4")]
+    pub scope: ::prost::alloc::string::String,
+    /// Optional, defaults to the time the hub applies it
+    #[prost(message, optional, tag = "5")]
+    pub asserted_at: ::core::option::Option<::prost_types::Timestamp>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateTrustRequest {
+    /// Agent whose trust store is updated
+    #[prost(string, tag = "1")]
+    pub agent_uuid: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "2")]
+    pub updates: ::prost::alloc::vec::Vec<TrustUpdate>,
+    /// Current version + 1
+    #[prost(int32, tag = "3")]
+    pub version: i32,
+    /// Signature by the agent's key
+    #[prost(string, tag = "4")]
+    pub signature: ::prost::alloc::string::String,
+}
 /// Fragments
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CreateFragmentRequest {
@@ -203,6 +294,41 @@ pub struct GetFragmentRequest {
     pub uuid: ::prost::alloc::string::String,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FragmentValidityRequest {
+    #[prost(string, tag = "1")]
+    pub uuid: ::prost::alloc::string::String,
+    /// Optional, defaults to the fragment's creator
+    #[prost(string, tag = "2")]
+    pub viewer_address: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ValidityReport {
+    /// valid, conditional, contested, broken
+    #[prost(string, tag = "1")]
+    pub validity: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "2")]
+    pub issues: ::prost::alloc::vec::Vec<ValidityIssue>,
+    #[prost(float, tag = "3")]
+    pub confidence_weighted_score: f32,
+    #[prost(int32, tag = "4")]
+    pub fragments_analyzed: i32,
+    #[prost(int32, tag = "5")]
+    pub relations_analyzed: i32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ValidityIssue {
+    #[prost(string, tag = "1")]
+    pub fragment_uuid: ::prost::alloc::string::String,
+    /// missing_reference, contested_premise, low_confidence,
+    #[prost(string, tag = "2")]
+    pub issue_type: ::prost::alloc::string::String,
+    /// unverified_source, circular_dependency
+    #[prost(string, tag = "3")]
+    pub description: ::prost::alloc::string::String,
+    #[prost(float, tag = "4")]
+    pub severity: f32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SearchFragmentsRequest {
     #[prost(string, tag = "1")]
     pub query: ::prost::alloc::string::String,
@@ -235,6 +361,33 @@ pub struct GetRelationRequest {
     #[prost(string, tag = "1")]
     pub uuid: ::prost::alloc::string::String,
 }
+/// Votes
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CastVoteRequest {
+    #[prost(string, tag = "1")]
+    pub uuid: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub fragment_address: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub voter_address: ::prost::alloc::string::String,
+    /// VERIFY or CONTEST
+    #[prost(string, tag = "4")]
+    pub kind: ::prost::alloc::string::String,
+    /// Default 1.0
+    #[prost(float, optional, tag = "5")]
+    pub weight: ::core::option::Option<f32>,
+    #[prost(string, tag = "6")]
+    pub comment: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "7")]
+    pub when: ::core::option::Option<::prost_types::Timestamp>,
+    #[prost(string, tag = "8")]
+    pub signature: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetVoteRequest {
+    #[prost(string, tag = "1")]
+    pub uuid: ::prost::alloc::string::String,
+}
 /// Tags
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CreateTagRequest {
@@ -309,6 +462,15 @@ pub struct TrustPathRequest {
     /// Entity address
     #[prost(string, tag = "2")]
     pub to_address: ::prost::alloc::string::String,
+    /// Optional override, 0 = hub default
+    #[prost(int32, tag = "3")]
+    pub max_depth: i32,
+    /// Optional override, 0 = hub default
+    #[prost(float, tag = "4")]
+    pub damping_factor: f32,
+    /// Optional contexts, empty = general trust only
+    #[prost(string, repeated, tag = "5")]
+    pub scopes: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TrustPath {
@@ -329,6 +491,9 @@ pub struct TrustPathHop {
     pub agent_address: ::prost::alloc::string::String,
     #[prost(float, tag = "2")]
     pub trust_level: f32,
+    /// Hub the asserting trust store was read from
+    #[prost(string, tag = "3")]
+    pub hub: ::prost::alloc::string::String,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TrustScoreRequest {
@@ -338,6 +503,24 @@ pub struct TrustScoreRequest {
     /// Viewer perspective (agent address)
     #[prost(string, tag = "2")]
     pub viewer_address: ::prost::alloc::string::String,
+    /// Optional override, 0 = hub default
+    #[prost(int32, tag = "3")]
+    pub max_depth: i32,
+    /// Optional override, 0 = hub default
+    #[prost(float, tag = "4")]
+    pub damping_factor: f32,
+    /// Optional contexts; fragments add their tags
+    #[prost(string, repeated, tag = "5")]
+    pub scopes: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Include a TrustExplanation
+    #[prost(bool, tag = "6")]
+    pub explain: bool,
+    /// Paths to explain, 0 = 3
+    #[prost(int32, tag = "7")]
+    pub top_k: i32,
+    /// Fold TRUST relations about the entity into the score
+    #[prost(bool, tag = "8")]
+    pub include_endorsements: bool,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TrustScore {
@@ -351,6 +534,78 @@ pub struct TrustScore {
     pub path_count: i32,
     #[prost(message, optional, tag = "5")]
     pub best_path: ::core::option::Option<TrustPath>,
+    /// Set when requested
+    #[prost(message, optional, tag = "6")]
+    pub explanation: ::core::option::Option<TrustExplanation>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TrustExplanation {
+    #[prost(string, tag = "1")]
+    pub formula: ::prost::alloc::string::String,
+    /// Fragments are explained via their creator
+    #[prost(string, tag = "2")]
+    pub target_address: ::prost::alloc::string::String,
+    #[prost(float, tag = "3")]
+    pub damping_factor: f32,
+    #[prost(float, tag = "4")]
+    pub min_trust_threshold: f32,
+    #[prost(int32, tag = "5")]
+    pub max_depth: i32,
+    #[prost(message, repeated, tag = "6")]
+    pub paths: ::prost::alloc::vec::Vec<ExplainedPath>,
+    #[prost(message, repeated, tag = "7")]
+    pub endorsements: ::prost::alloc::vec::Vec<ExplainedEndorsement>,
+    #[prost(message, repeated, tag = "8")]
+    pub cut_edges: ::prost::alloc::vec::Vec<CutTrustEdge>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExplainedPath {
+    #[prost(message, repeated, tag = "1")]
+    pub hops: ::prost::alloc::vec::Vec<ExplainedHop>,
+    #[prost(float, tag = "2")]
+    pub effective_trust: f32,
+    /// Relation UUID if the path ends in an endorsement
+    #[prost(string, tag = "3")]
+    pub endorsement: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExplainedHop {
+    #[prost(string, tag = "1")]
+    pub agent_address: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub hub: ::prost::alloc::string::String,
+    #[prost(float, tag = "3")]
+    pub trust_level: f32,
+    #[prost(float, tag = "4")]
+    pub damping: f32,
+    #[prost(float, tag = "5")]
+    pub sybil_factor: f32,
+    #[prost(float, tag = "6")]
+    pub cumulative_trust: f32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExplainedEndorsement {
+    #[prost(string, tag = "1")]
+    pub relation_uuid: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub endorser_address: ::prost::alloc::string::String,
+    #[prost(float, tag = "3")]
+    pub confidence: f32,
+    #[prost(float, optional, tag = "4")]
+    pub endorser_trust: ::core::option::Option<f32>,
+    #[prost(float, tag = "5")]
+    pub contribution: f32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CutTrustEdge {
+    #[prost(string, tag = "1")]
+    pub from_address: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub to_address: ::prost::alloc::string::String,
+    #[prost(float, tag = "3")]
+    pub trust_level: f32,
+    #[prost(float, tag = "4")]
+    pub cumulative_trust: f32,
 }
 /// Discovery
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -629,6 +884,27 @@ pub mod hub_service_client {
                 .insert(GrpcMethod::new("wisdom.hub.v1.HubService", "ListAgents"));
             self.inner.unary(req, path, codec).await
         }
+        pub async fn update_agent_trust(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UpdateTrustRequest>,
+        ) -> std::result::Result<tonic::Response<super::Agent>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/wisdom.hub.v1.HubService/UpdateAgentTrust",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("wisdom.hub.v1.HubService", "UpdateAgentTrust"));
+            self.inner.unary(req, path, codec).await
+        }
         /// Fragments
         pub async fn create_fragment(
             &mut self,
@@ -672,6 +948,29 @@ pub mod hub_service_client {
                 .insert(GrpcMethod::new("wisdom.hub.v1.HubService", "GetFragment"));
             self.inner.unary(req, path, codec).await
         }
+        pub async fn get_fragment_validity(
+            &mut self,
+            request: impl tonic::IntoRequest<super::FragmentValidityRequest>,
+        ) -> std::result::Result<tonic::Response<super::ValidityReport>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/wisdom.hub.v1.HubService/GetFragmentValidity",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("wisdom.hub.v1.HubService", "GetFragmentValidity"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
         pub async fn search_fragments(
             &mut self,
             request: impl tonic::IntoRequest<super::SearchFragmentsRequest>,
@@ -739,6 +1038,49 @@ pub mod hub_service_client {
                 .insert(GrpcMethod::new("wisdom.hub.v1.HubService", "GetRelation"));
             self.inner.unary(req, path, codec).await
         }
+        /// Votes
+        pub async fn cast_vote(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CastVoteRequest>,
+        ) -> std::result::Result<tonic::Response<super::Vote>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/wisdom.hub.v1.HubService/CastVote",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("wisdom.hub.v1.HubService", "CastVote"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_vote(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetVoteRequest>,
+        ) -> std::result::Result<tonic::Response<super::Vote>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/wisdom.hub.v1.HubService/GetVote",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("wisdom.hub.v1.HubService", "GetVote"));
+            self.inner.unary(req, path, codec).await
+        }
         /// Tags
         pub async fn create_tag(
             &mut self,
@@ -1020,6 +1362,10 @@ pub mod hub_service_server {
             tonic::Response<super::ListAgentsResponse>,
             tonic::Status,
         >;
+        async fn update_agent_trust(
+            &self,
+            request: tonic::Request<super::UpdateTrustRequest>,
+        ) -> std::result::Result<tonic::Response<super::Agent>, tonic::Status>;
         /// Fragments
         async fn create_fragment(
             &self,
@@ -1029,6 +1375,10 @@ pub mod hub_service_server {
             &self,
             request: tonic::Request<super::GetFragmentRequest>,
         ) -> std::result::Result<tonic::Response<super::Fragment>, tonic::Status>;
+        async fn get_fragment_validity(
+            &self,
+            request: tonic::Request<super::FragmentValidityRequest>,
+        ) -> std::result::Result<tonic::Response<super::ValidityReport>, tonic::Status>;
         /// Server streaming response type for the SearchFragments method.
         type SearchFragmentsStream: tonic::codegen::tokio_stream::Stream<
                 Item = std::result::Result<super::Fragment, tonic::Status>,
@@ -1051,6 +1401,15 @@ pub mod hub_service_server {
             &self,
             request: tonic::Request<super::GetRelationRequest>,
         ) -> std::result::Result<tonic::Response<super::Relation>, tonic::Status>;
+        /// Votes
+        async fn cast_vote(
+            &self,
+            request: tonic::Request<super::CastVoteRequest>,
+        ) -> std::result::Result<tonic::Response<super::Vote>, tonic::Status>;
+        async fn get_vote(
+            &self,
+            request: tonic::Request<super::GetVoteRequest>,
+        ) -> std::result::Result<tonic::Response<super::Vote>, tonic::Status>;
         /// Tags
         async fn create_tag(
             &self,
@@ -1324,6 +1683,51 @@ pub mod hub_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/wisdom.hub.v1.HubService/UpdateAgentTrust" => {
+                    #[allow(non_camel_case_types)]
+                    struct UpdateAgentTrustSvc<T: HubService>(pub Arc<T>);
+                    impl<
+                        T: HubService,
+                    > tonic::server::UnaryService<super::UpdateTrustRequest>
+                    for UpdateAgentTrustSvc<T> {
+                        type Response = super::Agent;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UpdateTrustRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as HubService>::update_agent_trust(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = UpdateAgentTrustSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/wisdom.hub.v1.HubService/CreateFragment" => {
                     #[allow(non_camel_case_types)]
                     struct CreateFragmentSvc<T: HubService>(pub Arc<T>);
@@ -1414,6 +1818,52 @@ pub mod hub_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/wisdom.hub.v1.HubService/GetFragmentValidity" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetFragmentValiditySvc<T: HubService>(pub Arc<T>);
+                    impl<
+                        T: HubService,
+                    > tonic::server::UnaryService<super::FragmentValidityRequest>
+                    for GetFragmentValiditySvc<T> {
+                        type Response = super::ValidityReport;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::FragmentValidityRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as HubService>::get_fragment_validity(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetFragmentValiditySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/wisdom.hub.v1.HubService/SearchFragments" => {
                     #[allow(non_camel_case_types)]
                     struct SearchFragmentsSvc<T: HubService>(pub Arc<T>);
@@ -1551,6 +2001,96 @@ pub mod hub_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/wisdom.hub.v1.HubService/CastVote" => {
+                    #[allow(non_camel_case_types)]
+                    struct CastVoteSvc<T: HubService>(pub Arc<T>);
+                    impl<
+                        T: HubService,
+                    > tonic::server::UnaryService<super::CastVoteRequest>
+                    for CastVoteSvc<T> {
+                        type Response = super::Vote;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CastVoteRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as HubService>::cast_vote(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = CastVoteSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/wisdom.hub.v1.HubService/GetVote" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetVoteSvc<T: HubService>(pub Arc<T>);
+                    impl<
+                        T: HubService,
+                    > tonic::server::UnaryService<super::GetVoteRequest>
+                    for GetVoteSvc<T> {
+                        type Response = super::Vote;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetVoteRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as HubService>::get_vote(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetVoteSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/wisdom.hub.v1.HubService/CreateTag" => {
                     #[allow(non_camel_case_types)]
                     struct CreateTagSvc<T: HubService>(pub Arc<T>);